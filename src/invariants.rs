@@ -0,0 +1,145 @@
+//! Consistency checks of the registry's storage, for tests to run after
+//! mutation-heavy scenarios so that indexes and counters drifting from the
+//! records they summarize surface where they happen.
+
+use crate::{
+    count_of, keys_of, owner_page, owners_of, stats_of, version_of, DataKey, KeyExtra, INDEX_PAGE,
+};
+use soroban_auth::Identifier;
+use soroban_sdk::{symbol, Bytes, BytesN, Env, IntoVal, Map};
+
+/// Checks the storage of the registry at `contract_id`, panicking with a
+/// description of the first inconsistency found.
+pub fn check_invariants(e: &Env, contract_id: &BytesN<32>) {
+    e.invoke_contract::<()>(contract_id, &symbol!("invariants"), ().into_val(e));
+}
+
+/// Checks the storage of the current contract, walking the key index, the
+/// index of every owner found there, the counters and the records of every
+/// key. Owners are only known through the key index, so an index left behind
+/// by an identifier that no longer owns any key isn't found.
+pub(crate) fn check(e: &Env) {
+    let mut owned: Map<Identifier, u32> = Map::new(e);
+    let mut active = 0u64;
+    for (slot, key) in keys_of(e).iter().enumerate() {
+        let key = key.unwrap();
+        let recorded: Option<u32> = e.data().get(DataKey::Slot(key.clone())).map(Result::unwrap);
+        if recorded != Some(slot as u32) {
+            panic!("key {:?} in slot {} records slot {:?}", key, slot, recorded)
+        }
+
+        match e.data().get::<_, Identifier>(DataKey::Owner(key.clone())) {
+            Some(owner) => {
+                let owner = owner.unwrap();
+                check_indexed(e, &key, &owner);
+                let count = owned.get(owner.clone()).unwrap_or(Ok(0)).unwrap();
+                owned.set(owner, count + 1);
+                active += 1;
+            }
+            None => check_unowned(e, &key),
+        }
+        check_history(e, &key);
+    }
+
+    for entry in owned.iter() {
+        let (owner, count) = entry.unwrap();
+        if count_of(e, &owner) != count {
+            panic!(
+                "{:?} owns {} keys but counts {}",
+                owner,
+                count,
+                count_of(e, &owner)
+            )
+        }
+        check_pages(e, &owner, count);
+    }
+    if stats_of(e).active != active {
+        panic!(
+            "{} keys are owned but stats count {}",
+            active,
+            stats_of(e).active
+        )
+    }
+}
+
+/// Checks that `key` sits in the index of `owner` at the slot it records.
+fn check_indexed(e: &Env, key: &Bytes, owner: &Identifier) {
+    let slot: u32 = e
+        .data()
+        .get(DataKey::OwnerSlot(key.clone()))
+        .unwrap_or_else(|| panic!("owned key {:?} has no owner slot", key))
+        .unwrap();
+    if slot >= count_of(e, owner) {
+        panic!(
+            "key {:?} is in slot {} past the count of its owner",
+            key, slot
+        )
+    }
+    let indexed = owner_page(e, owner, slot / INDEX_PAGE)
+        .get(slot % INDEX_PAGE)
+        .map(Result::unwrap);
+    if indexed.as_ref() != Some(key) {
+        panic!(
+            "slot {} of the owner of {:?} holds {:?}",
+            slot, key, indexed
+        )
+    }
+}
+
+/// Checks that the pages of the index of `owner` hold `count` keys and that
+/// no page past them is left behind. Each of the `count` keys owned by `owner`
+/// was found in its own slot already, so the pages hold nothing else.
+fn check_pages(e: &Env, owner: &Identifier, count: u32) {
+    let pages = count.div_ceil(INDEX_PAGE);
+    for page in 0..=pages {
+        let keys = owner_page(e, owner, page);
+        let expected = count.saturating_sub(page * INDEX_PAGE).min(INDEX_PAGE);
+        if keys.len() != expected {
+            panic!(
+                "page {} of {:?} holds {} keys instead of {}",
+                page,
+                owner,
+                keys.len(),
+                expected
+            )
+        }
+    }
+}
+
+/// Checks that the unowned `key` holds no value, index slot or approval.
+fn check_unowned(e: &Env, key: &Bytes) {
+    if e.data().has(DataKey::Value(key.clone())) {
+        panic!("unowned key {:?} holds a value", key)
+    }
+    if e.data().has(DataKey::OwnerSlot(key.clone())) {
+        panic!("unowned key {:?} has an owner slot", key)
+    }
+    if e.data().has(DataKey::Grants(key.clone()))
+        || e.data().has(DataKey::Spender(key.clone()))
+        || e.data().has(DataKey::Approvals(key.clone()))
+        || e.data().has(DataKey::Shares(key.clone()))
+        || e.data().has(KeyExtra::Handoff(key.clone()))
+        || e.data().has(KeyExtra::Swap(key.clone()))
+    {
+        panic!("unowned key {:?} has approvals", key)
+    }
+}
+
+/// Checks that the owner history of `key` goes up to its current version at
+/// most, oldest first.
+fn check_history(e: &Env, key: &Bytes) {
+    let mut last = 0;
+    for owner in owners_of(e, key).iter() {
+        let version = owner.unwrap().version;
+        if version <= last || version > version_of(e, key) {
+            panic!(
+                "owner history of {:?} has version {} after {} at version {}",
+                key,
+                version,
+                last,
+                version_of(e, key)
+            )
+        }
+        last = version;
+    }
+}
@@ -1,61 +1,5434 @@
 #![no_std]
 use soroban_auth::{verify, Identifier, Signature};
-use soroban_sdk::{contractimpl, symbol, Address, Bytes, Env};
+use soroban_sdk::{
+    contracterror, contractimpl, contracttype,
+    events::Topics,
+    log, panic_error,
+    serde::{Deserialize, Serialize},
+    symbol, Address, BigInt, Bytes, BytesN, Env, IntoVal, Map, RawVal, Status, Symbol, Vec,
+};
+use topics::{
+    TOPIC_ACCEPTED, TOPIC_ATTEST, TOPIC_DELETE, TOPIC_DISPUTE, TOPIC_ENDED, TOPIC_GUARDIAN,
+    TOPIC_HELD, TOPIC_HIST_CLEAR, TOPIC_HOOK_FAIL, TOPIC_NONCE, TOPIC_PROPOSED, TOPIC_REBOUND,
+    TOPIC_RECOVER, TOPIC_RECOVERED, TOPIC_RELEASED, TOPIC_RENOUNCED, TOPIC_RESOLVED, TOPIC_SET,
+    TOPIC_TRANSFER, TOPIC_VERBOSITY, TOPIC_VETOED,
+};
 
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    KeyNotFound = 1,
+    EmptyList = 2,
+    ReservedValue = 3,
+    NoAdmin = 4,
+    AlreadyInitialized = 5,
+    NotAuthorized = 6,
+    Paused = 7,
+    NoCommitment = 8,
+    CommitExists = 9,
+    CommitExpired = 10,
+    CommitPending = 11,
+    PayloadMismatch = 12,
+    NoToken = 13,
+    OfferExists = 14,
+    NoOffer = 15,
+    NoHistory = 16,
+    RegistrationClosed = 17,
+    NotAllowed = 18,
+    InvalidPhase = 19,
+    ValueLocked = 20,
+    ReservedKey = 21,
+    Burned = 22,
+    AlreadyExists = 23,
+    SignatureRequired = 24,
+    Sealed = 25,
+    Disputed = 26,
+    NoDispute = 27,
+    InvalidOutcome = 28,
+    InvalidKey = 29,
+    RequestReused = 30,
+    RequestPending = 31,
+    NoRequest = 32,
+    TooManySubs = 33,
+    NotContract = 34,
+    CoOwned = 35,
+    NoMajority = 36,
+    InvalidShares = 37,
+    StaleVersion = 38,
+    NoRecovery = 39,
+    RecoveryPending = 40,
+    NoNamespace = 41,
+    InvalidPolicy = 42,
+    InvalidSplit = 43,
+    ContractClaim = 44,
+    ValueTooLarge = 45,
+    BlobTooLarge = 46,
+    LengthMismatch = 47,
+    NoValue = 48,
+    InvalidMode = 49,
+    NoOp = 50,
+}
+
+/// Everything a client needs to render a key, returned by `get_meta` in a
+/// single call.
+///
+/// `modified` is the ledger sequence of the last write to the key's value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct KeyMeta {
+    pub value: Identifier,
+    pub owner: Identifier,
+    pub modified: u32,
+    pub frozen: bool,
+    pub private: bool,
+}
+
+/// Ownership record of a key, returned by `get_entry`.
+///
+/// `expires` is `0` while no expiry system is configured, `modified` is the
+/// ledger sequence of the last write, `locked` reports whether writes are
+/// currently blocked and `version` counts the writes made to the key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Entry {
+    pub owner: Identifier,
+    pub expires: u32,
+    pub modified: u32,
+    pub locked: bool,
+    pub version: u32,
+}
+
+/// The format a key is stored in, returned by `get_stored`. `V0` holds the
+/// value of a key stored under its raw bytes by the previous layout, with the
+/// value doubling as the owner, and `migrate` upgrades it to `V1`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum StoredEntry {
+    V0(Identifier),
+    V1(Entry),
+}
+
+/// A text record of a key only returned by `get_gated` to its owner and
+/// `reader`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct GatedText {
+    pub text: Bytes,
+    pub reader: Identifier,
+}
+
+/// An ownership attestation recorded by `attest`: `owner` owned `key` at the
+/// ledger sequence `ledger`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Attestation {
+    pub key: Bytes,
+    pub owner: Identifier,
+    pub ledger: u32,
+}
+
+/// What off-chain tools need to build a signature of a signer, returned by
+/// `sig_state`: the nonce the next signature has to be bound to, whether the
+/// signer was blocked with `block` and the current ledger sequence.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SignerState {
+    pub nonce: BigInt,
+    pub blocked: bool,
+    pub ledger: u32,
+}
+
+/// Restrictions on writes to and reads of a key, returned by `status`.
+/// `lock_until` is the deadline `lock_for` locks the key until, to be read
+/// according to `time_mode`, or `0` if it isn't locked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct KeyStatus {
+    pub frozen: bool,
+    pub lock_until: u64,
+    pub private: bool,
+    pub sig_only: bool,
+}
+
+/// Keys of all records in contract storage. User keys only ever appear
+/// wrapped in a variant, so no key can collide with an internal record.
+///
+/// This replaces the previous layout, where values were stored under the raw
+/// key bytes and other records under `(Symbol, ..)` tuples. Contracts can't be
+/// upgraded in place, so registries on the old layout have to be redeployed.
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    Value(Bytes),
+    Owner(Bytes),
+    Modified(Bytes),
+    Version(Bytes),
+    Frozen(Bytes),
+    Private(Bytes),
+    List(Bytes),
+    History(Bytes),
+    Keys,
+    Admin,
+    Allowed(Identifier),
+    Commit(BytesN<32>),
+    Offer(OfferKey),
+    Nonce(Identifier),
+    Lock(Bytes),
+    Burned(Bytes),
+    Grants(Bytes),
+    Text(Bytes),
+    Slot(Bytes),
+    SigOnly(Bytes),
+    StateHash,
+    Dispute(Bytes),
+    Request(BytesN<32>),
+    Subs(Bytes),
+    Hook(Bytes),
+    WriteOnce(Bytes),
+    BlobType(Bytes),
+    Shares(Bytes),
+    SoloUpd(Bytes),
+    Approvals(Bytes),
+    ClientVer(Bytes),
+    Recovery(Bytes),
+    Namespace(Bytes),
+    OwnerCount(Identifier),
+    Parent(Identifier),
+    Split(Bytes),
+    Guardian(Bytes),
+    Hot,
+    Blob(Bytes),
+    Stats,
+    Gated(Bytes),
+    Attest(BytesN<32>),
+    KeyPage(u32),
+    OwnerPage(PageKey),
+    OwnerSlot(Bytes),
+    Claimer(Bytes),
+    Linked(Bytes),
+    Topic(Bytes),
+    Uses(Bytes),
+    Spender(Bytes),
+}
+
+/// Keys of the per-key and per-identifier records that no longer fit in
+/// `DataKey`, which is at the 50 variant limit of contract types. No variant
+/// shares its name with one of `DataKey` or `Setting`, so none of them can
+/// collide.
+#[derive(Clone)]
+#[contracttype]
+pub enum KeyExtra {
+    Rental(Bytes),
+    Wildcard(Identifier),
+    Memo(Bytes),
+    Tombstone(Bytes),
+    Guardians(Bytes),
+    Redeemed(Bytes),
+    Handoff(Bytes),
+    Owners(Bytes),
+    Swap(Bytes),
+    Paused(Bytes),
+}
+
+/// Keys of the registry-wide settings in contract storage, kept apart from
+/// `DataKey` as contract types can't have more than 50 variants. No variant
+/// shares its name with one of `DataKey`, so the two can't collide.
+#[derive(Clone)]
+#[contracttype]
+pub enum Setting {
+    Config,
+    Prefixes,
+    Proposed,
+    Blocked(Identifier),
+    Reserved(Identifier),
+    Journals,
+    Journal(u32),
+    Restricted(Identifier),
+    Barred(Identifier),
+}
+
+/// Registry counters returned by `get_stats`: keys claimed and removed since
+/// the contract was deployed, keys currently claimed, values written and
+/// offers currently escrowed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct Stats {
+    pub claims: u64,
+    pub removals: u64,
+    pub active: u64,
+    pub writes: u64,
+    pub offers: u64,
+}
+
+/// Registry-wide settings, stored together in a single entry. `max_key`,
+/// `max_val`, `max_dlg` and `max_owned` are 0 when there is no limit, `token`
+/// is unset until the admin sets one with `set_token` and `stage` is `live`
+/// until `wind_down` moves it to `winding` and `terminate` to `ended`. The
+/// other fields are what their setters, named in the docs of `Config`, last
+/// set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Settings {
+    pub paused: bool,
+    pub verbosity: Symbol,
+    pub phase: Symbol,
+    pub max_key: u32,
+    pub req_ttl: u32,
+    pub rsv_ttl: u32,
+    pub tomb_ttl: u32,
+    pub swap_ttl: u32,
+    pub token: MaybeToken,
+    pub profile: Symbol,
+    pub stage: Symbol,
+    pub fallback: MaybeId,
+    pub ns_gated: bool,
+    pub strict: bool,
+    pub max_val: u32,
+    pub max_blob: u32,
+    pub time_mode: Symbol,
+    pub noop_guard: bool,
+    pub append: bool,
+    pub quorum: MaybeQuorum,
+    pub dflt_owner: MaybeId,
+    pub max_dlg: u32,
+    pub max_owned: u32,
+    pub claim_sig: MaybeId,
+    pub min_bal: MaybeMin,
+    pub recv_guard: bool,
+}
+
+/// Operational parameters returned by `get_config`. `admin` is unset before
+/// `initialize` and after `renounce`, `events` is false at the `off`
+/// verbosity only, `keys` is the number of keys currently claimed and the
+/// other fields are those of `Settings`: `fallback` is set with `set_dflt`,
+/// `ns_gated` with `gate_ns`, `strict` with `set_strict`, `max_val` with
+/// `set_maxval`, `max_blob` with `blob_limit`, `time_mode` with `set_tmode`,
+/// `noop_guard` and `recv_guard` with the functions of the same name,
+/// `append` with `set_append`, `quorum` with `set_quorum`, `dflt_owner` with
+/// `set_downer`, `max_dlg` with `set_maxdlg`, `max_owned` with `set_maxown`,
+/// `claim_sig` with `set_csig` and `min_bal` with `set_minbal`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Config {
+    pub init: bool,
+    pub paused: bool,
+    pub admin: MaybeId,
+    pub events: bool,
+    pub verbosity: Symbol,
+    pub keys: u64,
+    pub phase: Symbol,
+    pub max_key: u32,
+    pub req_ttl: u32,
+    pub rsv_ttl: u32,
+    pub tomb_ttl: u32,
+    pub swap_ttl: u32,
+    pub token: MaybeToken,
+    pub profile: Symbol,
+    pub stage: Symbol,
+    pub fallback: MaybeId,
+    pub ns_gated: bool,
+    pub strict: bool,
+    pub max_val: u32,
+    pub max_blob: u32,
+    pub time_mode: Symbol,
+    pub noop_guard: bool,
+    pub append: bool,
+    pub quorum: MaybeQuorum,
+    pub dflt_owner: MaybeId,
+    pub max_dlg: u32,
+    pub max_owned: u32,
+    pub claim_sig: MaybeId,
+    pub min_bal: MaybeMin,
+    pub recv_guard: bool,
+}
+
+/// A recipient of a split value and its share of `SPLIT_DENOMINATOR`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Part {
+    pub to: Identifier,
+    pub weight: u32,
+}
+
+/// An identifier that may be unset. Fields of contract types can't be
+/// `Option`s, which this SDK can't convert to XDR, so optional settings and
+/// parts of `Config` use these instead, converting from and to `Option`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum MaybeId {
+    None,
+    Some(Identifier),
+}
+
+impl From<Option<Identifier>> for MaybeId {
+    fn from(value: Option<Identifier>) -> Self {
+        match value {
+            Some(value) => MaybeId::Some(value),
+            None => MaybeId::None,
+        }
+    }
+}
+
+impl From<MaybeId> for Option<Identifier> {
+    fn from(value: MaybeId) -> Self {
+        match value {
+            MaybeId::Some(value) => Some(value),
+            MaybeId::None => None,
+        }
+    }
+}
+
+/// A token contract id that may be unset. See `MaybeId`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum MaybeToken {
+    None,
+    Some(BytesN<32>),
+}
+
+impl From<Option<BytesN<32>>> for MaybeToken {
+    fn from(value: Option<BytesN<32>>) -> Self {
+        match value {
+            Some(value) => MaybeToken::Some(value),
+            None => MaybeToken::None,
+        }
+    }
+}
+
+impl From<MaybeToken> for Option<BytesN<32>> {
+    fn from(value: MaybeToken) -> Self {
+        match value {
+            MaybeToken::Some(value) => Some(value),
+            MaybeToken::None => None,
+        }
+    }
+}
+
+/// A quorum that may be unset. See `MaybeId`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum MaybeQuorum {
+    None,
+    Some(Quorum),
+}
+
+impl From<Option<Quorum>> for MaybeQuorum {
+    fn from(value: Option<Quorum>) -> Self {
+        match value {
+            Some(value) => MaybeQuorum::Some(value),
+            None => MaybeQuorum::None,
+        }
+    }
+}
+
+impl From<MaybeQuorum> for Option<Quorum> {
+    fn from(value: MaybeQuorum) -> Self {
+        match value {
+            MaybeQuorum::Some(value) => Some(value),
+            MaybeQuorum::None => None,
+        }
+    }
+}
+
+/// The balance of `token` claimers must hold under `set_minbal`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct MinBal {
+    pub token: BytesN<32>,
+    pub amount: BigInt,
+}
+
+/// A minimum balance that may be unset. See `MaybeId`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum MaybeMin {
+    None,
+    Some(MinBal),
+}
+
+impl From<Option<MinBal>> for MaybeMin {
+    fn from(value: Option<MinBal>) -> Self {
+        match value {
+            Some(value) => MaybeMin::Some(value),
+            None => MaybeMin::None,
+        }
+    }
+}
+
+impl From<MaybeMin> for Option<MinBal> {
+    fn from(value: MaybeMin) -> Self {
+        match value {
+            MaybeMin::Some(value) => Some(value),
+            MaybeMin::None => None,
+        }
+    }
+}
+
+/// Identifiers that can act together when at least `threshold` of them sign:
+/// for the admin with `set_quorum`, or as the guardians of a key with
+/// `set_guards`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Quorum {
+    pub signers: Vec<Identifier>,
+    pub threshold: u32,
+}
+
+/// A co-owner of a key and the weight of their vote, set with `set_owners`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Share {
+    pub owner: Identifier,
+    pub weight: u32,
+}
+
+/// A change to a co-owned key that co-owners approve by its hash, the sha256
+/// of its serialization, and anyone can `execute` once approved.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Action {
+    SetValue(Identifier),
+    Transfer(Identifier),
+}
+
+/// What a grantee may do with a key on the owner's behalf. Owners implicitly
+/// hold every permission. `Renew` is reserved for key renewals.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Permission {
+    Update,
+    Metadata,
+    Renew,
+}
+
+/// Identifies page `page` of the index of the keys owned by `owner`.
+#[derive(Clone)]
+#[contracttype]
+pub struct PageKey {
+    pub owner: Identifier,
+    pub page: u32,
+}
+
+/// A rental of a key to `renter` from `start` until `until`, both read
+/// according to `time_mode`, with the `price` the renter paid held in escrow
+/// until it ends.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Rental {
+    pub renter: Identifier,
+    pub start: u64,
+    pub until: u64,
+    pub price: BigInt,
+}
+
+/// A transfer of a key proposed with `prop_xfer`, which `to` can accept with
+/// `acpt_xfer` until `expiry`, read according to `time_mode`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Handoff {
+    pub to: Identifier,
+    pub expiry: u64,
+}
+
+/// A swap proposed with `prop_swap` by `proposer` of the key it is stored
+/// with for `their_key` of `with`, which `with` can accept with `acpt_swap`
+/// until `expiry`, read according to `time_mode`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Swap {
+    pub proposer: Identifier,
+    pub their_key: Bytes,
+    pub with: Identifier,
+    pub expiry: u64,
+}
+
+/// The owner of a key at `version`, which it reached at ledger `ledger`, as
+/// recorded for `owner_at`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct OwnerAt {
+    pub owner: Identifier,
+    pub ledger: u32,
+    pub version: u32,
+}
+
+/// A key and the identifier an `AdminOp::Reassign` hands it to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct KeyOwner {
+    pub key: Bytes,
+    pub owner: Identifier,
+}
+
+/// An admin operation recorded in a journal with `jrnl_add`, applied by
+/// `jrnl_apply` as the admin function it is named after would: `Phase` as
+/// `set_phase`, `Reassign` as `resolve` upholding a dispute in favour of
+/// the identifier, `Dismiss` as `resolve` dismissing it, `Token` as
+/// `set_token`, `Allow` and `Disallow` as `allow` and `disallow`, and `Block`
+/// and `Unblock` as `block_val` and `unblock`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum AdminOp {
+    Phase(Symbol),
+    Reassign(KeyOwner),
+    Dismiss(Bytes),
+    Token(BytesN<32>),
+    Allow(Identifier),
+    Disallow(Identifier),
+    Block(Identifier),
+    Unblock(Identifier),
+}
+
+/// Identifies the offer `bidder` made for `key`.
+#[derive(Clone)]
+#[contracttype]
+pub struct OfferKey {
+    pub key: Bytes,
+    pub bidder: Identifier,
+}
+
+/// A key with its owner and value, as exported by `export` and imported by
+/// `ingest` when state moves to a redeployed contract.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Record {
+    pub key: Bytes,
+    pub owner: Identifier,
+    pub value: Identifier,
+}
+
+/// A pending recovery of a key, started with `recover` by the admin or, if
+/// `guardian` is set, by the guardian of the key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Recovery {
+    pub new_owner: Identifier,
+    pub deadline: u64,
+    pub guardian: bool,
+}
+
+/// A namespace created with `create_ns` and the policy its owner set for claims
+/// of keys inside it: `open`, `owner` for claims by the owner only, or `fee`
+/// for open claims paying `fee` to the owner in the registry token.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Namespace {
+    pub owner: Identifier,
+    pub policy: Symbol,
+    pub fee: BigInt,
+}
+
+/// Version of the state layout exported by `export`, bumped whenever the
+/// shape of `Record` changes.
+pub const STATE_VERSION: u32 = 1;
+
+/// Number of ledgers after a commitment lands during which it can be revealed.
+/// Older commitments can be removed by anyone with `sweep_cmt`.
+pub const REVEAL_WINDOW: u32 = 100;
+
+/// Number of ledgers a `claim_once` request id is kept before `prune_req` can
+/// remove it, unless the admin configures another value with `set_reqttl`.
+pub const REQUEST_TTL: u32 = 1000;
+
+/// Number of ledgers a nonce reserved with `rsv_nonce` stays usable, unless
+/// the admin configures another value with `set_rsvttl`.
+pub const RESERVE_TTL: u32 = 1000;
+
+/// Number of ledgers the tombstone of a removed key is kept before
+/// `sweep_tomb` can remove it, unless the admin configures another value with
+/// `set_tmbttl`.
+pub const TOMBSTONE_TTL: u32 = 1000;
+
+/// Maximum number of contracts that can `subscribe` to a single key, bounding
+/// the cost of notifying them on every write.
+pub const MAX_SUBSCRIBERS: u32 = 5;
+
+/// Number of ledgers a dispute raised with `dispute` blocks mutations of its
+/// key. Disputes the admin doesn't resolve in time lapse on their own.
+pub const DISPUTE_WINDOW: u32 = 1000;
+
+/// Number of ledgers the owner of a key has to `veto` an admin recovery of it
+/// before the admin can `finalize` it.
+pub const RECOVERY_DELAY: u32 = 1000;
+
+/// Number of ledgers the owner of a key has to `veto` a recovery of it started
+/// by its guardian before anyone can `finalize` it.
+pub const GUARDIAN_DELAY: u32 = 30_000;
+
+/// Maximum size of a `set_sealed` blob, unless the admin configures another
+/// value with `blob_limit`.
+pub const MAX_BLOB: u32 = 256;
+
+/// Number of ledgers a swap proposed with `prop_swap` can be accepted for,
+/// unless the admin configures another value with `set_swpttl`.
+pub const SWAP_TTL: u32 = 1000;
+
+/// Number of the latest versions of a key whose owner `owner_at` can tell.
+pub const OWNER_HISTORY: u32 = 4;
+
+/// Maximum size of a memo written with `memo_val`.
+pub const MAX_MEMO: u32 = 64;
+
+/// Number of keys per page of the key index and of the per-owner indices, so
+/// that no stored vector grows with the registry.
+pub const INDEX_PAGE: u32 = 8;
+
+/// Most slots of the key index `find_keys` scans in a call.
+pub const MAX_SCAN: u32 = 50;
+
+/// Sum the weights of the parts of a split value set with `set_split` must add
+/// up to.
+pub const SPLIT_DENOMINATOR: u32 = 10_000;
+
+/// First byte of the keys storing namespaced entries, which can't be claimed
+/// outside of their namespace.
+const NS_MARKER: u8 = 0xff;
+
+/// Maximum length of a namespace name.
+pub const MAX_NS_LEN: u32 = 32;
+
+/// Why a check refused a write, either with a contract error or, for the
+/// checks that predate the error codes, with a message.
+enum Refusal {
+    Error(Error),
+    Message(&'static str),
+}
+
+impl From<Error> for Refusal {
+    fn from(error: Error) -> Self {
+        Refusal::Error(error)
+    }
+}
+
+/// Returns the value of `result`, trapping with its refusal if there is one.
+fn enforce<T>(e: &Env, result: Result<T, Refusal>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(Refusal::Error(error)) => panic_error!(e, error),
+        Err(Refusal::Message(message)) => panic!("{}", message),
+    }
+}
+
+/// Maps the invoker to its identifier. Invokers are always accounts or
+/// contracts; `Identifier::Ed25519` owners can't invoke and authorize through
+/// `use_sig` instead, where the signer identifier of any variant is compared
+/// with the owner as a whole.
+fn invoker_id(e: &Env) -> Identifier {
+    match e.invoker() {
+        Address::Account(id) => Identifier::Account(id),
+        Address::Contract(id) => Identifier::Contract(id),
+    }
+}
+
+/// Returns the identifier allowed to change `key`, falling back to
+/// `unclaimed` for unclaimed keys.
+fn owner_of(e: &Env, key: &Bytes) -> Identifier {
+    e.data()
+        .get(DataKey::Owner(key.clone()))
+        .unwrap_or_else(|| Ok(unclaimed(e)))
+        .unwrap()
+}
+
+/// Returns the sentinel unclaimed keys are owned by, the current contract's
+/// own identifier, which no one can sign for or set as a value.
+fn unclaimed(e: &Env) -> Identifier {
+    Identifier::Contract(e.current_contract())
+}
+
+fn is_frozen(e: &Env, key: &Bytes) -> bool {
+    e.data()
+        .get(DataKey::Frozen(key.clone()))
+        .unwrap_or(Ok(false))
+        .unwrap()
+}
+
+/// Returns the time mode set with `set_tmode`, `ledger` by default.
+fn time_mode_of(e: &Env) -> Symbol {
+    settings_of(e).time_mode
+}
+
+/// Returns the current time deadlines are compared against: the ledger
+/// sequence, or the ledger timestamp in the `time` mode set with `set_tmode`.
+fn now(e: &Env) -> u64 {
+    if time_mode_of(e) == symbol!("time") {
+        e.ledger().timestamp()
+    } else {
+        e.ledger().sequence() as u64
+    }
+}
+
+/// Returns whether `now` has reached `deadline`. Every expiry and timelock is
+/// compared through here, so switching the time mode moves all of them.
+fn deadline_passed(e: &Env, deadline: u64) -> bool {
+    now(e) >= deadline
+}
+
+/// Returns whether `last`, the last moment something is still valid at, has
+/// gone by.
+fn expired(e: &Env, last: u64) -> bool {
+    deadline_passed(e, last.saturating_add(1))
+}
+
+/// Returns whether `key` is under a `lock_for` lock that hasn't run out yet.
+fn is_locked(e: &Env, key: &Bytes) -> bool {
+    match e.data().get::<_, u64>(DataKey::Lock(key.clone())) {
+        Some(until) => !deadline_passed(e, until.unwrap()),
+        None => false,
+    }
+}
+
+fn is_private(e: &Env, key: &Bytes) -> bool {
+    e.data()
+        .get(DataKey::Private(key.clone()))
+        .unwrap_or(Ok(false))
+        .unwrap()
+}
+
+fn modified_of(e: &Env, key: &Bytes) -> u32 {
+    e.data()
+        .get(DataKey::Modified(key.clone()))
+        .unwrap_or(Ok(0))
+        .unwrap()
+}
+
+fn version_of(e: &Env, key: &Bytes) -> u32 {
+    e.data()
+        .get(DataKey::Version(key.clone()))
+        .unwrap_or(Ok(0))
+        .unwrap()
+}
+
+/// Returns the commitment to the entry of `key` returned by `entry_hash`.
+fn hash_entry(e: &Env, key: &Bytes) -> BytesN<32> {
+    let entry = (
+        e.current_contract(),
+        key.clone(),
+        owner_of(e, key),
+        version_of(e, key),
+        0_u32,
+    );
+    e.compute_hash_sha256(&entry.serialize(e))
+}
+
+/// Returns the admin, trapping with `NoAdmin` before `initialize` and after
+/// `renounce`.
+fn admin_of(e: &Env) -> Identifier {
+    let admin = e
+        .data()
+        .get(DataKey::Admin)
+        .unwrap_or_else(|| panic_error!(e, Error::NoAdmin))
+        .unwrap();
+    if same_id(&admin, &Identifier::Contract(e.current_contract())) {
+        panic_error!(e, Error::NoAdmin)
+    }
+    admin
+}
+
+/// Returns whether `id` is the admin, without trapping before `initialize`.
+fn is_admin(e: &Env, id: &Identifier) -> bool {
+    e.data()
+        .get(DataKey::Admin)
+        .is_some_and(|admin| same_id(&admin.unwrap(), id))
+}
+
+fn require_admin(e: &Env) {
+    check_live(e);
+    if !same_id(&admin_of(e), &invoker_id(e)) {
+        panic_error!(e, Error::NotAuthorized)
+    }
+}
+
+/// Traps with `Sealed` once the registry was ended with `terminate`.
+fn check_live(e: &Env) {
+    if settings_of(e).stage == symbol!("ended") {
+        panic_error!(e, Error::Sealed)
+    }
+}
+
+/// Traps with `NotAuthorized` unless `sigs` are signatures over `(nonce,)`
+/// for `name` by at least the threshold of distinct signers of the quorum set
+/// with `set_quorum`. Every signature consumes the nonce of its signer.
+fn require_quorum(e: &Env, sigs: &Vec<Signature>, name: Symbol) {
+    check_live(e);
+    let quorum = Option::from(settings_of(e).quorum)
+        .unwrap_or_else(|| panic_error!(e, Error::NotAuthorized));
+    require_signers(e, &quorum, sigs, name, Vec::new(e));
+}
+
+/// Traps with `NotAuthorized` unless `sigs` are signatures over `args`
+/// prefixed with their signer's nonce for `name`, by at least the threshold
+/// of distinct signers of `quorum`. Every signature consumes the nonce of its
+/// signer.
+fn require_signers(
+    e: &Env,
+    quorum: &Quorum,
+    sigs: &Vec<Signature>,
+    name: Symbol,
+    args: Vec<RawVal>,
+) {
+    let mut signed: Vec<Identifier> = Vec::new(e);
+    for sig in sigs.iter() {
+        let sig = sig.unwrap();
+        let signer = sig.identifier(e);
+        if !contains_id(&quorum.signers, &signer) || contains_id(&signed, &signer) {
+            panic_error!(e, Error::NotAuthorized)
+        }
+        verify_nonce(e, &sig, name, args.clone());
+        signed.push_back(signer);
+    }
+    if signed.len() < quorum.threshold {
+        panic_error!(e, Error::NotAuthorized)
+    }
+}
+
+/// Lifts a pause, trapping with `Sealed` once the registry was sealed with
+/// `seal` or is winding down.
+fn lift_pause(e: &Env) {
+    if e.data().has(DataKey::StateHash) || settings_of(e).stage != symbol!("live") {
+        panic_error!(e, Error::Sealed)
+    }
+    update_settings(e, |s| s.paused = false);
+}
+
+fn settings_of(e: &Env) -> Settings {
+    e.data()
+        .get(Setting::Config)
+        .unwrap_or_else(|| {
+            Ok(Settings {
+                paused: false,
+                verbosity: symbol!("full"),
+                phase: symbol!("open"),
+                max_key: 0,
+                req_ttl: REQUEST_TTL,
+                rsv_ttl: RESERVE_TTL,
+                tomb_ttl: TOMBSTONE_TTL,
+                swap_ttl: SWAP_TTL,
+                token: MaybeToken::None,
+                profile: symbol!("none"),
+                stage: symbol!("live"),
+                fallback: MaybeId::None,
+                ns_gated: false,
+                strict: false,
+                max_val: 0,
+                max_blob: MAX_BLOB,
+                time_mode: symbol!("ledger"),
+                noop_guard: false,
+                append: false,
+                quorum: MaybeQuorum::None,
+                dflt_owner: MaybeId::None,
+                max_dlg: 0,
+                max_owned: 0,
+                claim_sig: MaybeId::None,
+                min_bal: MaybeMin::None,
+                recv_guard: false,
+            })
+        })
+        .unwrap()
+}
+
+/// Applies `update` to the registry settings. Every setter of a field of
+/// `Settings` goes through here, so they are always stored together.
+fn update_settings(e: &Env, update: impl FnOnce(&mut Settings)) {
+    let mut settings = settings_of(e);
+    update(&mut settings);
+    e.data().set(Setting::Config, settings);
+}
+
+fn phase_of(e: &Env) -> Symbol {
+    settings_of(e).phase
+}
+
+/// Returns the implicit owner of unset keys set with `set_downer`, falling
+/// back to `unclaimed`. Everything reporting who unset keys belong to goes
+/// through it, so reads agree with the owner writes are authorized against.
+fn default_owner(e: &Env) -> Identifier {
+    Option::from(settings_of(e).dflt_owner).unwrap_or_else(|| unclaimed(e))
+}
+
+fn prefixes_of(e: &Env) -> Vec<Bytes> {
+    e.data()
+        .get(Setting::Prefixes)
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
+
+/// Returns whether `key` starts with any of the reserved prefixes.
+fn is_reserved(e: &Env, key: &Bytes) -> bool {
+    prefixes_of(e)
+        .iter()
+        .any(|prefix| starts_with(key, &prefix.unwrap()))
+}
+
+fn starts_with(key: &Bytes, prefix: &Bytes) -> bool {
+    key.len() >= prefix.len() && key.slice(..prefix.len()) == *prefix
+}
+
+/// Traps with `InvalidKey` if `key` is longer than the limit set with
+/// `set_maxlen` or has a byte the profile set with `set_prof` rejects, in
+/// which case the offset of the byte is logged.
+fn check_key(e: &Env, key: &Bytes) {
+    enforce(e, valid_key(e, key))
+}
+
+fn valid_key(e: &Env, key: &Bytes) -> Result<(), Refusal> {
+    let settings = settings_of(e);
+    if settings.max_key != 0 && key.len() > settings.max_key {
+        return Err(Error::InvalidKey.into());
+    }
+
+    let dns = settings.profile == symbol!("dns_label");
+    if !dns && settings.profile != symbol!("printable") {
+        return Ok(());
+    }
+    if dns && key.is_empty() {
+        log!(e, "invalid key byte at offset {}", 0_u32);
+        return Err(Error::InvalidKey.into());
+    }
+    for (offset, byte) in key.iter().enumerate() {
+        let valid = if dns {
+            byte.is_ascii_lowercase()
+                || byte.is_ascii_digit()
+                || (byte == b'-' && offset != 0 && offset as u32 != key.len() - 1)
+        } else {
+            (0x20..=0x7e).contains(&byte)
+        };
+        if !valid {
+            log!(e, "invalid key byte at offset {}", offset as u32);
+            return Err(Error::InvalidKey.into());
+        }
+    }
+    Ok(())
+}
+
+/// Enforces the key length limit, the reserved prefixes and the registration
+/// phase for `claimer` taking the unclaimed `key`. Only the admin can claim
+/// reserved keys, and burned keys can't be claimed by anyone. Keys starting
+/// with `NS_MARKER` belong to namespaces and can only be claimed through
+/// `set_ns`. Updates to keys that are already owned are not affected.
+///
+/// Under `set_strict`, contracts can't claim keys for themselves, so that
+/// routers users call through don't end up owning their keys; they have to
+/// name the owner with `claim_for`.
+fn check_claim(e: &Env, key: &Bytes, claimer: &Identifier) {
+    enforce(e, claimable(e, key, claimer))
+}
+
+fn claimable(e: &Env, key: &Bytes, claimer: &Identifier) -> Result<(), Refusal> {
+    if matches!(claimer, Identifier::Contract(_)) && settings_of(e).strict {
+        return Err(Error::ContractClaim.into());
+    }
+    claimable_for(e, key, claimer)
+}
+
+/// Enforces the claim rules for `key` being claimed on behalf of `claimer`,
+/// regardless of who invokes the claim.
+fn check_claim_for(e: &Env, key: &Bytes, claimer: &Identifier) {
+    enforce(e, claimable_for(e, key, claimer))
+}
+
+fn claimable_for(e: &Env, key: &Bytes, claimer: &Identifier) -> Result<(), Refusal> {
+    valid_key(e, key)?;
+    if e.data().has(Setting::Barred(claimer.clone())) {
+        return Err(Error::NotAllowed.into());
+    }
+    if e.data().has(DataKey::Burned(key.clone())) {
+        return Err(Error::Burned.into());
+    }
+    if key.get(0) == Some(NS_MARKER) {
+        return Err(Error::ReservedKey.into());
+    }
+
+    let settings = settings_of(e);
+    if let MaybeId::Some(fallback) = settings.dflt_owner {
+        if !same_id(claimer, &fallback) {
+            return Err(Refusal::Message("you are not allowed to change this value"));
+        }
+        return Ok(());
+    }
+
+    if is_reserved(e, key) && !is_admin(e, claimer) {
+        return Err(Error::ReservedKey.into());
+    }
+
+    let phase = settings.phase;
+
+    if phase == symbol!("closed") {
+        return Err(Error::RegistrationClosed.into());
+    }
+
+    if phase == symbol!("allowlist") {
+        allowed(e, claimer)?;
+    }
+
+    if let MaybeMin::Some(min) = settings.min_bal {
+        if token::Client::new(e, min.token).balance(claimer) < min.amount {
+            return Err(Error::NotAllowed.into());
+        }
+    }
+    Ok(())
+}
+
+/// Traps with `NotAllowed` unless `who` was allowed with `allow`.
+fn check_allowed(e: &Env, who: &Identifier) {
+    enforce(e, allowed(e, who))
+}
+
+fn allowed(e: &Env, who: &Identifier) -> Result<(), Refusal> {
+    if !e
+        .data()
+        .get(DataKey::Allowed(who.clone()))
+        .unwrap_or(Ok(false))
+        .unwrap()
+    {
+        return Err(Error::NotAllowed.into());
+    }
+    Ok(())
+}
+
+fn require_owner(e: &Env, key: &Bytes) {
+    require_owned_by(e, key, &invoker_id(e))
+}
+
+/// Like `require_owner`, for batches that look the invoker up once with
+/// `invoker_id` and pass it in for every key.
+fn require_owned_by(e: &Env, key: &Bytes, invoker: &Identifier) {
+    check_live(e);
+    if !same_id(&owner_of(e, key), invoker) {
+        panic!("you are not allowed to change this value")
+    }
+    check_unbarred(e, invoker);
+    if !shares_of(e, key).is_empty() {
+        panic_error!(e, Error::CoOwned)
+    }
+    if is_disputed(e, key) {
+        panic_error!(e, Error::Disputed)
+    }
+}
+
+/// Returns whether `key` is under a dispute that hasn't been resolved or
+/// lapsed yet.
+fn is_disputed(e: &Env, key: &Bytes) -> bool {
+    match e.data().get::<_, u64>(DataKey::Dispute(key.clone())) {
+        Some(since) => !deadline_passed(e, since.unwrap() + u64::from(DISPUTE_WINDOW)),
+        None => false,
+    }
+}
+
+/// Traps with `SignatureRequired` if `key` only accepts updates through
+/// `use_sig`.
+fn check_invoker_write(e: &Env, key: &Bytes) {
+    if e.data().has(DataKey::SigOnly(key.clone())) {
+        panic_error!(e, Error::SignatureRequired)
+    }
+}
+
+fn grants_of(e: &Env, key: &Bytes) -> Map<Identifier, Vec<Permission>> {
+    e.data()
+        .get(DataKey::Grants(key.clone()))
+        .unwrap_or_else(|| Ok(Map::new(e)))
+        .unwrap()
+}
+
+fn shares_of(e: &Env, key: &Bytes) -> Vec<Share> {
+    e.data()
+        .get(DataKey::Shares(key.clone()))
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
+
+/// Returns whether `key` is co-owned, in which case `who` must be a co-owner
+/// and the key must accept updates from single co-owners, or this traps with
+/// `CoOwned`.
+fn co_owned_write(e: &Env, key: &Bytes, who: &Identifier) -> bool {
+    enforce(e, co_owner_writes(e, key, who))
+}
+
+fn co_owner_writes(e: &Env, key: &Bytes, who: &Identifier) -> Result<bool, Refusal> {
+    let shares = shares_of(e, key);
+    if shares.is_empty() {
+        return Ok(false);
+    }
+
+    let solo = e.data().has(DataKey::SoloUpd(key.clone()));
+    if !solo
+        || !shares
+            .iter()
+            .any(|share| same_id(&share.unwrap().owner, who))
+    {
+        return Err(Error::CoOwned.into());
+    }
+    Ok(true)
+}
+
+fn parent_of(e: &Env, child: &Identifier) -> Option<Identifier> {
+    e.data()
+        .get(DataKey::Parent(child.clone()))
+        .map(Result::unwrap)
+}
+
+/// Returns whether `a` and `b` are the same identifier, the comparison
+/// authorization checks use between two identifiers and, through
+/// `contains_id`, against signer and approver lists. Lookups of grant maps
+/// compare the encoded identifiers and agree with it. Each variant wraps a
+/// fixed-size key or id with a single encoding, so identifiers of the same
+/// variant are the same exactly when their bytes are. Identifiers of
+/// different variants never are, even over the same bytes: an account
+/// authorizes as the invoker and an ed25519 key by signature, and neither
+/// stands in for the other.
+pub(crate) fn same_id(a: &Identifier, b: &Identifier) -> bool {
+    match (a, b) {
+        (Identifier::Contract(a), Identifier::Contract(b)) => a == b,
+        (Identifier::Ed25519(a), Identifier::Ed25519(b)) => a == b,
+        (Identifier::Account(a), Identifier::Account(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Returns the position of `id` in `ids`, compared with `same_id`.
+fn index_of_id(ids: &Vec<Identifier>, id: &Identifier) -> Option<u32> {
+    ids.iter()
+        .position(|other| same_id(&other.unwrap(), id))
+        .map(|i| i as u32)
+}
+
+/// Returns whether `ids` lists `id`, compared with `same_id`.
+fn contains_id(ids: &Vec<Identifier>, id: &Identifier) -> bool {
+    index_of_id(ids, id).is_some()
+}
+
+/// Returns whether `who` owns `key`, is the registered parent of its owner or
+/// was granted `perm` on it with uses left.
+fn has_perm(e: &Env, key: &Bytes, who: &Identifier, perm: Permission) -> bool {
+    let owner = owner_of(e, key);
+    same_id(&owner, who)
+        || parent_of(e, &owner).is_some_and(|parent| same_id(&parent, who))
+        || grants_of(e, key)
+            .get(who.clone())
+            .map(|perms| perms.unwrap().contains(perm))
+            .unwrap_or(false)
+            && uses_of(e, key).get(who.clone()) != Some(Ok(0))
+}
+
+/// Traps with `NotAllowed` if `who` was barred with `block`.
+fn check_unbarred(e: &Env, who: &Identifier) {
+    if e.data().has(Setting::Barred(who.clone())) {
+        panic_error!(e, Error::NotAllowed)
+    }
+}
+
+fn require_perm(e: &Env, key: &Bytes, perm: Permission) {
+    check_live(e);
+    let invoker = invoker_id(e);
+    check_unbarred(e, &invoker);
+    if !co_owned_write(e, key, &invoker) && !has_perm(e, key, &invoker, perm) {
+        panic!("you are not allowed to change this value")
+    }
+    check_unbarred(e, &owner_of(e, key));
+    use_grant(e, key, &invoker);
+}
+
+fn uses_of(e: &Env, key: &Bytes) -> Map<Identifier, u32> {
+    e.data()
+        .get(DataKey::Uses(key.clone()))
+        .unwrap_or_else(|| Ok(Map::new(e)))
+        .unwrap()
+}
+
+/// Counts a change to `key` by `who` against the uses `set_uses` left on its
+/// grants. Changes by the owner and its parent don't count.
+fn use_grant(e: &Env, key: &Bytes, who: &Identifier) {
+    let owner = owner_of(e, key);
+    if same_id(&owner, who) || parent_of(e, &owner).is_some_and(|parent| same_id(&parent, who)) {
+        return;
+    }
+    let mut uses = uses_of(e, key);
+    if let Some(left) = uses.get(who.clone()) {
+        uses.set(who.clone(), left.unwrap().saturating_sub(1));
+        e.data().set(DataKey::Uses(key.clone()), uses);
+    }
+}
+
+fn history_of(e: &Env, key: &Bytes) -> Vec<Identifier> {
+    e.data()
+        .get(DataKey::History(key.clone()))
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
+
+/// Returns the number of slots in the key index, including the slots of
+/// removed keys.
+fn slot_count(e: &Env) -> u32 {
+    e.data().get(DataKey::Keys).unwrap_or(Ok(0)).unwrap()
+}
+
+fn key_page(e: &Env, page: u32) -> Vec<Bytes> {
+    e.data()
+        .get(DataKey::KeyPage(page))
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
+
+fn key_in_slot(e: &Env, slot: u32) -> Option<Bytes> {
+    if slot >= slot_count(e) {
+        return None;
+    }
+    Some(
+        key_page(e, slot / INDEX_PAGE)
+            .get_unchecked(slot % INDEX_PAGE)
+            .unwrap(),
+    )
+}
+
+/// Returns the keys in slots `start` up to `end` of the key index, reading
+/// only the pages that hold them.
+fn keys_in(e: &Env, start: u32, end: u32) -> Vec<Bytes> {
+    let end = end.min(slot_count(e));
+    let mut keys = Vec::new(e);
+    let mut slot = start;
+    while slot < end {
+        let page = slot / INDEX_PAGE;
+        let first = page * INDEX_PAGE;
+        let upto = end.min(first + INDEX_PAGE);
+        for key in key_page(e, page).slice(slot - first..upto - first) {
+            keys.push_back(key.unwrap());
+        }
+        slot = upto;
+    }
+    keys
+}
+
+fn keys_of(e: &Env) -> Vec<Bytes> {
+    keys_in(e, 0, slot_count(e))
+}
+
+/// Appends `key` to the key index and returns its slot.
+fn push_key(e: &Env, key: &Bytes) -> u32 {
+    let slot = slot_count(e);
+    let mut page = key_page(e, slot / INDEX_PAGE);
+    page.push_back(key.clone());
+    e.data().set(DataKey::KeyPage(slot / INDEX_PAGE), page);
+    e.data().set(DataKey::Keys, slot + 1);
+    slot
+}
+
+fn owner_page(e: &Env, owner: &Identifier, page: u32) -> Vec<Bytes> {
+    e.data()
+        .get(DataKey::OwnerPage(PageKey {
+            owner: owner.clone(),
+            page,
+        }))
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
+
+fn set_owner_page(e: &Env, owner: &Identifier, page: u32, keys: Vec<Bytes>) {
+    let page = DataKey::OwnerPage(PageKey {
+        owner: owner.clone(),
+        page,
+    });
+    if keys.is_empty() {
+        e.data().remove(page);
+    } else {
+        e.data().set(page, keys);
+    }
+}
+
+/// Appends `key` to the index of the keys owned by `owner`.
+fn index_key(e: &Env, owner: &Identifier, key: &Bytes) {
+    let slot = count_of(e, owner);
+    let mut page = owner_page(e, owner, slot / INDEX_PAGE);
+    page.push_back(key.clone());
+    set_owner_page(e, owner, slot / INDEX_PAGE, page);
+    e.data().set(DataKey::OwnerSlot(key.clone()), slot);
+    e.data().set(DataKey::OwnerCount(owner.clone()), slot + 1);
+}
+
+/// Returns the owners of the latest versions of `key`, oldest first.
+fn owners_of(e: &Env, key: &Bytes) -> Vec<OwnerAt> {
+    e.data()
+        .get(KeyExtra::Owners(key.clone()))
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
+
+/// Records `owner` as the owner of `key` at `version`, forgetting the owners
+/// of the versions older than the latest `OWNER_HISTORY`. An owner already
+/// recorded at `version`, which `rebind` moved away from without bumping the
+/// version, is replaced.
+fn record_owner(e: &Env, key: &Bytes, owner: Identifier, version: u32) {
+    let mut owners = owners_of(e, key);
+    if owners.last().map(|last| last.unwrap().version) == Some(version) {
+        owners.pop_back();
+    }
+    owners.push_back(OwnerAt {
+        owner,
+        ledger: e.ledger().sequence(),
+        version,
+    });
+    while owners.len() > OWNER_HISTORY {
+        owners.pop_front();
+    }
+    e.data().set(KeyExtra::Owners(key.clone()), owners);
+}
+
+/// Removes `key` from the index of the keys owned by `owner`, moving the
+/// owner's last key into its slot so the pages stay full.
+fn unindex_key(e: &Env, owner: &Identifier, key: &Bytes) {
+    let last = count_of(e, owner) - 1;
+    let slot: u32 = e
+        .data()
+        .get(DataKey::OwnerSlot(key.clone()))
+        .unwrap()
+        .unwrap();
+
+    let mut page = owner_page(e, owner, last / INDEX_PAGE);
+    let moved = page.pop_back().unwrap().unwrap();
+    set_owner_page(e, owner, last / INDEX_PAGE, page);
+    if slot != last {
+        let mut page = owner_page(e, owner, slot / INDEX_PAGE);
+        page.set(slot % INDEX_PAGE, moved.clone());
+        set_owner_page(e, owner, slot / INDEX_PAGE, page);
+        e.data().set(DataKey::OwnerSlot(moved), slot);
+    }
+    e.data().remove(DataKey::OwnerSlot(key.clone()));
+    e.data().set(DataKey::OwnerCount(owner.clone()), last);
+}
+
+/// Traps unless `key` can currently be written to: neither the registry nor
+/// the key is paused, the registry isn't ended,
+/// the key isn't claimed in an append-only registry and it is neither frozen,
+/// locked nor disputed.
+fn check_writable(e: &Env, key: &Bytes) {
+    enforce(e, writable(e, key))
+}
+
+fn writable(e: &Env, key: &Bytes) -> Result<(), Refusal> {
+    let settings = settings_of(e);
+    if settings.stage == symbol!("ended") {
+        return Err(Error::Sealed.into());
+    }
+    if settings.paused || e.data().has(KeyExtra::Paused(key.clone())) {
+        return Err(Error::Paused.into());
+    }
+    if settings.append && e.data().has(DataKey::Owner(key.clone())) {
+        return Err(Error::ValueLocked.into());
+    }
+    if is_frozen(e, key) {
+        return Err(Refusal::Message("this key is frozen"));
+    }
+    if is_locked(e, key) {
+        return Err(Error::ValueLocked.into());
+    }
+    if is_disputed(e, key) {
+        return Err(Error::Disputed.into());
+    }
+    Ok(())
+}
+
+fn count_of(e: &Env, owner: &Identifier) -> u32 {
+    e.data()
+        .get(DataKey::OwnerCount(owner.clone()))
+        .unwrap_or(Ok(0))
+        .unwrap()
+}
+
+/// Moves `key` from the index and key count of `from` to those of `to`,
+/// either of which can be `None` for keys being claimed or removed. Traps
+/// with `NotAllowed` if `to` was restricted with `restrict` or already owns
+/// as many keys as `set_maxown` allows.
+fn move_key(e: &Env, key: &Bytes, from: Option<Identifier>, to: Option<Identifier>) {
+    if from == to {
+        return;
+    }
+    if let Some(from) = from {
+        unindex_key(e, &from, key);
+    }
+    if let Some(to) = to {
+        if e.data().has(Setting::Restricted(to.clone())) {
+            panic_error!(e, Error::NotAllowed)
+        }
+        let max = settings_of(e).max_owned;
+        if max != 0 && count_of(e, &to) >= max {
+            panic_error!(e, Error::NotAllowed)
+        }
+        index_key(e, &to, key);
+    }
+}
+
+fn hot_of(e: &Env) -> Map<Bytes, bool> {
+    e.data()
+        .get(DataKey::Hot)
+        .unwrap_or_else(|| Ok(Map::new(e)))
+        .unwrap()
+}
+
+fn stats_of(e: &Env) -> Stats {
+    e.data()
+        .get(DataKey::Stats)
+        .unwrap_or_else(|| Ok(Stats::default()))
+        .unwrap()
+}
+
+fn update_stats(e: &Env, update: impl FnOnce(&mut Stats)) {
+    let mut stats = stats_of(e);
+    update(&mut stats);
+    e.data().set(DataKey::Stats, stats);
+}
+
+/// Records whether `key` is claimed in the hot set, if it is pinned.
+fn sync_hot(e: &Env, key: &Bytes, claimed: bool) {
+    let mut hot = hot_of(e);
+    if hot.get(key.clone()).is_some_and(|c| c.unwrap() != claimed) {
+        hot.set(key.clone(), claimed);
+        e.data().set(DataKey::Hot, hot);
+    }
+}
+
+/// Records a write to `key` by `owner`, bumping its modification ledger and
+/// version and keeping the key counts of its previous and new owner in sync.
+/// Claims of unclaimed keys also record `owner` as the claimer.
+/// Keys claimed for the first time are appended to the key index; keys that
+/// were removed and claimed again keep their original slot.
+fn touch(e: &Env, key: &Bytes, owner: Identifier) {
+    check_writable(e, key);
+    stamp(e, key, owner);
+}
+
+/// Like `touch`, for callers that already checked `key` is writable.
+fn stamp(e: &Env, key: &Bytes, owner: Identifier) {
+    let previous = e
+        .data()
+        .get(DataKey::Owner(key.clone()))
+        .map(Result::unwrap);
+    if previous.is_none() {
+        e.data().remove(KeyExtra::Tombstone(key.clone()));
+        e.data().set(
+            DataKey::Claimer(key.clone()),
+            (owner.clone(), e.ledger().sequence()),
+        );
+        sync_hot(e, key, true);
+        update_stats(e, |stats| {
+            stats.claims += 1;
+            stats.active += 1;
+        });
+    }
+    move_key(e, key, previous, Some(owner.clone()));
+
+    if !e.data().has(DataKey::Slot(key.clone())) {
+        let slot = push_key(e, key);
+        e.data().set(DataKey::Slot(key.clone()), slot);
+    }
+
+    e.data().set(DataKey::Owner(key.clone()), owner.clone());
+    e.data()
+        .set(DataKey::Modified(key.clone()), e.ledger().sequence());
+    let version = version_of(e, key) + 1;
+    e.data().set(DataKey::Version(key.clone()), version);
+    record_owner(e, key, owner.clone(), version);
+}
+
+/// Refuses the unclaimed-key sentinel as a value with `ReservedValue`, and
+/// values that serialize to more bytes than the limit set with `set_maxval`
+/// with `ValueTooLarge`.
+fn valid_value(e: &Env, value: &Identifier) -> Result<(), Refusal> {
+    if *value == unclaimed(e) {
+        return Err(Error::ReservedValue.into());
+    }
+    if e.data().has(Setting::Blocked(value.clone())) {
+        return Err(Error::NotAllowed.into());
+    }
+    let max = settings_of(e).max_val;
+    if max != 0 && value.clone().serialize(e).len() > max {
+        return Err(Error::ValueTooLarge.into());
+    }
+    Ok(())
+}
+
+/// Traps with `NoOp` for writes that wouldn't change anything, if the admin
+/// turned this on with `noop_guard`.
+fn check_noop(e: &Env) {
+    if settings_of(e).noop_guard {
+        panic_error!(e, Error::NoOp)
+    }
+}
+
+/// Writes `value` under `key`, appending the value it replaces to the key's
+/// history and notifying its subscribers. The current contract's identifier is
+/// the unclaimed-key sentinel and is rejected as a value; `remove_val` is the
+/// way to release a key.
+/// Writing the value already stored by the same owner is a no-op and does not
+/// bump the version, or traps under `noop_guard`, once the key was found
+/// writable. Otherwise a `set` event is
+/// published with the key, the new owner, who is the signer for signed writes,
+/// the value, the new `entry_hash` of the key and whether its text records
+/// changed with the value. Keys can be longer than event topics allow, so
+/// they go in the data, while the topic set with `set_topic` follows `set` as
+/// a second topic. Returns the value the key held before, if any, from the
+/// same read the no-op check makes.
+fn write_val(e: &Env, key: Bytes, owner: Identifier, value: Identifier) -> Option<Identifier> {
+    write_entry(e, key, owner, value, false, None)
+}
+
+/// Like `write_val`, for writes that also replaced the text records of `key`
+/// if `texts` is set, in which case writing the stored value isn't a no-op,
+/// and writes forwarding `forward` to the subscribers of `key`.
+fn write_entry(
+    e: &Env,
+    key: Bytes,
+    owner: Identifier,
+    value: Identifier,
+    texts: bool,
+    forward: Option<Bytes>,
+) -> Option<Identifier> {
+    enforce(e, valid_value(e, &value));
+
+    let previous = e
+        .data()
+        .get(DataKey::Value(key.clone()))
+        .map(Result::unwrap);
+    check_writable(e, &key);
+    if !texts && same_id(&owner_of(e, &key), &owner) && previous.as_ref() == Some(&value) {
+        check_noop(e);
+        return previous;
+    }
+    if previous.is_some() && e.data().has(DataKey::WriteOnce(key.clone())) {
+        panic_error!(e, Error::ValueLocked)
+    }
+    if previous.as_ref() != Some(&value) && e.data().has(Setting::Restricted(value.clone())) {
+        panic_error!(e, Error::NotAllowed)
+    }
+
+    let level = if e.data().has(DataKey::Owner(key.clone())) {
+        Level::Full
+    } else {
+        Level::Minimal
+    };
+    stamp(e, &key, owner.clone());
+    e.data().remove(DataKey::Split(key.clone()));
+    e.data().remove(KeyExtra::Memo(key.clone()));
+    update_stats(e, |stats| stats.writes += 1);
+
+    if let Some(previous) = previous.clone() {
+        let mut history = history_of(e, &key);
+        history.push_back(previous);
+        e.data().set(DataKey::History(key.clone()), history);
+    }
+
+    let data = (
+        key.clone(),
+        owner,
+        value.clone(),
+        hash_entry(e, &key),
+        texts,
+    );
+    match e.data().get::<_, Symbol>(DataKey::Topic(key.clone())) {
+        Some(topic) => emit(e, level, (TOPIC_SET, topic.unwrap()), data),
+        None => emit(e, level, (TOPIC_SET,), data),
+    }
+    e.data().set(DataKey::Value(key.clone()), value.clone());
+    call_hook(
+        e,
+        &key,
+        previous.clone().unwrap_or_else(|| unclaimed(e)),
+        value.clone(),
+    );
+    notify(e, key, value, forward);
+    previous
+}
+
+/// Calls `on_update(key, old, new)` on the hook of `key`, if it has one. The
+/// write stands even if the hook traps, in which case a `hook_fail` event is
+/// published with the key and the hook.
+fn call_hook(e: &Env, key: &Bytes, old: Identifier, new: Identifier) {
+    if let Some(hook) = e.data().get::<_, BytesN<32>>(DataKey::Hook(key.clone())) {
+        let hook = hook.unwrap();
+        let result = e.try_invoke_contract::<RawVal, Status>(
+            &hook,
+            &symbol!("on_update"),
+            (key.clone(), old, new).into_val(e),
+        );
+        if result.is_err() {
+            emit(e, Level::Full, (TOPIC_HOOK_FAIL,), (key.clone(), hook));
+        }
+    }
+}
+
+fn subs_of(e: &Env, key: &Bytes) -> Vec<BytesN<32>> {
+    e.data()
+        .get(DataKey::Subs(key.clone()))
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
+
+/// Calls `on_change(key, value)` on every contract subscribed to `key`, or
+/// `on_data(key, value, data)` for writes forwarding `data`. Notifications are
+/// best-effort like hooks: a subscriber that traps doesn't revert the write
+/// and gets a `hook_fail` event instead.
+fn notify(e: &Env, key: Bytes, value: Identifier, data: Option<Bytes>) {
+    for sub in subs_of(e, &key).iter() {
+        let sub = sub.unwrap();
+        let result = match &data {
+            Some(data) => e.try_invoke_contract::<RawVal, Status>(
+                &sub,
+                &symbol!("on_data"),
+                (key.clone(), value.clone(), data.clone()).into_val(e),
+            ),
+            None => e.try_invoke_contract::<RawVal, Status>(
+                &sub,
+                &symbol!("on_change"),
+                (key.clone(), value.clone()).into_val(e),
+            ),
+        };
+        if result.is_err() {
+            emit(e, Level::Full, (TOPIC_HOOK_FAIL,), (key.clone(), sub));
+        }
+    }
+}
+
+fn nonce_of(e: &Env, id: &Identifier) -> BigInt {
+    e.data()
+        .get(DataKey::Nonce(id.clone()))
+        .unwrap_or_else(|| Ok(BigInt::zero(e)))
+        .unwrap()
+}
+
+/// How much of the registry's activity an event reports: `Minimal` for
+/// changes of ownership and `Full` for everything else, published only at the
+/// `full` verbosity set with `set_verbos`.
+enum Level {
+    Minimal,
+    Full,
+}
+
+/// Publishes an event with `topics`, unless the verbosity set with
+/// `set_verbos` leaves out events of `level`. Every event of the registry
+/// goes through here, except the one `set_verbos` always publishes.
+fn emit(e: &Env, level: Level, topics: impl Topics, data: impl IntoVal<Env, RawVal>) {
+    let verbosity = settings_of(e).verbosity;
+    let enabled = if verbosity == symbol!("off") {
+        false
+    } else if verbosity == symbol!("minimal") {
+        matches!(level, Level::Minimal)
+    } else {
+        true
+    };
+    if enabled {
+        e.events().publish(topics, data);
+    }
+}
+
+/// Increments the nonce of `id` and publishes `(id, nonce)` with the new value,
+/// so relayers can tell when a nonce they signed with was consumed by another
+/// transaction. Event topics can't hold identifiers, so `id` goes in the data.
+fn bump_nonce(e: &Env, id: Identifier) {
+    let nonce: BigInt = nonce_of(e, &id) + 1;
+    emit(e, Level::Full, (TOPIC_NONCE,), (id.clone(), nonce.clone()));
+    e.data().set(DataKey::Nonce(id), nonce);
+}
+
+/// Most signatures `verify` accepts for a single account.
+const MAX_ACCOUNT_SIGS: u32 = 20;
+
+/// Returns whether `verify` could accept `sig` at all, leaving only the
+/// cryptographic checks, which trap. Account signatures must name an existing
+/// account, be sorted by key without duplicates and come from its signers,
+/// together reaching its medium threshold. Other signatures always could.
+fn well_formed(e: &Env, sig: &Signature) -> bool {
+    let auth = match sig {
+        Signature::Account(auth) => auth,
+        _ => return true,
+    };
+    let account = match e.accounts().get(&auth.account_id) {
+        Some(account) => account,
+        None => return false,
+    };
+    if auth.signatures.len() > MAX_ACCOUNT_SIGS {
+        return false;
+    }
+
+    let mut weight: u32 = 0;
+    let mut previous: Option<BytesN<32>> = None;
+    for signature in auth.signatures.iter() {
+        let key = signature.unwrap().public_key;
+        let signer = account.signer_weight(&key);
+        if signer == 0 || previous.is_some_and(|previous| previous >= key) {
+            return false;
+        }
+        weight += signer as u32;
+        previous = Some(key);
+    }
+    weight >= account.medium_threshold() as u32
+}
+
+/// Verifies `sig` over `args` prefixed with the signer's current nonce, then
+/// consumes the nonce so the signature can't be replayed. Invoker signatures
+/// carry no cryptographic payload and leave the nonce untouched.
+fn verify_nonce(e: &Env, sig: &Signature, name: Symbol, args: impl IntoVal<Env, Vec<RawVal>>) {
+    let nonce = nonce_of(e, &sig.identifier(e));
+    let args = nonce_args(e, nonce.clone(), args);
+    verify_nonce_at(e, sig, name, nonce, args)
+}
+
+/// Like `verify_nonce`, but with the nonce `sig` was made with, which is
+/// either the signer's current nonce or one it reserved with `rsv_nonce`, and
+/// `args` already starting with it.
+/// Reserved nonces are consumed without touching the sequential one, and any
+/// other nonce traps with `NotAuthorized`.
+fn verify_nonce_at(e: &Env, sig: &Signature, name: Symbol, nonce: BigInt, args: Vec<RawVal>) {
+    let signer = sig.identifier(e);
+    let sequential = nonce == nonce_of(e, &signer);
+    if *sig != Signature::Invoker && !sequential && !take_reserved(e, &signer, &nonce) {
+        panic_error!(e, Error::NotAuthorized)
+    }
+
+    verify(e, sig, name, args);
+
+    if *sig != Signature::Invoker && sequential {
+        bump_nonce(e, signer);
+    }
+}
+
+/// Returns the arguments a signature made with `nonce` covers: `args`
+/// prefixed with the nonce.
+fn nonce_args(e: &Env, nonce: BigInt, args: impl IntoVal<Env, Vec<RawVal>>) -> Vec<RawVal> {
+    let mut args: Vec<RawVal> = args.into_val(e);
+    args.push_front(nonce.into_val(e));
+    args
+}
+
+/// Returns the arguments `use_sig` checks the signature over when setting
+/// `key` to `value` with `nonce`, in the order they are signed in:
+/// `(nonce, key, value)`.
+pub(crate) fn change_args(e: &Env, nonce: BigInt, key: Bytes, value: Identifier) -> Vec<RawVal> {
+    nonce_args(e, nonce, (key, value))
+}
+
+/// Returns the nonces `id` reserved with `rsv_nonce` that haven't expired
+/// yet, with when each was reserved.
+fn reserved_of(e: &Env, id: &Identifier) -> Map<BigInt, u64> {
+    let reserved: Map<BigInt, u64> = e
+        .data()
+        .get(Setting::Reserved(id.clone()))
+        .unwrap_or_else(|| Ok(Map::new(e)))
+        .unwrap();
+    let ttl = settings_of(e).rsv_ttl;
+    let mut live = Map::new(e);
+    for entry in reserved.iter() {
+        let (nonce, since) = entry.unwrap();
+        if !expired(e, since.saturating_add(u64::from(ttl))) {
+            live.set(nonce, since);
+        }
+    }
+    live
+}
+
+/// Removes `nonce` from the reservations of `id`, returning whether it was
+/// reserved and hadn't expired.
+fn take_reserved(e: &Env, id: &Identifier, nonce: &BigInt) -> bool {
+    let mut reserved = reserved_of(e, id);
+    let found = reserved.contains_key(nonce.clone());
+    reserved.remove(nonce.clone());
+    set_reserved(e, id, reserved);
+    found
+}
+
+fn set_reserved(e: &Env, id: &Identifier, reserved: Map<BigInt, u64>) {
+    if reserved.is_empty() {
+        e.data().remove(Setting::Reserved(id.clone()));
+    } else {
+        e.data().set(Setting::Reserved(id.clone()), reserved);
+    }
+}
+
+/// Runs the checks `change_val` makes before `who` writes to `key`, without
+/// writing anything, and returns the owner the value would be stored under.
+/// Shared with `can_change` and `can_claim` so the dry runs can't drift from
+/// the real write. Checks on the value itself, such as its size, are left to
+/// `write_val`. Signed writes skip the `sig_only` check, as they are what it
+/// asks for.
+fn authorize_change(
+    e: &Env,
+    key: &Bytes,
+    who: &Identifier,
+    signed: bool,
+) -> Result<Identifier, Refusal> {
+    let stored_addr = owner_of(e, key);
+    let owner = if stored_addr == unclaimed(e) {
+        claimable(e, key, who)?;
+        who.clone()
+    } else {
+        if e.data().has(Setting::Barred(who.clone()))
+            || e.data().has(Setting::Barred(stored_addr.clone()))
+        {
+            return Err(Error::NotAllowed.into());
+        }
+        let permitted = match active_rental(e, key) {
+            Some(rental) => same_id(&rental.renter, who),
+            None => co_owner_writes(e, key, who)? || has_perm(e, key, who, Permission::Update),
+        };
+        if !permitted {
+            return Err(Refusal::Message("you are not allowed to change this value"));
+        }
+        if !signed && e.data().has(DataKey::SigOnly(key.clone())) {
+            return Err(Error::SignatureRequired.into());
+        }
+        if e.data().has(DataKey::WriteOnce(key.clone()))
+            && e.data().has(DataKey::Value(key.clone()))
+        {
+            return Err(Error::ValueLocked.into());
+        }
+        stored_addr
+    };
+    writable(e, key)?;
+    Ok(owner)
+}
+
+/// Writes `value` under `key` on behalf of the signer of `sig`, who must own the
+/// key, hold `Update` on it or be claiming it, after verifying the signature
+/// over `args`. Returns the value the key held before, if any.
+fn signed_write(
+    e: &Env,
+    sig: &Signature,
+    nonce: BigInt,
+    key: Bytes,
+    value: Identifier,
+    args: Vec<RawVal>,
+) -> Option<Identifier> {
+    let signer = sig.identifier(e);
+    let owner = enforce(e, authorize_change(e, &key, &signer, true));
+
+    verify_nonce_at(e, sig, symbol!("change"), nonce, args);
+    use_grant(e, &key, &signer);
+
+    write_val(e, key, owner, value)
+}
+
+/// Traps with `NotAllowed` if `recv_guard` is on and `to` is a contract that
+/// doesn't return true from `can_recv(key, from)`, including contracts that
+/// don't implement it or trap in it.
+fn check_receiver(e: &Env, key: &Bytes, to: &Identifier) {
+    let id = match to {
+        Identifier::Contract(id) => id,
+        _ => return,
+    };
+    if !settings_of(e).recv_guard {
+        return;
+    }
+
+    let accepted = e.try_invoke_contract::<bool, Status>(
+        id,
+        &symbol!("can_recv"),
+        (key.clone(), owner_of(e, key)).into_val(e),
+    );
+    if accepted != Ok(Ok(true)) {
+        panic_error!(e, Error::NotAllowed)
+    }
+}
+
+/// Hands ownership of `key` to `new_owner`, leaving its value untouched unless
+/// the key is linked with `link_owner`, in which case the value is set to the
+/// new owner. Permissions granted by the previous owner are wiped, as is
+/// co-ownership. A `transfer` event is published with the key, the previous
+/// and the new owner and the new `entry_hash` of the key.
+fn transfer_key(e: &Env, key: &Bytes, new_owner: Identifier) {
+    check_recipient(e, key, &new_owner);
+    let previous = owner_of(e, key);
+    if same_id(&previous, &new_owner) {
+        check_noop(e);
+    }
+    touch(e, key, new_owner.clone());
+    clear_owner_state(e, key);
+    follow_owner(e, key, new_owner.clone());
+    emit_transfer(e, key, previous, new_owner);
+}
+
+/// Traps unless `new_owner` may be handed `key`.
+fn check_recipient(e: &Env, key: &Bytes, new_owner: &Identifier) {
+    if e.data().has(Setting::Blocked(new_owner.clone())) {
+        panic_error!(e, Error::NotAllowed)
+    }
+    check_receiver(e, key, new_owner);
+}
+
+/// Wipes everything the previous owner of `key` set up on it.
+fn clear_owner_state(e: &Env, key: &Bytes) {
+    e.data().remove(DataKey::Grants(key.clone()));
+    e.data().remove(DataKey::Uses(key.clone()));
+    e.data().remove(DataKey::Spender(key.clone()));
+    e.data().remove(DataKey::Recovery(key.clone()));
+    e.data().remove(DataKey::Guardian(key.clone()));
+    e.data().remove(KeyExtra::Guardians(key.clone()));
+    e.data().remove(KeyExtra::Handoff(key.clone()));
+    e.data().remove(KeyExtra::Swap(key.clone()));
+    e.data().remove(DataKey::Blob(key.clone()));
+    e.data().remove(DataKey::BlobType(key.clone()));
+    e.data().remove(DataKey::Gated(key.clone()));
+    e.data().remove(DataKey::Hook(key.clone()));
+    e.data().remove(DataKey::Topic(key.clone()));
+    clear_shares(e, key);
+}
+
+/// Publishes the `transfer` event of `key` going from `from` to `to`.
+fn emit_transfer(e: &Env, key: &Bytes, from: Identifier, to: Identifier) {
+    emit(
+        e,
+        Level::Minimal,
+        (TOPIC_TRANSFER,),
+        (key.clone(), from, to, hash_entry(e, key)),
+    );
+}
+
+/// Sets the value of `key` to `owner` if the key is linked and holds anything
+/// else.
+fn follow_owner(e: &Env, key: &Bytes, owner: Identifier) {
+    if e.data().has(DataKey::Linked(key.clone()))
+        && e.data().get(DataKey::Value(key.clone())) != Some(Ok(owner.clone()))
+    {
+        write_val(e, key.clone(), owner.clone(), owner);
+    }
+}
+
+fn clear_shares(e: &Env, key: &Bytes) {
+    e.data().remove(DataKey::Shares(key.clone()));
+    e.data().remove(DataKey::SoloUpd(key.clone()));
+    e.data().remove(DataKey::Approvals(key.clone()));
+}
+
+fn approvals_of(e: &Env, key: &Bytes) -> Map<BytesN<32>, Vec<Identifier>> {
+    e.data()
+        .get(DataKey::Approvals(key.clone()))
+        .unwrap_or_else(|| Ok(Map::new(e)))
+        .unwrap()
+}
+
+/// Returns the co-owner of `key` the invoker is, trapping with `NotAuthorized`
+/// if the invoker isn't one.
+fn require_co_owner(e: &Env, key: &Bytes) -> Identifier {
+    let invoker = invoker_id(e);
+    if !shares_of(e, key)
+        .iter()
+        .any(|share| same_id(&share.unwrap().owner, &invoker))
+    {
+        panic_error!(e, Error::NotAuthorized)
+    }
+    invoker
+}
+
+fn list_of(e: &Env, key: &Bytes) -> Vec<Identifier> {
+    e.data()
+        .get(DataKey::List(key.clone()))
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
+
+/// Returns the key the symbol-keyed entry points address for `key`: the
+/// characters of the symbol as ASCII bytes, so `symbol!("hello")` and
+/// `bytes!(&e, 0x68656c6c6f)` are the same key.
+fn sym_key(e: &Env, key: Symbol) -> Bytes {
+    let mut bytes = Bytes::new(e);
+    for c in key {
+        bytes.push(c as u8);
+    }
+    bytes
+}
+
+/// Returns the value of `key`. A key that never existed traps with a message
+/// naming it, and the key is also logged so it shows up in the diagnostics
+/// of the failed transaction.
+fn read_val(e: &Env, key: &Bytes) -> Identifier {
+    let value = stored_val(e, key);
+    if is_private(e, key) && !same_id(&owner_of(e, key), &invoker_id(e)) {
+        panic!("this key is private")
+    }
+
+    value
+}
+
+/// Returns the value of `key` regardless of its privacy, trapping like `get`
+/// if it has none.
+fn stored_val(e: &Env, key: &Bytes) -> Identifier {
+    e.data()
+        .get(DataKey::Value(key.clone()))
+        .unwrap_or_else(|| {
+            if e.data().has(DataKey::Burned(key.clone())) {
+                panic_error!(e, Error::Burned)
+            }
+            if e.data().has(DataKey::Owner(key.clone())) {
+                panic_error!(e, Error::NoValue)
+            }
+            log!(e, "key does not exist: {}", key.clone());
+            panic!("Key does not exist: {:?}", key)
+        })
+        .unwrap()
+}
+
+fn commitment(e: &Env, hash: &BytesN<32>) -> Option<(Identifier, u64)> {
+    e.data()
+        .get(DataKey::Commit(hash.clone()))
+        .map(Result::unwrap)
+}
+
+/// Fails with `Burned` for burned keys and `KeyNotFound` for keys without a
+/// value.
+fn check_present(e: &Env, key: &Bytes) -> Result<(), Error> {
+    if e.data().has(DataKey::Burned(key.clone())) {
+        return Err(Error::Burned);
+    }
+    if !e.data().has(DataKey::Value(key.clone())) {
+        return Err(Error::KeyNotFound);
+    }
+    Ok(())
+}
+
+/// Returns whether the value of `key` was blocked with `block_val`.
+fn value_blocked(e: &Env, key: &Bytes) -> bool {
+    match e.data().get::<_, Identifier>(DataKey::Value(key.clone())) {
+        Some(value) => e.data().has(Setting::Blocked(value.unwrap())),
+        None => false,
+    }
+}
+
+fn journal_of(e: &Env, id: u32) -> Vec<AdminOp> {
+    e.data()
+        .get(Setting::Journal(id))
+        .unwrap_or_else(|| panic_error!(e, Error::NoRequest))
+        .unwrap()
+}
+
+/// Runs the checks the admin function behind `op` would make, so a journal
+/// can be refused as a whole before any of it is applied.
+fn check_op(e: &Env, op: &AdminOp) -> Result<(), Error> {
+    match op {
+        AdminOp::Phase(phase)
+            if *phase != symbol!("closed")
+                && *phase != symbol!("allowlist")
+                && *phase != symbol!("open") =>
+        {
+            Err(Error::InvalidPhase)
+        }
+        AdminOp::Reassign(KeyOwner { key, .. }) | AdminOp::Dismiss(key) if !is_disputed(e, key) => {
+            Err(Error::NoDispute)
+        }
+        AdminOp::Reassign(KeyOwner { owner, .. })
+            if e.data().has(Setting::Blocked(owner.clone())) =>
+        {
+            Err(Error::NotAllowed)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn rental_of(e: &Env, key: &Bytes) -> Option<Rental> {
+    e.data()
+        .get(KeyExtra::Rental(key.clone()))
+        .map(|rental| rental.unwrap())
+}
+
+/// Returns the rental of `key` if it hasn't run out yet.
+fn active_rental(e: &Env, key: &Bytes) -> Option<Rental> {
+    rental_of(e, key).filter(|rental| !expired(e, rental.until))
+}
+
+fn token_client(e: &Env) -> token::Client {
+    let id: BytesN<32> =
+        Option::from(settings_of(e).token).unwrap_or_else(|| panic_error!(e, Error::NoToken));
+    token::Client::new(e, id)
+}
+
+fn namespace_of(e: &Env, ns: &Bytes) -> Namespace {
+    e.data()
+        .get(DataKey::Namespace(ns.clone()))
+        .unwrap_or_else(|| panic_error!(e, Error::NoNamespace))
+        .unwrap()
+}
+
+/// Returns the key storing the entry for `key` inside the namespace `ns`,
+/// which is `NS_MARKER`, the length of `ns`, `ns` and then `key`.
+fn namespaced(e: &Env, ns: &Bytes, key: &Bytes) -> Bytes {
+    if ns.len() > MAX_NS_LEN {
+        panic_error!(e, Error::InvalidKey)
+    }
+
+    let mut full = Bytes::from_array(e, &[NS_MARKER, ns.len() as u8]);
+    full.append(ns);
+    full.append(key);
+    full
+}
+
+/// Enforces the claim policy of the namespace `ns` for `claimer` taking the
+/// unclaimed `key` inside it, charging the claim fee if there is one.
+fn check_ns_claim(e: &Env, ns: &Bytes, key: &Bytes, claimer: &Identifier) {
+    let namespace = namespace_of(e, ns);
+    check_key(e, key);
+    check_unbarred(e, claimer);
+    if e.data().has(DataKey::Burned(namespaced(e, ns, key))) {
+        panic_error!(e, Error::Burned)
+    }
+
+    if namespace.policy == symbol!("owner") && !same_id(claimer, &namespace.owner) {
+        panic_error!(e, Error::NotAllowed)
+    }
+    if namespace.policy == symbol!("fee") {
+        token_client(e).xfer_from(
+            &Signature::Invoker,
+            &BigInt::zero(e),
+            claimer,
+            &namespace.owner,
+            &namespace.fee,
+        );
+    }
+}
+
+fn offer_of(e: &Env, key: &Bytes, bidder: &Identifier) -> Option<BigInt> {
+    e.data()
+        .get(DataKey::Offer(OfferKey {
+            key: key.clone(),
+            bidder: bidder.clone(),
+        }))
+        .map(Result::unwrap)
+}
+
+fn entry_of(e: &Env, key: &Bytes) -> Entry {
+    Entry {
+        owner: owner_of(e, key),
+        expires: 0,
+        modified: modified_of(e, key),
+        locked: is_frozen(e, key) || is_locked(e, key),
+        version: version_of(e, key),
+    }
+}
+
+/// What becomes of a record of a key: `Aux` records are optional ones
+/// `purge_aux` deletes, `Key` records go when the key is deleted and `Core`
+/// records are those `delete_key` handles itself or keeps on purpose.
+#[derive(PartialEq)]
+enum Lifetime {
+    Aux,
+    Key,
+    Core,
+}
+
+/// Returns every `DataKey` record a key can have, with the category
+/// `footprint` counts it in and its lifetime. Records of new kinds belong
+/// here so that deleting, purging and sizing keys all see them.
+fn key_records(key: &Bytes) -> [(Symbol, Lifetime, DataKey); 34] {
+    let k = || key.clone();
+    [
+        (symbol!("owner"), Lifetime::Core, DataKey::Owner(k())),
+        (symbol!("owner"), Lifetime::Core, DataKey::Value(k())),
+        (symbol!("owner"), Lifetime::Core, DataKey::Version(k())),
+        (symbol!("owner"), Lifetime::Core, DataKey::Slot(k())),
+        (symbol!("owner"), Lifetime::Core, DataKey::OwnerSlot(k())),
+        (symbol!("owner"), Lifetime::Key, DataKey::Modified(k())),
+        (symbol!("metadata"), Lifetime::Aux, DataKey::Text(k())),
+        (symbol!("metadata"), Lifetime::Aux, DataKey::ClientVer(k())),
+        (symbol!("metadata"), Lifetime::Key, DataKey::Private(k())),
+        (symbol!("metadata"), Lifetime::Key, DataKey::SigOnly(k())),
+        (symbol!("metadata"), Lifetime::Key, DataKey::Gated(k())),
+        (symbol!("metadata"), Lifetime::Key, DataKey::Hook(k())),
+        (symbol!("metadata"), Lifetime::Key, DataKey::WriteOnce(k())),
+        (symbol!("metadata"), Lifetime::Key, DataKey::Linked(k())),
+        (symbol!("metadata"), Lifetime::Key, DataKey::Topic(k())),
+        (symbol!("approvals"), Lifetime::Key, DataKey::Grants(k())),
+        (symbol!("approvals"), Lifetime::Key, DataKey::Uses(k())),
+        (symbol!("approvals"), Lifetime::Key, DataKey::Spender(k())),
+        (symbol!("approvals"), Lifetime::Key, DataKey::Shares(k())),
+        (symbol!("approvals"), Lifetime::Key, DataKey::SoloUpd(k())),
+        (symbol!("approvals"), Lifetime::Key, DataKey::Approvals(k())),
+        (symbol!("history"), Lifetime::Aux, DataKey::History(k())),
+        (symbol!("history"), Lifetime::Key, DataKey::List(k())),
+        (symbol!("blob"), Lifetime::Aux, DataKey::Blob(k())),
+        (symbol!("blob"), Lifetime::Aux, DataKey::BlobType(k())),
+        (symbol!("other"), Lifetime::Key, DataKey::Lock(k())),
+        (symbol!("other"), Lifetime::Key, DataKey::Dispute(k())),
+        (symbol!("other"), Lifetime::Key, DataKey::Recovery(k())),
+        (symbol!("other"), Lifetime::Key, DataKey::Split(k())),
+        (symbol!("other"), Lifetime::Key, DataKey::Guardian(k())),
+        (symbol!("other"), Lifetime::Key, DataKey::Claimer(k())),
+        (symbol!("other"), Lifetime::Core, DataKey::Frozen(k())),
+        (symbol!("other"), Lifetime::Core, DataKey::Burned(k())),
+        (symbol!("other"), Lifetime::Core, DataKey::Subs(k())),
+    ]
+}
+
+/// Returns every `KeyExtra` record a key can have, like `key_records`.
+fn key_extras(key: &Bytes) -> [(Symbol, Lifetime, KeyExtra); 9] {
+    let k = || key.clone();
+    [
+        (symbol!("metadata"), Lifetime::Aux, KeyExtra::Memo(k())),
+        (symbol!("approvals"), Lifetime::Key, KeyExtra::Handoff(k())),
+        (symbol!("approvals"), Lifetime::Key, KeyExtra::Swap(k())),
+        (symbol!("other"), Lifetime::Key, KeyExtra::Guardians(k())),
+        (symbol!("other"), Lifetime::Core, KeyExtra::Rental(k())),
+        (symbol!("other"), Lifetime::Core, KeyExtra::Paused(k())),
+        (symbol!("other"), Lifetime::Core, KeyExtra::Tombstone(k())),
+        (symbol!("other"), Lifetime::Core, KeyExtra::Redeemed(k())),
+        (symbol!("history"), Lifetime::Core, KeyExtra::Owners(k())),
+    ]
+}
+
+/// Deletes the value of `key` and every record attached to it, publishing a
+/// `delete` event with the value it held. The version counter is kept so a
+/// version handed out before the deletion never becomes current again.
+fn delete_key(e: &Env, key: &Bytes) {
+    check_writable(e, key);
+    if e.data().has(KeyExtra::Rental(key.clone())) {
+        panic_error!(e, Error::ValueLocked)
+    }
+    for (_, lifetime, record) in key_records(key) {
+        if lifetime != Lifetime::Core {
+            e.data().remove(record);
+        }
+    }
+    for (_, lifetime, record) in key_extras(key) {
+        if lifetime != Lifetime::Core {
+            e.data().remove(record);
+        }
+    }
+    e.data().set(KeyExtra::Tombstone(key.clone()), now(e));
+
+    if let Some(value) = e.data().get::<_, Identifier>(DataKey::Value(key.clone())) {
+        emit(
+            e,
+            Level::Minimal,
+            (TOPIC_DELETE,),
+            (key.clone(), value.unwrap()),
+        );
+    }
+    if let Some(owner) = e.data().get(DataKey::Owner(key.clone())) {
+        move_key(e, key, Some(owner.unwrap()), None);
+        sync_hot(e, key, false);
+        update_stats(e, |stats| {
+            stats.removals += 1;
+            stats.active -= 1;
+        });
+    }
+    e.data().remove(DataKey::Owner(key.clone()));
+    e.data().remove(DataKey::Value(key.clone()));
+}
+
+/// Returns the records of the keys holding a value in `count` slots of the key
+/// index starting at `start`.
+fn records(e: &Env, start: u32, count: u32) -> Vec<Record> {
+    let mut records = Vec::new(e);
+    for key in keys_in(e, start, start.saturating_add(count)).iter() {
+        let key = key.unwrap();
+        if let Some(value) = e.data().get(DataKey::Value(key.clone())) {
+            records.push_back(Record {
+                owner: owner_of(e, &key),
+                value: value.unwrap(),
+                key,
+            });
+        }
+    }
+    records
+}
+
+/// Hashes the serialization of `records`, which `seal` and `ingest` compare to
+/// check that a handoff is complete and untampered.
+fn records_hash(e: &Env, records: &Vec<Record>) -> BytesN<32> {
+    e.compute_hash_sha256(&records.serialize(e))
+}
+
+/// The registry contract. Other contracts can call it through the generated
+/// `ExampleContractClient`, preferably with `get_opt` for reads as it never
+/// traps.
+///
+/// Contract function names are limited to 10 characters, so many of its
+/// functions go by abbreviations, such as `acpt_xfer` for accepting a
+/// proposed transfer or `set_tmode` for setting the time mode.
 pub struct ExampleContract;
 
-#[contractimpl]
-impl ExampleContract {
-    pub fn change_val(e: Env, key: Bytes, value: Identifier) {
-        let stored_addr = e
+#[contractimpl]
+impl ExampleContract {
+    /// Sets the administrator of the registry. Can only be called once.
+    pub fn initialize(e: Env, admin: Identifier) {
+        if e.data().has(DataKey::Admin) {
+            panic_error!(&e, Error::AlreadyInitialized)
+        }
+
+        e.data().set(DataKey::Admin, admin);
+    }
+
+    pub fn get_admin(e: Env) -> Identifier {
+        admin_of(&e)
+    }
+
+    /// Gives up the admin role for good, publishing a `renounced` event. The
+    /// current contract's identifier takes its place, which no invoker can
+    /// be, so admin-only functions trap with `NoAdmin` from then on and
+    /// `initialize` still traps with `AlreadyInitialized`. Admin only.
+    pub fn renounce(e: Env) {
+        require_admin(&e);
+        e.data()
+            .set(DataKey::Admin, Identifier::Contract(e.current_contract()));
+        e.data().remove(Setting::Proposed);
+        emit(&e, Level::Full, (TOPIC_RENOUNCED,), ());
+    }
+
+    /// Proposes `new_admin` as the next admin, who takes over once they call
+    /// `accept_adm`, and publishes a `proposed` event. A new proposal replaces
+    /// the previous one. Admin only.
+    pub fn set_admin(e: Env, new_admin: Identifier) {
+        require_admin(&e);
+        e.data().set(Setting::Proposed, new_admin.clone());
+        emit(&e, Level::Full, (TOPIC_PROPOSED,), new_admin);
+    }
+
+    /// Makes the invoker the admin if they were proposed with `set_admin`,
+    /// publishing an `accepted` event, or traps with `NotAuthorized`.
+    pub fn accept_adm(e: Env) {
+        let invoker = invoker_id(&e);
+        if e.data().get(Setting::Proposed) != Some(Ok(invoker.clone())) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+
+        e.data().set(DataKey::Admin, invoker.clone());
+        e.data().remove(Setting::Proposed);
+        emit(&e, Level::Full, (TOPIC_ACCEPTED,), invoker);
+    }
+
+    /// Blocks all writes until `unpause` is called. Admin only.
+    pub fn pause(e: Env) {
+        require_admin(&e);
+        update_settings(&e, |s| s.paused = true);
+    }
+
+    /// Lifts a `pause`. Traps with `Sealed` once the registry was sealed with
+    /// `seal` or is winding down. Admin only.
+    pub fn unpause(e: Env) {
+        require_admin(&e);
+        lift_pause(&e);
+    }
+
+    /// Sets the identifiers that can act for the admin together, `threshold`
+    /// of them being enough, through the functions taking their signatures
+    /// such as `q_pause`. The admin keeps its own powers. An empty `signers`
+    /// removes the quorum. Traps with `InvalidPolicy` if `threshold` is 0 or
+    /// above the number of signers. Admin only.
+    pub fn set_quorum(e: Env, signers: Vec<Identifier>, threshold: u32) {
+        require_admin(&e);
+        if signers.is_empty() {
+            update_settings(&e, |s| s.quorum = MaybeQuorum::None);
+            return;
+        }
+        if threshold == 0 || threshold > signers.len() {
+            panic_error!(&e, Error::InvalidPolicy)
+        }
+        update_settings(&e, |s| {
+            s.quorum = MaybeQuorum::Some(Quorum { signers, threshold })
+        });
+    }
+
+    /// Returns the quorum set with `set_quorum`, if any.
+    ///
+    /// Named `get_quorum` as a function called `quorum` would clash with the
+    /// `Quorum` type in the contract spec.
+    pub fn get_quorum(e: Env) -> Option<Quorum> {
+        settings_of(&e).quorum.into()
+    }
+
+    /// Pauses like `pause`, authorized by the quorum instead of the admin:
+    /// `sigs` are signatures over `(nonce,)` for the `pause` function name,
+    /// from at least the threshold of quorum signers. Traps with
+    /// `NotAuthorized` otherwise.
+    pub fn q_pause(e: Env, sigs: Vec<Signature>) {
+        require_quorum(&e, &sigs, symbol!("pause"));
+        update_settings(&e, |s| s.paused = true);
+    }
+
+    /// Lifts a pause like `unpause`, authorized by the quorum like `q_pause`
+    /// with signatures for the `unpause` function name.
+    pub fn q_unpause(e: Env, sigs: Vec<Signature>) {
+        require_quorum(&e, &sigs, symbol!("unpause"));
+        lift_pause(&e);
+    }
+
+    /// Sets the identifier `get_or` falls back to for unset keys. Admin only.
+    pub fn set_dflt(e: Env, id: Identifier) {
+        require_admin(&e);
+        update_settings(&e, |s| s.fallback = MaybeId::Some(id));
+    }
+
+    /// Removes the default set with `set_dflt`. Admin only.
+    pub fn clear_dflt(e: Env) {
+        require_admin(&e);
+        update_settings(&e, |s| s.fallback = MaybeId::None);
+    }
+
+    /// Limits new keys to `max` bytes, with `0` meaning no limit. Claims of
+    /// longer keys trap with `InvalidKey`. Keys claimed before the limit was
+    /// lowered stay readable and writable, so tightening it never strands a
+    /// key. Admin only.
+    pub fn set_maxlen(e: Env, max: u32) {
+        require_admin(&e);
+        update_settings(&e, |s| s.max_key = max);
+    }
+
+    /// Sets the bytes new keys can be made of: `none` accepts any byte,
+    /// `printable` printable ASCII and `dns_label` lowercase letters, digits
+    /// and hyphens, but not at either end. Claims of other keys trap with
+    /// `InvalidKey`. Like `set_maxlen`, this doesn't affect keys that are
+    /// already claimed. Traps with `InvalidMode` for other profiles. Admin
+    /// only.
+    pub fn set_prof(e: Env, profile: Symbol) {
+        require_admin(&e);
+        if profile != symbol!("none")
+            && profile != symbol!("printable")
+            && profile != symbol!("dns_label")
+        {
+            panic_error!(&e, Error::InvalidMode)
+        }
+        update_settings(&e, |s| s.profile = profile);
+    }
+
+    /// Turns event publishing on or off for deployments that don't index
+    /// events, like `set_verbos` with `full` or `off`. Events are on by
+    /// default. Admin only.
+    pub fn set_events(e: Env, enabled: bool) {
+        let verbosity = if enabled {
+            symbol!("full")
+        } else {
+            symbol!("off")
+        };
+        Self::set_verbos(e, verbosity);
+    }
+
+    /// Sets which events are published, to save their fees on deployments
+    /// that don't need them all: `off` publishes none, `minimal` those of
+    /// changes of ownership only and `full`, the default, also value updates,
+    /// nonces, metadata and admin actions. A `verbosity` event with the new
+    /// level is published whatever the level. Other levels trap with
+    /// `InvalidMode`. Admin only.
+    pub fn set_verbos(e: Env, verbosity: Symbol) {
+        require_admin(&e);
+        if verbosity != symbol!("off")
+            && verbosity != symbol!("minimal")
+            && verbosity != symbol!("full")
+        {
+            panic_error!(&e, Error::InvalidMode)
+        }
+
+        update_settings(&e, |s| s.verbosity = verbosity);
+        e.events().publish((TOPIC_VERBOSITY,), verbosity);
+    }
+
+    /// Sets `key` to `value`, claiming it for the invoker if it is unclaimed,
+    /// and returns the value it held before, or `None` if it had none.
+    pub fn change_val(e: Env, key: Bytes, value: Identifier) -> Option<Identifier> {
+        let invoker = invoker_id(&e);
+        let owner = enforce(&e, authorize_change(&e, &key, &invoker, false));
+        use_grant(&e, &key, &invoker);
+        write_val(&e, key, owner, value)
+    }
+
+    /// Like `change_val`, also storing `memo` with the new value for audit
+    /// trails. The memo stays until the value is written again, and memos
+    /// longer than `MAX_MEMO` bytes trap with `BlobTooLarge`.
+    pub fn memo_val(e: Env, key: Bytes, value: Identifier, memo: Bytes) -> Option<Identifier> {
+        if memo.len() > MAX_MEMO {
+            panic_error!(&e, Error::BlobTooLarge)
+        }
+
+        let previous = Self::change_val(e.clone(), key.clone(), value);
+        e.data().set(KeyExtra::Memo(key), memo);
+        previous
+    }
+
+    /// Like `change_val`, also forwarding `data` to the subscribers of `key`,
+    /// whose `on_data(key, value, data)` is called instead of `on_change`, so
+    /// writers can tell listeners why the value changed. Unlike a memo, `data`
+    /// isn't stored.
+    pub fn data_val(e: Env, key: Bytes, value: Identifier, data: Bytes) -> Option<Identifier> {
+        let invoker = invoker_id(&e);
+        let owner = enforce(&e, authorize_change(&e, &key, &invoker, false));
+        use_grant(&e, &key, &invoker);
+        write_entry(&e, key, owner, value, false, Some(data))
+    }
+
+    /// Returns the memo written with the current value of `key`, empty if it
+    /// was written without one. Traps like `get` otherwise.
+    pub fn get_memo(e: Env, key: Bytes) -> Bytes {
+        read_val(&e, &key);
+        e.data()
+            .get(KeyExtra::Memo(key))
+            .unwrap_or_else(|| Ok(Bytes::new(&e)))
+            .unwrap()
+    }
+
+    /// Returns whether `change_val` from `who` would get past its checks on
+    /// `key` right now, without writing anything and without trapping. Checks
+    /// on the value itself, such as `set_maxval`, aren't covered.
+    pub fn can_change(e: Env, key: Bytes, who: Identifier) -> bool {
+        authorize_change(&e, &key, &who, false).is_ok()
+    }
+
+    /// Returns whether `who` could claim the unclaimed `key` with
+    /// `change_val` right now, false if the key is already claimed.
+    pub fn can_claim(e: Env, key: Bytes, who: Identifier) -> bool {
+        !e.data().has(DataKey::Owner(key.clone()))
+            && authorize_change(&e, &key, &who, false).is_ok()
+    }
+
+    /// Claims the unclaimed `key` for the invoker without setting a value, so
+    /// that `get` traps with `NoValue` until the owner sets one with
+    /// `change_val`. Traps with `AlreadyExists` if the key is claimed.
+    pub fn hold_key(e: Env, key: Bytes) {
+        if e.data().has(DataKey::Owner(key.clone())) {
+            panic_error!(&e, Error::AlreadyExists)
+        }
+
+        let invoker_id = invoker_id(&e);
+        check_claim(&e, &key, &invoker_id);
+        touch(&e, &key, invoker_id.clone());
+        emit(&e, Level::Minimal, (TOPIC_HELD,), (key, invoker_id));
+    }
+
+    /// Claims `key` with `value` like a first write through `change_val`, but
+    /// traps with `AlreadyExists` instead of overwriting a key that already
+    /// holds a value, so registrations can be retried safely.
+    pub fn create_val(e: Env, key: Bytes, value: Identifier) {
+        if e.data().has(DataKey::Value(key.clone())) {
+            panic_error!(&e, Error::AlreadyExists)
+        }
+
+        Self::change_val(e, key, value);
+    }
+
+    /// Returns the value of `key` like `get`, first claiming it with `initial`
+    /// like `create_val` if it is unclaimed, so two clients initializing the
+    /// same key can't both write it. A key held with `hold_key` is left alone
+    /// and traps with `NoValue` like `get`, even for its holder, who sets the
+    /// value with `change_val`.
+    pub fn get_or_set(e: Env, key: Bytes, initial: Identifier) -> Identifier {
+        if !e.data().has(DataKey::Value(key.clone())) && !e.data().has(DataKey::Owner(key.clone()))
+        {
+            Self::change_val(e.clone(), key.clone(), initial);
+        }
+        read_val(&e, &key)
+    }
+
+    /// Claims `key` like `create_val`, deduplicated by `request_id` so retried
+    /// submissions are harmless: resubmitting the same request id with the
+    /// same arguments succeeds without doing anything, while reusing it with
+    /// other arguments traps with `RequestReused`.
+    pub fn claim_once(e: Env, key: Bytes, value: Identifier, request_id: BytesN<32>) {
+        let args = (key.clone(), value.clone(), invoker_id(&e)).serialize(&e);
+        let hash = e.compute_hash_sha256(&args);
+
+        if let Some(record) = e
+            .data()
+            .get::<_, (BytesN<32>, u64)>(DataKey::Request(request_id.clone()))
+        {
+            if record.unwrap().0 != hash {
+                panic_error!(&e, Error::RequestReused)
+            }
+            return;
+        }
+
+        e.data().set(DataKey::Request(request_id), (hash, now(&e)));
+        Self::create_val(e, key, value)
+    }
+
+    /// Sets `key` to `value` on behalf of the signer of `sig`, which covers
+    /// `(nonce, key, value)` where `nonce` is the signer's current `nonce` or
+    /// one reserved with `rsv_nonce`. Returns the value the key held before,
+    /// like `change_val`. For a key owned by an account, `sig` can carry the
+    /// signatures of several signers of the account, whose combined weight
+    /// must reach its medium threshold.
+    pub fn use_sig(
+        e: Env,
+        sig: Signature,
+        nonce: BigInt,
+        key: Bytes,
+        value: Identifier,
+    ) -> Option<Identifier> {
+        let args = change_args(&e, nonce.clone(), key.clone(), value.clone());
+        signed_write(&e, &sig, nonce, key, value, args)
+    }
+
+    /// Sets each of `keys` to the value at the same position in `values` like
+    /// `use_sig`, with one signature covering `(nonce, keys, values)` for the
+    /// whole batch, trapping with `LengthMismatch` if the two differ in
+    /// length. If the signer can't write one of the keys, the whole call traps
+    /// and nothing is written.
+    pub fn sig_batch(
+        e: Env,
+        sig: Signature,
+        keys: Vec<Bytes>,
+        values: Vec<Identifier>,
+        nonce: BigInt,
+    ) {
+        if keys.len() != values.len() {
+            panic_error!(&e, Error::LengthMismatch)
+        }
+        let args = nonce_args(&e, nonce.clone(), (keys.clone(), values.clone()));
+        verify_nonce_at(&e, &sig, symbol!("sig_batch"), nonce, args);
+
+        let signer = sig.identifier(&e);
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let key = key.unwrap();
+            let owner = enforce(&e, authorize_change(&e, &key, &signer, true));
+            use_grant(&e, &key, &signer);
+            write_val(&e, key, owner, value.unwrap());
+        }
+    }
+
+    /// Returns whether `use_sig` would accept `sig` for setting `key` to
+    /// `value`, with `nonce` as the nonce it was signed with, without writing
+    /// anything or consuming the nonce. A wrong nonce, a value `use_sig` would
+    /// refuse, a signer who can't write to the key or account signatures
+    /// short of the account's threshold return `false`, while a signature that
+    /// doesn't verify cryptographically still traps like in `check_sig`.
+    pub fn sim_sig(e: Env, sig: Signature, key: Bytes, value: Identifier, nonce: BigInt) -> bool {
+        let signer = sig.identifier(&e);
+        let sequential = nonce == nonce_of(&e, &signer);
+        if !sequential && !reserved_of(&e, &signer).contains_key(nonce.clone())
+            || valid_value(&e, &value).is_err()
+            || authorize_change(&e, &key, &signer, true).is_err()
+            || !well_formed(&e, &sig)
+        {
+            return false;
+        }
+
+        verify(
+            &e,
+            &sig,
+            symbol!("change"),
+            change_args(&e, nonce, key, value),
+        );
+        true
+    }
+
+    /// Like `use_sig`, but the signature covers `(nonce, hash)` instead of the
+    /// full payload, where `hash` is the sha256 of the serialized `(key, value)`
+    /// tuple. The contract recomputes the hash and traps with `PayloadMismatch`
+    /// if it doesn't match.
+    pub fn use_hashed(e: Env, sig: Signature, key: Bytes, value: Identifier, hash: BytesN<32>) {
+        let payload = (key.clone(), value.clone()).serialize(&e);
+        if e.compute_hash_sha256(&payload) != hash {
+            panic_error!(&e, Error::PayloadMismatch)
+        }
+
+        let nonce = nonce_of(&e, &sig.identifier(&e));
+        let args = nonce_args(&e, nonce.clone(), (hash,));
+        signed_write(&e, &sig, nonce, key, value, args);
+    }
+
+    pub fn get(e: Env, key: Bytes) -> Identifier {
+        read_val(&e, &key)
+    }
+
+    /// Like `change_val`, for keys named by a symbol. The symbol addresses the
+    /// key holding its characters as ASCII bytes, so keys written here can be
+    /// read with `get` and the other way around.
+    pub fn change_sym(e: Env, key: Symbol, value: Identifier) -> Option<Identifier> {
+        let key = sym_key(&e, key);
+        Self::change_val(e, key, value)
+    }
+
+    /// Like `get`, for keys named by a symbol, addressed as by `change_sym`.
+    pub fn get_sym(e: Env, key: Symbol) -> Identifier {
+        read_val(&e, &sym_key(&e, key))
+    }
+
+    /// Returns the value of `key`, even if it is private, to whoever the
+    /// signer of `sig` lets read it. The signature covers `(nonce, key)` with
+    /// the signer's current nonce, which isn't consumed, so the read can be
+    /// simulated for free and the signature stops working once the signer
+    /// uses their nonce. The signer must be the owner or hold a permission on
+    /// the key, or the call traps with `NotAuthorized`.
+    pub fn verify_get(e: Env, sig: Signature, key: Bytes) -> Identifier {
+        let signer = sig.identifier(&e);
+        if !has_perm(&e, &key, &signer, Permission::Update)
+            && !grants_of(&e, &key).contains_key(signer.clone())
+        {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+
+        verify(
+            &e,
+            &sig,
+            symbol!("read"),
+            (nonce_of(&e, &signer), key.clone()),
+        );
+        stored_val(&e, &key)
+    }
+
+    /// Returns the value of `key`, or `None` where `get` would trap: if the key
+    /// has no value, or is private and the invoker isn't its owner. Meant for
+    /// other contracts, which a missing key would otherwise take down.
+    pub fn get_opt(e: Env, key: Bytes) -> Option<Identifier> {
+        let value = e.data().get(DataKey::Value(key.clone()))?.unwrap();
+        if is_private(&e, &key) && !same_id(&owner_of(&e, &key), &invoker_id(&e)) {
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Returns the value of `key`, or if it has none, the wildcard its owner
+    /// set with `set_wild` for keys claimed without a value, and then the
+    /// default set with `set_dflt`. Traps with `KeyNotFound` if there is
+    /// none of them. Unlike
+    /// `get`, integrations routing payments through it never hit a missing key
+    /// once a default is configured.
+    pub fn get_or(e: Env, key: Bytes) -> Identifier {
+        if e.data().has(DataKey::Value(key.clone())) {
+            return read_val(&e, &key);
+        }
+        if let Some(Ok(owner)) = e.data().get::<_, Identifier>(DataKey::Owner(key)) {
+            if let Some(value) = e.data().get(KeyExtra::Wildcard(owner)) {
+                return value.unwrap();
+            }
+        }
+
+        Option::from(settings_of(&e).fallback)
+            .unwrap_or_else(|| panic_error!(&e, Error::KeyNotFound))
+    }
+
+    /// Returns the value of `key` together with its owner, trapping like `get`
+    /// for unset keys.
+    pub fn get_pair(e: Env, key: Bytes) -> (Identifier, Identifier) {
+        (read_val(&e, &key), owner_of(&e, &key))
+    }
+
+    /// Returns the identifier writes to `key` are authorized against, exactly
+    /// as `change_val` sees it: the owner of the key, or `dflt_owner` if the
+    /// key is unclaimed, in which case the next write claims it. Unlike
+    /// `get`, this never traps.
+    pub fn get_owner(e: Env, key: Bytes) -> Identifier {
+        let owner = owner_of(&e, &key);
+        if owner == unclaimed(&e) {
+            return default_owner(&e);
+        }
+        owner
+    }
+
+    /// Returns the owner `key` had at version `version` of its entry, as
+    /// counted by `get_fresh`. The owners of the `OWNER_HISTORY` latest
+    /// versions are kept, even across removals of the key, and the current
+    /// version is always answered. Older versions fail with `NoHistory` and versions
+    /// the key hasn't reached yet with `KeyNotFound`.
+    pub fn owner_at(e: Env, key: Bytes, version: u32) -> Result<Identifier, Error> {
+        let current = version_of(&e, &key);
+        if version > current || version == 0 {
+            return Err(Error::KeyNotFound);
+        }
+        for owner in owners_of(&e, &key).iter() {
+            let owner = owner.unwrap();
+            if owner.version == version {
+                return Ok(owner.owner);
+            }
+        }
+        if version == current {
+            return Ok(owner_of(&e, &key));
+        }
+        Err(Error::NoHistory)
+    }
+
+    /// Returns the value of `key` together with its version counter, for
+    /// caches to later validate with `is_fresh`, or `None` for unset keys.
+    pub fn get_fresh(e: Env, key: Bytes) -> Option<(Identifier, u32)> {
+        if !e.data().has(DataKey::Value(key.clone())) {
+            return None;
+        }
+        Some((read_val(&e, &key), version_of(&e, &key)))
+    }
+
+    /// Returns whether `version` is still the current version of `key`, false
+    /// once the key was updated, transferred or removed.
+    pub fn is_fresh(e: Env, key: Bytes, version: u32) -> bool {
+        e.data().has(DataKey::Value(key.clone())) && version_of(&e, &key) == version
+    }
+
+    /// Appends `value` to the list stored under `key`, claiming the key for the
+    /// invoker if it is unset.
+    pub fn push_val(e: Env, key: Bytes, value: Identifier) {
+        let stored_addr = owner_of(&e, &key);
+        let invoker_id = invoker_id(&e);
+
+        let owner = if stored_addr == unclaimed(&e) {
+            check_claim(&e, &key, &invoker_id);
+            invoker_id
+        } else if co_owned_write(&e, &key, &invoker_id)
+            || has_perm(&e, &key, &invoker_id, Permission::Update)
+        {
+            check_unbarred(&e, &invoker_id);
+            check_invoker_write(&e, &key);
+            use_grant(&e, &key, &invoker_id);
+            stored_addr
+        } else {
+            panic!("you are not allowed to change this value")
+        };
+
+        let mut list = list_of(&e, &key);
+        list.push_back(value);
+
+        touch(&e, &key, owner);
+        e.data().set(DataKey::List(key), list);
+    }
+
+    /// Removes and returns the last identifier pushed to the list under `key`.
+    pub fn pop_val(e: Env, key: Bytes) -> Identifier {
+        require_perm(&e, &key, Permission::Update);
+        check_invoker_write(&e, &key);
+
+        let mut list = list_of(&e, &key);
+        let value = list
+            .pop_back()
+            .unwrap_or_else(|| panic_error!(&e, Error::EmptyList))
+            .unwrap();
+
+        touch(&e, &key, owner_of(&e, &key));
+        e.data().set(DataKey::List(key), list);
+
+        value
+    }
+
+    /// Releases `key`, deleting its value, list and ownership records so it can
+    /// be claimed again, and publishes a `delete` event with the key and the
+    /// value it held. Only the owner can remove a key.
+    pub fn remove_val(e: Env, key: Bytes) {
+        require_owner(&e, &key);
+        delete_key(&e, &key);
+    }
+
+    /// Removes `key` like `remove_val` regardless of who owns it, for
+    /// moderation. Admin only.
+    pub fn admin_del(e: Env, key: Bytes) {
+        require_admin(&e);
+        delete_key(&e, &key);
+    }
+
+    /// Removes every key in `keys` like `remove_val`. Keys that are already
+    /// unset are skipped. If the invoker doesn't own one of the other keys the
+    /// whole call traps and nothing is removed.
+    pub fn del_vals(e: Env, keys: Vec<Bytes>) {
+        let invoker = invoker_id(&e);
+        let mut owned = Vec::new(&e);
+        for key in keys.iter() {
+            let key = key.unwrap();
+            if e.data().has(DataKey::Owner(key.clone())) {
+                require_owned_by(&e, &key, &invoker);
+                owned.push_back(key);
+            }
+        }
+
+        for key in owned.iter() {
+            delete_key(&e, &key.unwrap());
+        }
+    }
+
+    /// Blocks writes to `key` with `Paused` until `resume_key`, like `pause`
+    /// does for the whole registry, such as while moderators review it.
+    /// Reads keep working. Admin only.
+    pub fn pause_key(e: Env, key: Bytes) {
+        require_admin(&e);
+        e.data().set(KeyExtra::Paused(key), ());
+    }
+
+    /// Lifts a `pause_key`. Admin only.
+    pub fn resume_key(e: Env, key: Bytes) {
+        require_admin(&e);
+        e.data().remove(KeyExtra::Paused(key));
+    }
+
+    /// Permanently blocks further writes to `key`. Only the owner can freeze.
+    pub fn freeze(e: Env, key: Bytes) {
+        require_owner(&e, &key);
+        e.data().set(DataKey::Frozen(key), true);
+    }
+
+    /// Sets the value of `key` like `change_val` and freezes it in the same
+    /// call, so nothing can change the value in between. Only the owner can
+    /// finalize a key.
+    pub fn set_final(e: Env, key: Bytes, value: Identifier) {
+        require_owner(&e, &key);
+        Self::change_val(e.clone(), key.clone(), value);
+        e.data().set(DataKey::Frozen(key), true);
+    }
+
+    /// Blocks writes to `key` until the ledger sequence reaches `until`, or
+    /// the ledger timestamp in the `time` mode, after which writes resume
+    /// without further action. Only the owner can lock, and a new lock
+    /// replaces the previous one.
+    pub fn lock_for(e: Env, key: Bytes, until: u64) {
+        require_owner(&e, &key);
+        e.data().set(DataKey::Lock(key), until);
+    }
+
+    /// Toggles whether the value of `key` mirrors its owner. While linked, the
+    /// value is set to the owner right away and again on every transfer, as
+    /// it was when values doubled as owners. Only the owner can toggle it.
+    pub fn link_owner(e: Env, key: Bytes, linked: bool) {
+        require_owner(&e, &key);
+        if linked {
+            e.data().set(DataKey::Linked(key.clone()), true);
+            follow_owner(&e, &key, owner_of(&e, &key));
+        } else {
+            e.data().remove(DataKey::Linked(key));
+        }
+    }
+
+    /// Toggles whether updates to `key` have to go through `use_sig`. While
+    /// set, `change_val`, `push_val`, `pop_val` and `restore` trap with
+    /// `SignatureRequired`. Only the owner can toggle it.
+    pub fn sig_only(e: Env, key: Bytes, required: bool) {
+        require_owner(&e, &key);
+        if required {
+            e.data().set(DataKey::SigOnly(key), true);
+        } else {
+            e.data().remove(DataKey::SigOnly(key));
+        }
+    }
+
+    /// Toggles whether `key` can be read by anyone other than its owner.
+    pub fn set_priv(e: Env, key: Bytes, private: bool) {
+        require_owner(&e, &key);
+        e.data().set(DataKey::Private(key), private);
+    }
+
+    /// Returns the value, owner, last modification ledger and flags of `key`.
+    ///
+    /// Traps like `get` for unset keys rather than returning a default-populated
+    /// struct, so a `KeyMeta` always describes a key that holds a value.
+    pub fn get_meta(e: Env, key: Bytes) -> KeyMeta {
+        KeyMeta {
+            value: read_val(&e, &key),
+            owner: owner_of(&e, &key),
+            modified: modified_of(&e, &key),
+            frozen: is_frozen(&e, &key),
+            private: is_private(&e, &key),
+        }
+    }
+
+    /// Assembles the ownership record of `key` from its underlying records.
+    ///
+    /// Named `get_entry` as a function called `entry` would clash with the
+    /// `Entry` type in the contract spec.
+    pub fn get_entry(e: Env, key: Bytes) -> Result<Entry, Error> {
+        check_present(&e, &key)?;
+        Ok(entry_of(&e, &key))
+    }
+
+    /// Returns the value of `key` only if its entry is healthy, failing with
+    /// `Burned` or `KeyNotFound` like `get_entry`, `Disputed` while the key is
+    /// disputed and `NotAllowed` if the value was blocked with `block_val`.
+    /// Private keys stay readable by their owner only, like with `get`.
+    pub fn get_strict(e: Env, key: Bytes) -> Result<Identifier, Error> {
+        check_present(&e, &key)?;
+        if is_disputed(&e, &key) {
+            return Err(Error::Disputed);
+        }
+        if value_blocked(&e, &key) {
+            return Err(Error::NotAllowed);
+        }
+
+        Ok(read_val(&e, &key))
+    }
+
+    /// Returns who claimed `key` and the ledger sequence of the claim, which
+    /// stay the same across transfers. Removing the key forgets them, so a
+    /// key claimed again reports its new claimer. Traps with `KeyNotFound` if
+    /// the key is unclaimed.
+    pub fn claimer_of(e: Env, key: Bytes) -> (Identifier, u32) {
+        e.data()
+            .get(DataKey::Claimer(key))
+            .unwrap_or_else(|| panic_error!(&e, Error::KeyNotFound))
+            .unwrap()
+    }
+
+    /// Returns a 32-byte commitment to the entry of `key` that light clients
+    /// can compare with what an indexer serves, also found at the end of the
+    /// `set` events of the key. Traps with `KeyNotFound` if the key is
+    /// unclaimed.
+    ///
+    /// The hash is the sha256 of the XDR encoded `ScVal` of the vector
+    /// `[contract id, key, owner, version, expiry]`, where the contract id and
+    /// the key are `Bytes` objects, the owner is the `Identifier` as contract
+    /// types encode enums, a vector of the variant name as a `Symbol` followed
+    /// by its value, and the version and expiry are `U32`s. The expiry is
+    /// always `0`, as keys don't expire.
+    pub fn entry_hash(e: Env, key: Bytes) -> BytesN<32> {
+        if !e.data().has(DataKey::Owner(key.clone())) {
+            panic_error!(&e, Error::KeyNotFound)
+        }
+        hash_entry(&e, &key)
+    }
+
+    /// Returns the number of ledgers since `key` was claimed, as recorded for
+    /// `claimer_of`. Traps with `KeyNotFound` if the key is unclaimed.
+    pub fn key_age(e: Env, key: Bytes) -> u32 {
+        let (_, ledger) = Self::claimer_of(e.clone(), key);
+        e.ledger().sequence() - ledger
+    }
+
+    /// Returns `key` in the format it is stored in, for tooling to tell
+    /// records left on the previous layout apart, or `None` if it is stored in
+    /// neither.
+    pub fn get_stored(e: Env, key: Bytes) -> Option<StoredEntry> {
+        if e.data().has(DataKey::Value(key.clone())) {
+            return Some(StoredEntry::V1(entry_of(&e, &key)));
+        }
+        e.data()
+            .get(key)
+            .map(|value| StoredEntry::V0(value.unwrap()))
+    }
+
+    /// Upgrades the `V0` record of `key` to the current layout, owned by the
+    /// identifier it holds. Anyone can migrate a key, as this doesn't change
+    /// who controls it. Traps with `KeyNotFound` if there is no `V0` record.
+    pub fn migrate(e: Env, key: Bytes) {
+        let value: Identifier = e
+            .data()
+            .get(key.clone())
+            .unwrap_or_else(|| panic_error!(&e, Error::KeyNotFound))
+            .unwrap();
+
+        e.data().remove(key.clone());
+        write_val(&e, key, value.clone(), value);
+    }
+
+    /// Commits to claiming a key without revealing it, where `hash` is the
+    /// sha256 of the key bytes followed by a secret salt.
+    pub fn commit(e: Env, hash: BytesN<32>) {
+        if commitment(&e, &hash).is_some() {
+            panic_error!(&e, Error::CommitExists)
+        }
+
+        e.data()
+            .set(DataKey::Commit(hash), (invoker_id(&e), now(&e)));
+    }
+
+    /// Claims `key` for the committer of `sha256(key || salt)`, as long as the
+    /// commitment is still within the reveal window and the key is unset.
+    pub fn reveal(e: Env, key: Bytes, value: Identifier, salt: Bytes) {
+        let mut preimage = key.clone();
+        preimage.append(&salt);
+        let hash = e.compute_hash_sha256(&preimage);
+
+        let (committer, since) =
+            commitment(&e, &hash).unwrap_or_else(|| panic_error!(&e, Error::NoCommitment));
+
+        if !same_id(&committer, &invoker_id(&e)) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        if expired(&e, since + u64::from(REVEAL_WINDOW)) {
+            panic_error!(&e, Error::CommitExpired)
+        }
+        if owner_of(&e, &key) != unclaimed(&e) {
+            panic!("you are not allowed to change this value")
+        }
+        check_claim(&e, &key, &committer);
+
+        e.data().remove(DataKey::Commit(hash));
+        write_val(&e, key, committer, value);
+    }
+
+    /// Returns the committer of `hash` and when the commitment landed, read
+    /// according to `time_mode`.
+    pub fn commit_of(e: Env, hash: BytesN<32>) -> Option<(Identifier, u64)> {
+        commitment(&e, &hash)
+    }
+
+    /// Deletes a commitment. Only the original committer can cancel it.
+    pub fn cancel_cmt(e: Env, hash: BytesN<32>) {
+        let (committer, _) =
+            commitment(&e, &hash).unwrap_or_else(|| panic_error!(&e, Error::NoCommitment));
+
+        if !same_id(&committer, &invoker_id(&e)) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+
+        e.data().remove(DataKey::Commit(hash));
+    }
+
+    /// Deletes a commitment that is older than `REVEAL_WINDOW`. Anyone can
+    /// sweep, so abandoned commitments don't accumulate in storage.
+    pub fn sweep_cmt(e: Env, hash: BytesN<32>) {
+        let (_, since) =
+            commitment(&e, &hash).unwrap_or_else(|| panic_error!(&e, Error::NoCommitment));
+
+        if !expired(&e, since + u64::from(REVEAL_WINDOW)) {
+            panic_error!(&e, Error::CommitPending)
+        }
+
+        e.data().remove(DataKey::Commit(hash));
+    }
+
+    /// Sets the token used to escrow offers. Admin only.
+    pub fn set_token(e: Env, token: BytesN<32>) {
+        require_admin(&e);
+        update_settings(&e, |s| s.token = MaybeToken::Some(token));
+    }
+
+    /// Requires claimers to hold at least `amount` of `token` when claiming a
+    /// key, trapping with `NotAllowed` otherwise. Nothing is transferred, the
+    /// balance is only read. An `amount` of zero or less lifts the
+    /// requirement. Admin only.
+    pub fn set_minbal(e: Env, token: BytesN<32>, amount: BigInt) {
+        require_admin(&e);
+        if amount <= 0 {
+            update_settings(&e, |s| s.min_bal = MaybeMin::None);
+        } else {
+            update_settings(&e, |s| s.min_bal = MaybeMin::Some(MinBal { token, amount }));
+        }
+    }
+
+    /// Offers `amount` tokens for `key`, escrowing them in the contract. The
+    /// bidder must have approved the contract to spend `amount` beforehand.
+    pub fn make_offer(e: Env, key: Bytes, amount: BigInt) {
+        if settings_of(&e).stage != symbol!("live") {
+            panic_error!(&e, Error::Sealed)
+        }
+        if owner_of(&e, &key) == unclaimed(&e) {
+            panic_error!(&e, Error::KeyNotFound)
+        }
+
+        let bidder = invoker_id(&e);
+        if offer_of(&e, &key, &bidder).is_some() {
+            panic_error!(&e, Error::OfferExists)
+        }
+
+        token_client(&e).xfer_from(
+            &Signature::Invoker,
+            &BigInt::zero(&e),
+            &bidder,
+            &Identifier::Contract(e.current_contract()),
+            &amount,
+        );
+        e.data()
+            .set(DataKey::Offer(OfferKey { key, bidder }), amount);
+        update_stats(&e, |stats| stats.offers += 1);
+    }
+
+    /// Accepts the offer `bidder` made for `key`: ownership moves to the bidder
+    /// and the escrowed tokens are paid out to the current owner.
+    pub fn take_offer(e: Env, key: Bytes, bidder: Identifier) {
+        require_owner(&e, &key);
+        let owner = owner_of(&e, &key);
+
+        let amount =
+            offer_of(&e, &key, &bidder).unwrap_or_else(|| panic_error!(&e, Error::NoOffer));
+        e.data().remove(DataKey::Offer(OfferKey {
+            key: key.clone(),
+            bidder: bidder.clone(),
+        }));
+        update_stats(&e, |stats| stats.offers -= 1);
+
+        transfer_key(&e, &key, bidder);
+        token_client(&e).xfer(&Signature::Invoker, &BigInt::zero(&e), &owner, &amount);
+    }
+
+    /// Withdraws the invoker's offer for `key` and refunds the escrow.
+    pub fn drop_offer(e: Env, key: Bytes) {
+        let bidder = invoker_id(&e);
+        let amount =
+            offer_of(&e, &key, &bidder).unwrap_or_else(|| panic_error!(&e, Error::NoOffer));
+        e.data().remove(DataKey::Offer(OfferKey {
+            key,
+            bidder: bidder.clone(),
+        }));
+        update_stats(&e, |stats| stats.offers -= 1);
+
+        token_client(&e).xfer(&Signature::Invoker, &BigInt::zero(&e), &bidder, &amount);
+    }
+
+    /// Starts shutting the registry down for good: writes are paused, `unpause`
+    /// and `make_offer` trap with `Sealed`, and the escrowed offers are to be
+    /// refunded with `refund` before `terminate` ends the registry. Admin
+    /// only.
+    pub fn wind_down(e: Env) {
+        require_admin(&e);
+        update_settings(&e, |s| {
+            s.paused = true;
+            s.stage = symbol!("winding");
+        });
+    }
+
+    /// Refunds the escrowed `offers` to their bidders while the registry winds
+    /// down, so that a keeper can push refunds out in chunks. Offers already
+    /// refunded or withdrawn are skipped. Anyone can refund. Traps with
+    /// `InvalidPhase` unless `wind_down` was called.
+    pub fn refund(e: Env, offers: Vec<OfferKey>) {
+        if settings_of(&e).stage != symbol!("winding") {
+            panic_error!(&e, Error::InvalidPhase)
+        }
+
+        for offer in offers.iter() {
+            let offer = offer.unwrap();
+            let amount = match offer_of(&e, &offer.key, &offer.bidder) {
+                Some(amount) => amount,
+                None => continue,
+            };
+            e.data().remove(DataKey::Offer(offer.clone()));
+            update_stats(&e, |stats| stats.offers -= 1);
+            token_client(&e).xfer(
+                &Signature::Invoker,
+                &BigInt::zero(&e),
+                &offer.bidder,
+                &amount,
+            );
+        }
+    }
+
+    /// Ends a registry wound down with `wind_down` once every offer was
+    /// refunded, trapping with `OfferExists` before that and with
+    /// `InvalidPhase` if it isn't winding down. From then on reads keep
+    /// working, while writes and every owner and admin operation trap with
+    /// `Sealed`, for good. Publishes an `ended` event. Admin only.
+    pub fn terminate(e: Env) {
+        require_admin(&e);
+        if settings_of(&e).stage != symbol!("winding") {
+            panic_error!(&e, Error::InvalidPhase)
+        }
+        if stats_of(&e).offers != 0 {
+            panic_error!(&e, Error::OfferExists)
+        }
+
+        update_settings(&e, |s| s.stage = symbol!("ended"));
+        emit(&e, Level::Full, (TOPIC_ENDED,), ());
+    }
+
+    /// Returns the values previously held by `key`, oldest first.
+    pub fn history(e: Env, key: Bytes) -> Vec<Identifier> {
+        history_of(&e, &key)
+    }
+
+    /// Empties the value history of `key` to reclaim its storage, leaving the
+    /// current value and owner as they are, and publishes a `hist_clear`
+    /// event with the key. Owner only.
+    pub fn clear_hist(e: Env, key: Bytes) {
+        require_owner(&e, &key);
+        check_writable(&e, &key);
+
+        e.data().remove(DataKey::History(key.clone()));
+        emit(&e, Level::Full, (TOPIC_HIST_CLEAR,), key);
+    }
+
+    /// Undoes the most recent change to `key`, writing back the last value from
+    /// its history. The write goes through the same checks and notifications
+    /// as `change_val`, so write-once keys trap with `ValueLocked`, but the
+    /// value it replaces is dropped instead of added to the history. Requires
+    /// `Update`.
+    pub fn restore(e: Env, key: Bytes) {
+        require_perm(&e, &key, Permission::Update);
+        check_invoker_write(&e, &key);
+
+        let mut history = history_of(&e, &key);
+        let previous = history
+            .pop_back()
+            .unwrap_or_else(|| panic_error!(&e, Error::NoHistory))
+            .unwrap();
+
+        write_val(&e, key.clone(), owner_of(&e, &key), previous);
+        e.data().set(DataKey::History(key), history);
+    }
+
+    /// Sets the registration phase: `closed` rejects all new claims,
+    /// `allowlist` only accepts claims from allowed identifiers and `open`
+    /// accepts everyone. Admin only.
+    pub fn set_phase(e: Env, phase: Symbol) {
+        require_admin(&e);
+        if phase != symbol!("closed") && phase != symbol!("allowlist") && phase != symbol!("open") {
+            panic_error!(&e, Error::InvalidPhase)
+        }
+
+        update_settings(&e, |s| s.phase = phase);
+    }
+
+    pub fn allow(e: Env, id: Identifier) {
+        require_admin(&e);
+        e.data().set(DataKey::Allowed(id), true);
+    }
+
+    pub fn disallow(e: Env, id: Identifier) {
+        require_admin(&e);
+        e.data().remove(DataKey::Allowed(id));
+    }
+
+    /// Returns the live keys in the `limit` slots of the key index starting at
+    /// `start`, so clients can page through large registries by advancing
+    /// `start` by `limit` until it reaches `total_keys`.
+    ///
+    /// The index is append-only: removed keys leave their slot behind, so a
+    /// page can hold fewer than `limit` keys, but slots never move and a walk
+    /// never skips or repeats a live key, even while keys are being removed.
+    pub fn keys_page(e: Env, start: u32, limit: u32) -> Vec<Bytes> {
+        let mut page = Vec::new(&e);
+        for key in keys_in(&e, start, start.saturating_add(limit)).iter() {
+            let key = key.unwrap();
+            if e.data().has(DataKey::Owner(key.clone())) {
+                page.push_back(key);
+            }
+        }
+        page
+    }
+
+    /// Returns the live keys starting with `prefix` in the `limit` slots of
+    /// the key index starting at `start`, like `keys_page`. `limit` is capped
+    /// at `MAX_SCAN` to bound the work of a call, so clients continue a search
+    /// by advancing `start` by the capped limit until it reaches `total_keys`.
+    pub fn find_keys(e: Env, prefix: Bytes, start: u32, limit: u32) -> Vec<Bytes> {
+        let mut found = Vec::new(&e);
+        for key in keys_in(&e, start, start.saturating_add(limit.min(MAX_SCAN))).iter() {
+            let key = key.unwrap();
+            if starts_with(&key, &prefix) && e.data().has(DataKey::Owner(key.clone())) {
+                found.push_back(key);
+            }
+        }
+        found
+    }
+
+    /// Returns every key holding a value paired with its value, in the order
+    /// of the key index, leaving out private keys the invoker doesn't own.
+    /// This reads the whole registry in one call, so it only fits the read
+    /// budget of small registries; others page through `export` or
+    /// `keys_page` instead.
+    pub fn get_all(e: Env) -> Vec<(Bytes, Identifier)> {
+        let invoker = invoker_id(&e);
+        let mut pairs = Vec::new(&e);
+        for key in keys_of(&e).iter() {
+            let key = key.unwrap();
+            if let Some(value) = e.data().get(DataKey::Value(key.clone())) {
+                if !is_private(&e, &key) || same_id(&owner_of(&e, &key), &invoker) {
+                    pairs.push_back((key, value.unwrap()));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Returns the number of slots in the key index, including the slots of
+    /// removed keys.
+    pub fn total_keys(e: Env) -> u32 {
+        slot_count(&e)
+    }
+
+    /// Returns the key in slot `index` of the key index, trapping with
+    /// `KeyNotFound` if the slot doesn't exist or its key was removed.
+    pub fn key_at(e: Env, index: u32) -> Bytes {
+        let key = key_in_slot(&e, index).unwrap_or_else(|| panic_error!(&e, Error::KeyNotFound));
+        if !e.data().has(DataKey::Owner(key.clone())) {
+            panic_error!(&e, Error::KeyNotFound)
+        }
+        key
+    }
+
+    /// Returns the nonce the next signature of `id` has to be bound to.
+    pub fn nonce(e: Env, id: Identifier) -> BigInt {
+        nonce_of(&e, &id)
+    }
+
+    /// Returns the state signatures of `who` depend on, in one call.
+    pub fn sig_state(e: Env, who: Identifier) -> SignerState {
+        SignerState {
+            nonce: nonce_of(&e, &who),
+            blocked: e.data().has(Setting::Barred(who)),
+            ledger: e.ledger().sequence(),
+        }
+    }
+
+    /// Consumes the signer's current nonce, invalidating every outstanding
+    /// signature bound to it. `sig` covers `(nonce,)`; for invoker signatures
+    /// the nonce is bumped all the same.
+    ///
+    /// Takes a signature so that identifiers which can't invoke, like ed25519
+    /// keys, can revoke too.
+    pub fn revoke(e: Env, sig: Signature) {
+        verify_nonce(&e, &sig, symbol!("revoke"), ());
+
+        if sig == Signature::Invoker {
+            bump_nonce(&e, sig.identifier(&e));
+        }
+    }
+
+    /// Returns whether `key` is claimed and owned by `id`, so other contracts
+    /// can gate on registry ownership. Never traps, including for unset keys.
+    pub fn is_owner(e: Env, key: Bytes, id: Identifier) -> bool {
+        e.data().has(DataKey::Owner(key.clone())) && same_id(&owner_of(&e, &key), &id)
+    }
+
+    /// Returns whether `owner` holds `key`. The same check as `is_owner`, with
+    /// the owner first for owner-centric clients like dashboards.
+    pub fn owns(e: Env, owner: Identifier, key: Bytes) -> bool {
+        Self::is_owner(e, key, owner)
+    }
+
+    /// Reserves every key starting with `prefix` for the admin. Overlapping
+    /// prefixes are kept and checked independently. Admin only.
+    pub fn reserve(e: Env, prefix: Bytes) {
+        require_admin(&e);
+
+        let mut prefixes = prefixes_of(&e);
+        if !prefixes.contains(&prefix) {
+            prefixes.push_back(prefix);
+            e.data().set(Setting::Prefixes, prefixes);
+        }
+    }
+
+    /// Lifts a reservation made with `reserve`. Keys already claimed under the
+    /// prefix keep their owner. Admin only.
+    pub fn release(e: Env, prefix: Bytes) {
+        require_admin(&e);
+
+        let mut prefixes = prefixes_of(&e);
+        if let Some(i) = prefixes.first_index_of(&prefix) {
+            prefixes.remove(i);
+            e.data().set(Setting::Prefixes, prefixes);
+        }
+    }
+
+    /// Retires `key` for good: its value and records are deleted and a
+    /// tombstone is left behind, so the key can never be claimed again and
+    /// `get` traps with `Burned` instead of reporting a missing key. The owner
+    /// can burn their key and the admin can burn any key.
+    pub fn burn(e: Env, key: Bytes) {
+        if !is_admin(&e, &invoker_id(&e)) {
+            require_owner(&e, &key);
+        }
+
+        delete_key(&e, &key);
+        e.data().set(DataKey::Burned(key), true);
+    }
+
+    /// Grants `who` the permissions in `perms` on `key`, on top of any it
+    /// already holds. Only the owner can grant, and grants are wiped when the
+    /// key changes hands.
+    pub fn grant(e: Env, key: Bytes, who: Identifier, perms: Vec<Permission>) {
+        require_owner(&e, &key);
+
+        let mut grants = grants_of(&e, &key);
+        let max = settings_of(&e).max_dlg;
+        if max != 0 && !grants.contains_key(who.clone()) && grants.len() >= max {
+            panic_error!(&e, Error::NotAllowed)
+        }
+        let mut held = grants
+            .get(who.clone())
+            .unwrap_or_else(|| Ok(Vec::new(&e)))
+            .unwrap();
+        for perm in perms.iter() {
+            let perm = perm.unwrap();
+            if !held.contains(perm) {
+                held.push_back(perm);
+            }
+        }
+
+        grants.set(who, held);
+        e.data().set(DataKey::Grants(key), grants);
+    }
+
+    /// Takes `perm` on `key` away from `who`, along with the limit set with
+    /// `set_uses` once `who` holds no permission on it. Only the owner can
+    /// revoke.
+    pub fn ungrant(e: Env, key: Bytes, who: Identifier, perm: Permission) {
+        require_owner(&e, &key);
+
+        let mut grants = grants_of(&e, &key);
+        if let Some(held) = grants.get(who.clone()) {
+            let mut held = held.unwrap();
+            if let Some(i) = held.first_index_of(perm) {
+                held.remove(i);
+            }
+
+            if held.is_empty() {
+                grants.remove(who.clone());
+                let mut uses = uses_of(&e, &key);
+                if uses.contains_key(who.clone()) {
+                    uses.remove(who);
+                    e.data().set(DataKey::Uses(key.clone()), uses);
+                }
+            } else {
+                grants.set(who, held);
+            }
+            e.data().set(DataKey::Grants(key), grants);
+        }
+    }
+
+    /// Limits the grants of `editor` on `key` to `uses` more changes, after
+    /// which they stop working, until the owner tops them up by calling this
+    /// again. Changes by the editor through any of its permissions count, and
+    /// 0 suspends its grants right away. Only the owner can limit grants.
+    pub fn set_uses(e: Env, key: Bytes, editor: Identifier, uses: u32) {
+        require_owner(&e, &key);
+        let mut limits = uses_of(&e, &key);
+        limits.set(editor, uses);
+        e.data().set(DataKey::Uses(key), limits);
+    }
+
+    /// Returns the expiry and the uses left of `editor`'s `Update` grant on
+    /// `key`, or `None` if it wasn't granted `Update`. Grants don't expire, so
+    /// the expiry is always 0, and grants without a limit report `u32::MAX`
+    /// uses.
+    pub fn approval(e: Env, key: Bytes, editor: Identifier) -> Option<(u32, u32)> {
+        let perms = grants_of(&e, &key).get(editor.clone())?.unwrap();
+        if !perms.contains(Permission::Update) {
+            return None;
+        }
+        let uses = uses_of(&e, &key).get(editor).map(Result::unwrap);
+        Some((0, uses.unwrap_or(u32::MAX)))
+    }
+
+    /// Returns the permissions `who` was granted on `key`.
+    pub fn perms(e: Env, key: Bytes, who: Identifier) -> Vec<Permission> {
+        grants_of(&e, &key)
+            .get(who)
+            .unwrap_or_else(|| Ok(Vec::new(&e)))
+            .unwrap()
+    }
+
+    /// Sets the text record `name` of `key`. Requires `Metadata`.
+    pub fn set_text(e: Env, key: Bytes, name: Symbol, text: Bytes) {
+        require_perm(&e, &key, Permission::Metadata);
+        check_writable(&e, &key);
+
+        let mut texts: Map<Symbol, Bytes> = e
+            .data()
+            .get(DataKey::Text(key.clone()))
+            .unwrap_or_else(|| Ok(Map::new(&e)))
+            .unwrap();
+        texts.set(name, text);
+        e.data().set(DataKey::Text(key), texts);
+    }
+
+    /// Sets `key` to `value` like `change_val` and replaces all of its text
+    /// records with `texts` in the same call, so the value is never seen with
+    /// stale records. An empty map clears them. Updating a claimed key this
+    /// way takes `Metadata` on top of `Update`. The `set` event has its
+    /// texts flag set.
+    pub fn set_meta(e: Env, key: Bytes, value: Identifier, texts: Map<Symbol, Bytes>) {
+        let invoker = invoker_id(&e);
+        let claimed = e.data().has(DataKey::Owner(key.clone()));
+        let owner = enforce(&e, authorize_change(&e, &key, &invoker, false));
+        if claimed && !has_perm(&e, &key, &invoker, Permission::Metadata) {
+            panic!("you are not allowed to change this value")
+        }
+        use_grant(&e, &key, &invoker);
+
+        if texts.is_empty() {
+            e.data().remove(DataKey::Text(key.clone()));
+        } else {
+            e.data().set(DataKey::Text(key.clone()), texts);
+        }
+        write_entry(&e, key, owner, value, true, None);
+    }
+
+    pub fn text(e: Env, key: Bytes, name: Symbol) -> Option<Bytes> {
+        e.data()
+            .get::<_, Map<Symbol, Bytes>>(DataKey::Text(key))
+            .and_then(|texts| texts.unwrap().get(name))
+            .map(Result::unwrap)
+    }
+
+    /// Sets the gated text record `name` of `key`, which `get_gated` only
+    /// returns to the owner and `reader`. Contract data is public, so this
+    /// keeps the text out of the contract interface, not off the ledger;
+    /// encrypt anything secret. Transfers clear gated records. Requires
+    /// `Metadata`.
+    pub fn set_gated(e: Env, key: Bytes, name: Symbol, text: Bytes, reader: Identifier) {
+        require_perm(&e, &key, Permission::Metadata);
+        check_writable(&e, &key);
+
+        let mut gated: Map<Symbol, GatedText> = e
+            .data()
+            .get(DataKey::Gated(key.clone()))
+            .unwrap_or_else(|| Ok(Map::new(&e)))
+            .unwrap();
+        gated.set(name, GatedText { text, reader });
+        e.data().set(DataKey::Gated(key), gated);
+    }
+
+    /// Returns the gated text record `name` of `key` to the signer of `sig`,
+    /// which covers `(nonce, key, name)` for the `gated` function name with
+    /// the signer's current nonce. Like in `verify_get`, the nonce isn't
+    /// consumed, and the signature stops working once the signer uses it.
+    /// Traps with `NotAuthorized` unless the signer is the owner of the key or
+    /// the record's reader, and with `KeyNotFound` if there is no such record.
+    pub fn get_gated(e: Env, sig: Signature, key: Bytes, name: Symbol) -> Bytes {
+        let record = e
+            .data()
+            .get::<_, Map<Symbol, GatedText>>(DataKey::Gated(key.clone()))
+            .and_then(|gated| gated.unwrap().get(name))
+            .unwrap_or_else(|| panic_error!(&e, Error::KeyNotFound))
+            .unwrap();
+
+        let signer = sig.identifier(&e);
+        if !same_id(&signer, &record.reader) && !same_id(&signer, &owner_of(&e, &key)) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        verify(
+            &e,
+            &sig,
+            symbol!("gated"),
+            (nonce_of(&e, &signer), key, name),
+        );
+        record.text
+    }
+
+    /// Hands `key` to `to`, keeping its value. Only the owner can transfer,
+    /// whether it is an account or a contract.
+    pub fn transfer(e: Env, key: Bytes, to: Identifier) {
+        require_owner(&e, &key);
+        transfer_key(&e, &key, to);
+    }
+
+    /// Hands `key` to `new_owner` like `transfer` and sets it to `value` in the
+    /// same call, so a sale can leave the key holding the value the buyer
+    /// asked for without the seller writing it first. Owner only. `value` wins
+    /// over the new owner for keys linked with `link_owner`. The handover and
+    /// the write bump the version once.
+    pub fn xfer_set(e: Env, key: Bytes, new_owner: Identifier, value: Identifier) {
+        require_owner(&e, &key);
+        if e.data().get(DataKey::Value(key.clone())) == Some(Ok(value.clone())) {
+            transfer_key(&e, &key, new_owner);
+            return;
+        }
+        check_recipient(&e, &key, &new_owner);
+        let previous = owner_of(&e, &key);
+        clear_owner_state(&e, &key);
+        write_val(&e, key.clone(), new_owner.clone(), value);
+        emit_transfer(&e, &key, previous, new_owner);
+    }
+
+    /// Proposes handing `key` to `new_owner`, who takes it over by calling
+    /// `acpt_xfer` no later than `expiry`. A new proposal replaces the
+    /// previous one, expired or not, and an `expiry` already past traps with
+    /// `InvalidPolicy`. Owner only.
+    pub fn prop_xfer(e: Env, key: Bytes, new_owner: Identifier, expiry: u64) {
+        require_owner(&e, &key);
+        if expired(&e, expiry) {
+            panic_error!(&e, Error::InvalidPolicy)
+        }
+        let handoff = Handoff {
+            to: new_owner,
+            expiry,
+        };
+        e.data().set(KeyExtra::Handoff(key), handoff);
+    }
+
+    /// Hands `key` to the invoker if its owner proposed so with `prop_xfer`.
+    /// Traps with `NoRequest` if there is no proposal, `NotAuthorized` if it
+    /// is for someone else and `CommitExpired`, the code expired commitments
+    /// trap with too, once its expiry has passed, after which the owner has
+    /// to propose again.
+    pub fn acpt_xfer(e: Env, key: Bytes) {
+        let handoff: Handoff = e
+            .data()
+            .get(KeyExtra::Handoff(key.clone()))
+            .unwrap_or_else(|| panic_error!(&e, Error::NoRequest))
+            .unwrap();
+        let invoker = invoker_id(&e);
+        if !same_id(&handoff.to, &invoker) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        if expired(&e, handoff.expiry) {
+            panic_error!(&e, Error::CommitExpired)
+        }
+
+        transfer_key(&e, &key, invoker);
+    }
+
+    /// Proposes swapping `my_key` for `their_key`, owned by `counterparty`, who
+    /// can accept with `acpt_swap` for as many ledgers as `set_swpttl` allows.
+    /// A new proposal for `my_key` replaces the previous one, and handing
+    /// `my_key` over in the meantime drops it. Owner of `my_key` only.
+    pub fn prop_swap(e: Env, my_key: Bytes, their_key: Bytes, counterparty: Identifier) {
+        require_owner(&e, &my_key);
+        let swap = Swap {
+            proposer: invoker_id(&e),
+            their_key,
+            with: counterparty,
+            expiry: now(&e).saturating_add(u64::from(settings_of(&e).swap_ttl)),
+        };
+        e.data().set(KeyExtra::Swap(my_key), swap);
+    }
+
+    /// Accepts the swap the owner of `proposer_key` proposed for `my_key`:
+    /// each key goes to the owner of the other, with the approvals, grants
+    /// and pending transfers of both dropped. Both owners must still be able
+    /// to hand their key over and receive the other, so frozen, locked or
+    /// disputed keys and blocked or restricted owners trap like transfers do.
+    /// Each owner ends up with as many keys as before, but an owner at the cap
+    /// of `set_maxown` can't receive first, so swaps between two owners at
+    /// the cap trap with `NotAllowed`. Traps with `NoRequest` if there is no
+    /// proposal, `NotAuthorized` if it is for another key or counterparty and
+    /// `CommitExpired` once it expired, like `acpt_xfer`.
+    pub fn acpt_swap(e: Env, proposer_key: Bytes, my_key: Bytes) {
+        let swap: Swap = e
+            .data()
+            .get(KeyExtra::Swap(proposer_key.clone()))
+            .unwrap_or_else(|| panic_error!(&e, Error::NoRequest))
+            .unwrap();
+        let invoker = invoker_id(&e);
+        if !same_id(&swap.with, &invoker) || swap.their_key != my_key {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        if expired(&e, swap.expiry) {
+            panic_error!(&e, Error::CommitExpired)
+        }
+        require_owned_by(&e, &proposer_key, &swap.proposer);
+        require_owned_by(&e, &my_key, &invoker);
+
+        if count_of(&e, &swap.proposer) <= count_of(&e, &invoker) {
+            transfer_key(&e, &my_key, swap.proposer.clone());
+            transfer_key(&e, &proposer_key, invoker);
+        } else {
+            transfer_key(&e, &proposer_key, invoker);
+            transfer_key(&e, &my_key, swap.proposer);
+        }
+    }
+
+    /// Cancels the swap proposed for `my_key`. Owner only.
+    pub fn drop_swap(e: Env, my_key: Bytes) {
+        require_owner(&e, &my_key);
+        e.data().remove(KeyExtra::Swap(my_key));
+    }
+
+    /// Approves `spender` to hand `key` over once with `xfer_from`, replacing
+    /// any previous approval. Transfers of the key drop the approval. Owner
+    /// only.
+    ///
+    /// Named `approve_to` as `approve` approves co-owned changes.
+    pub fn approve_to(e: Env, key: Bytes, spender: Identifier) {
+        require_owner(&e, &key);
+        e.data().set(DataKey::Spender(key), spender);
+    }
+
+    /// Returns the spender approved for `key` with `approve_to`, if any.
+    pub fn spender(e: Env, key: Bytes) -> Option<Identifier> {
+        e.data().get(DataKey::Spender(key)).map(Result::unwrap)
+    }
+
+    /// Hands `key` to `new_owner` like `transfer`, on behalf of its owner, using
+    /// up the approval the invoker was given with `approve_to`. Traps with
+    /// `NotAuthorized` if the invoker isn't the approved spender and with
+    /// `CoOwned` if the key has co-owners.
+    pub fn xfer_from(e: Env, key: Bytes, new_owner: Identifier) {
+        check_live(&e);
+        if !Self::spender(e.clone(), key.clone())
+            .is_some_and(|spender| same_id(&spender, &invoker_id(&e)))
+        {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        if !shares_of(&e, &key).is_empty() {
+            panic_error!(&e, Error::CoOwned)
+        }
+        transfer_key(&e, &key, new_owner);
+    }
+
+    /// Hands `key` to `successor` like `transfer`, also clearing its text
+    /// records, and publishes a `released` event with the key and the
+    /// successor. During the `allowlist` phase the successor has to be allowed
+    /// with `allow`, as if they were claiming the key, or this traps with
+    /// `NotAllowed`. Owner only.
+    pub fn release_to(e: Env, key: Bytes, successor: Identifier) {
+        require_owner(&e, &key);
+        if phase_of(&e) == symbol!("allowlist") {
+            check_allowed(&e, &successor);
+        }
+
+        transfer_key(&e, &key, successor.clone());
+        e.data().remove(DataKey::Text(key.clone()));
+        emit(&e, Level::Minimal, (TOPIC_RELEASED,), (key, successor));
+    }
+
+    /// Hands `key` to `new_owner` like `transfer`, provided `accept_sig` is a
+    /// signature by `new_owner` over `(nonce, key, new_owner)` for the
+    /// `accept` function name, where `nonce` is the signer's current `nonce`,
+    /// so keys only go to recipients that can take them. Traps with
+    /// `NotAuthorized` if someone else signed. Owner only.
+    pub fn handover(e: Env, key: Bytes, new_owner: Identifier, accept_sig: Signature) {
+        require_owner(&e, &key);
+        if !same_id(&accept_sig.identifier(&e), &new_owner) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        verify_nonce(
+            &e,
+            &accept_sig,
+            symbol!("accept"),
+            (key.clone(), new_owner.clone()),
+        );
+
+        transfer_key(&e, &key, new_owner);
+    }
+
+    /// Hands `key` to `new_owner` like `handover`, except that the permissions
+    /// granted on it carry over to the new owner if `keep_grants` is set.
+    /// `accept_sig` is a signature by `new_owner` over
+    /// `(nonce, key, new_owner, keep_grants)` for the `xfer_with` function
+    /// name, so only the new owner can choose to keep the old owner's
+    /// delegates. Owner only.
+    pub fn xfer_with(
+        e: Env,
+        key: Bytes,
+        new_owner: Identifier,
+        keep_grants: bool,
+        accept_sig: Signature,
+    ) {
+        require_owner(&e, &key);
+        if !same_id(&accept_sig.identifier(&e), &new_owner) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        verify_nonce(
+            &e,
+            &accept_sig,
+            symbol!("xfer_with"),
+            (key.clone(), new_owner.clone(), keep_grants),
+        );
+
+        let grants = grants_of(&e, &key);
+        let uses = uses_of(&e, &key);
+        transfer_key(&e, &key, new_owner);
+        if keep_grants && !grants.is_empty() {
+            e.data().set(DataKey::Grants(key.clone()), grants);
+            if !uses.is_empty() {
+                e.data().set(DataKey::Uses(key), uses);
+            }
+        }
+    }
+
+    /// Hands each of `keys` to the new owner at the same position in
+    /// `new_owners` like `transfer`, trapping with `LengthMismatch` if the two
+    /// differ in length. If the invoker doesn't own one of the keys, counting
+    /// those it already handed over earlier in the batch, the whole call traps
+    /// and nothing is transferred.
+    pub fn batch_xfer(e: Env, keys: Vec<Bytes>, new_owners: Vec<Identifier>) {
+        if keys.len() != new_owners.len() {
+            panic_error!(&e, Error::LengthMismatch)
+        }
+        let invoker = invoker_id(&e);
+        for (key, to) in keys.iter().zip(new_owners.iter()) {
+            let key = key.unwrap();
+            require_owned_by(&e, &key, &invoker);
+            transfer_key(&e, &key, to.unwrap());
+        }
+    }
+
+    /// Returns the `STATE_VERSION` of the records this contract exports and
+    /// ingests.
+    pub fn state_ver(_e: Env) -> u32 {
+        STATE_VERSION
+    }
+
+    /// Returns the records of the keys holding a value in the `limit` slots of
+    /// the key index starting at `start`. Pages concatenated in order are what
+    /// `ingest` expects.
+    pub fn export(e: Env, start: u32, limit: u32) -> Vec<Record> {
+        records(&e, start, limit)
+    }
+
+    /// Prepares the registry for a handoff to a redeployed contract: writes
+    /// are paused for good and the hash of every exported record is recorded
+    /// and returned. Only owners and values are exported. Admin only.
+    pub fn seal(e: Env) -> BytesN<32> {
+        require_admin(&e);
+        if e.data().has(DataKey::StateHash) {
+            panic_error!(&e, Error::Sealed)
+        }
+
+        let hash = records_hash(&e, &records(&e, 0, slot_count(&e)));
+        update_settings(&e, |s| s.paused = true);
+        e.data().set(DataKey::StateHash, hash.clone());
+        hash
+    }
+
+    /// Returns the hash recorded by `seal`, if the registry was sealed.
+    pub fn state_hash(e: Env) -> Option<BytesN<32>> {
+        e.data().get(DataKey::StateHash).map(Result::unwrap)
+    }
+
+    /// Imports the records exported by a sealed registry, after checking that
+    /// they hash to `expected_hash` and trapping with `PayloadMismatch` if not.
+    /// Only a registry without keys can ingest. Admin only.
+    pub fn ingest(e: Env, entries: Vec<Record>, expected_hash: BytesN<32>) {
+        require_admin(&e);
+        if slot_count(&e) != 0 {
+            panic_error!(&e, Error::AlreadyExists)
+        }
+        if records_hash(&e, &entries) != expected_hash {
+            panic_error!(&e, Error::PayloadMismatch)
+        }
+
+        for record in entries.iter() {
+            let record = record.unwrap();
+            check_key(&e, &record.key);
+            write_val(&e, record.key, record.owner, record.value);
+        }
+    }
+
+    /// Flags `key` as disputed, blocking every mutation of it for
+    /// `DISPUTE_WINDOW` ledgers or until the dispute is resolved. Admin only.
+    pub fn dispute(e: Env, key: Bytes) {
+        require_admin(&e);
+        e.data().set(DataKey::Dispute(key.clone()), now(&e));
+        emit(&e, Level::Full, (TOPIC_DISPUTE,), key);
+    }
+
+    /// Resolves the dispute on `key`. `dismiss` restores normal operation,
+    /// while `uphold` hands the key to `new_owner`, or removes it if no new
+    /// owner is given. Traps with `NoDispute` if the key isn't disputed.
+    /// Admin only.
+    pub fn resolve(e: Env, key: Bytes, outcome: Symbol, new_owner: Option<Identifier>) {
+        require_admin(&e);
+        if !is_disputed(&e, &key) {
+            panic_error!(&e, Error::NoDispute)
+        }
+        if outcome != symbol!("dismiss") && outcome != symbol!("uphold") {
+            panic_error!(&e, Error::InvalidOutcome)
+        }
+
+        e.data().remove(DataKey::Dispute(key.clone()));
+        if outcome == symbol!("uphold") {
+            match new_owner {
+                Some(owner) => transfer_key(&e, &key, owner),
+                None => delete_key(&e, &key),
+            }
+        }
+        emit(&e, Level::Minimal, (TOPIC_RESOLVED,), (key, outcome));
+    }
+
+    /// Returns whether `sig` is a signature by the current owner of `key` over
+    /// `(key, challenge)` for the `login` function name, so dApps can use
+    /// registry keys for off-chain sign in. The signature is checked against
+    /// the owner first and `false` is returned for other signers, unset keys
+    /// and account signatures by keys that aren't the account's signers or
+    /// short of its threshold. A signature from the owner that doesn't verify
+    /// cryptographically still traps, as that check can't fail softly. No
+    /// nonce is consumed.
+    pub fn check_sig(e: Env, key: Bytes, sig: Signature, challenge: Bytes) -> bool {
+        if !e.data().has(DataKey::Owner(key.clone()))
+            || !same_id(&owner_of(&e, &key), &sig.identifier(&e))
+            || !well_formed(&e, &sig)
+        {
+            return false;
+        }
+
+        verify(&e, &sig, symbol!("login"), (key, challenge));
+        true
+    }
+
+    /// Sets the number of ledgers `claim_once` request ids are kept for.
+    /// Admin only.
+    pub fn set_reqttl(e: Env, ledgers: u32) {
+        require_admin(&e);
+        update_settings(&e, |s| s.req_ttl = ledgers);
+    }
+
+    /// Sets the number of ledgers nonces reserved with `rsv_nonce` stay
+    /// usable for. Admin only.
+    pub fn set_rsvttl(e: Env, ledgers: u32) {
+        require_admin(&e);
+        update_settings(&e, |s| s.rsv_ttl = ledgers);
+    }
+
+    /// Sets the number of ledgers the tombstones of removed keys are kept
+    /// for. Admin only.
+    pub fn set_tmbttl(e: Env, ledgers: u32) {
+        require_admin(&e);
+        update_settings(&e, |s| s.tomb_ttl = ledgers);
+    }
+
+    /// Sets the number of ledgers swaps proposed with `prop_swap` can be
+    /// accepted for. Admin only.
+    pub fn set_swpttl(e: Env, ledgers: u32) {
+        require_admin(&e);
+        update_settings(&e, |s| s.swap_ttl = ledgers);
+    }
+
+    /// Returns when `key` was removed, so callers can tell a key
+    /// removed shortly before from one that never existed without paying for
+    /// their fallback. The tombstone goes away once the key is claimed again
+    /// or swept with `sweep_tomb`.
+    pub fn removed_at(e: Env, key: Bytes) -> Option<u64> {
+        e.data()
+            .get(KeyExtra::Tombstone(key))
+            .map(|ledger| ledger.unwrap())
+    }
+
+    /// Deletes the tombstone of `key` once it is older than the tombstone
+    /// TTL, trapping with `RequestPending` before that and with `KeyNotFound`
+    /// if there is none. Anyone can sweep.
+    pub fn sweep_tomb(e: Env, key: Bytes) {
+        let since: u64 = e
+            .data()
+            .get(KeyExtra::Tombstone(key.clone()))
+            .unwrap_or_else(|| panic_error!(&e, Error::KeyNotFound))
+            .unwrap();
+
+        if !expired(
+            &e,
+            since.saturating_add(u64::from(settings_of(&e).tomb_ttl)),
+        ) {
+            panic_error!(&e, Error::RequestPending)
+        }
+
+        e.data().remove(KeyExtra::Tombstone(key));
+    }
+
+    /// Deletes a `claim_once` request id once it is older than the request
+    /// TTL, trapping with `RequestPending` before that. Anyone can prune.
+    pub fn prune_req(e: Env, request_id: BytesN<32>) {
+        let (_, since): (BytesN<32>, u64) = e
+            .data()
+            .get(DataKey::Request(request_id.clone()))
+            .unwrap_or_else(|| panic_error!(&e, Error::NoRequest))
+            .unwrap();
+        let ttl = settings_of(&e).req_ttl;
+
+        if !expired(&e, since.saturating_add(u64::from(ttl))) {
+            panic_error!(&e, Error::RequestPending)
+        }
+
+        e.data().remove(DataKey::Request(request_id));
+    }
+
+    /// Registers the contract `callback` to have `on_change(key, value)` called
+    /// after every write to the value of `key`, or `on_data(key, value, data)`
+    /// after writes through `data_val`. Only the owner of `key` can subscribe
+    /// contracts to it, so strangers can't fill its `MAX_SUBSCRIBERS` slots.
+    /// Traps with `TooManySubs` past `MAX_SUBSCRIBERS`.
+    pub fn subscribe(e: Env, key: Bytes, callback: Identifier) {
+        let id = match &callback {
+            Identifier::Contract(id) => id.clone(),
+            _ => panic_error!(&e, Error::NotContract),
+        };
+        if !same_id(&owner_of(&e, &key), &invoker_id(&e)) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+
+        let mut subs = subs_of(&e, &key);
+        if subs.contains(&id) {
+            return;
+        }
+        if subs.len() >= MAX_SUBSCRIBERS {
+            panic_error!(&e, Error::TooManySubs)
+        }
+        subs.push_back(id);
+        e.data().set(DataKey::Subs(key), subs);
+    }
+
+    /// Removes a subscription made with `subscribe`. The owner of `key` can
+    /// remove any of its subscribers and a subscribed contract itself.
+    pub fn unsub(e: Env, key: Bytes, callback: Identifier) {
+        let invoker = invoker_id(&e);
+        if !same_id(&invoker, &callback) && !same_id(&owner_of(&e, &key), &invoker) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+
+        if let Identifier::Contract(id) = callback {
+            let mut subs = subs_of(&e, &key);
+            if let Some(i) = subs.first_index_of(id) {
+                subs.remove(i);
+                e.data().set(DataKey::Subs(key), subs);
+            }
+        }
+    }
+
+    /// Turns `key` into a co-owned key split between `owners` by weight. From
+    /// then on transfers and other owner-only changes go through `approve` and
+    /// `execute`, and value updates too unless `solo` lets any single co-owner
+    /// make them. Only the sole owner can share a key. Traps with
+    /// `InvalidShares` if the weights add up to nothing or overflow, or if an
+    /// identifier is listed twice.
+    pub fn set_owners(e: Env, key: Bytes, owners: Vec<Share>, solo: bool) {
+        require_owner(&e, &key);
+
+        let mut total: u32 = 0;
+        let mut seen: Vec<Identifier> = Vec::new(&e);
+        for share in owners.iter() {
+            let share = share.unwrap();
+            if seen
+                .iter()
+                .any(|owner| same_id(&owner.unwrap(), &share.owner))
+            {
+                panic_error!(&e, Error::InvalidShares)
+            }
+            total = total
+                .checked_add(share.weight)
+                .unwrap_or_else(|| panic_error!(&e, Error::InvalidShares));
+            seen.push_back(share.owner);
+        }
+        if total == 0 {
+            panic_error!(&e, Error::InvalidShares)
+        }
+
+        e.data().set(DataKey::Shares(key.clone()), owners);
+        if solo {
+            e.data().set(DataKey::SoloUpd(key), true);
+        }
+    }
+
+    /// Returns the co-owners of `key`, empty for solely owned keys.
+    pub fn co_owners(e: Env, key: Bytes) -> Vec<Share> {
+        shares_of(&e, &key)
+    }
+
+    /// Approves the action with hash `action_hash` on `key` on behalf of the
+    /// invoking co-owner.
+    pub fn approve(e: Env, key: Bytes, action_hash: BytesN<32>) {
+        let invoker = require_co_owner(&e, &key);
+
+        let mut approvals = approvals_of(&e, &key);
+        let mut approvers = approvals
+            .get(action_hash.clone())
+            .unwrap_or_else(|| Ok(Vec::new(&e)))
+            .unwrap();
+        if !contains_id(&approvers, &invoker) {
+            approvers.push_back(invoker);
+            approvals.set(action_hash, approvers);
+            e.data().set(DataKey::Approvals(key), approvals);
+        }
+    }
+
+    /// Withdraws the invoking co-owner's approval of `action_hash` on `key`.
+    pub fn unapprove(e: Env, key: Bytes, action_hash: BytesN<32>) {
+        let invoker = require_co_owner(&e, &key);
+
+        let mut approvals = approvals_of(&e, &key);
+        if let Some(approvers) = approvals.get(action_hash.clone()) {
+            let mut approvers = approvers.unwrap();
+            if let Some(i) = index_of_id(&approvers, &invoker) {
+                approvers.remove(i);
+                approvals.set(action_hash, approvers);
+                e.data().set(DataKey::Approvals(key), approvals);
+            }
+        }
+    }
+
+    /// Applies `action` to the co-owned `key` once co-owners holding more than
+    /// half of the weight approved it, trapping with `NoMajority` before that.
+    pub fn execute(e: Env, key: Bytes, action: Action) {
+        let shares = shares_of(&e, &key);
+        let hash = e.compute_hash_sha256(&action.clone().serialize(&e));
+        let approvers = approvals_of(&e, &key)
+            .get(hash.clone())
+            .unwrap_or_else(|| Ok(Vec::new(&e)))
+            .unwrap();
+
+        let mut total: u32 = 0;
+        let mut approved: u32 = 0;
+        for share in shares.iter() {
+            let share = share.unwrap();
+            total += share.weight;
+            if contains_id(&approvers, &share.owner) {
+                approved += share.weight;
+            }
+        }
+        if shares.is_empty() || approved as u64 * 2 <= total as u64 {
+            panic_error!(&e, Error::NoMajority)
+        }
+
+        let mut approvals = approvals_of(&e, &key);
+        approvals.remove(hash);
+        e.data().set(DataKey::Approvals(key.clone()), approvals);
+
+        match action {
+            Action::SetValue(value) => {
+                write_val(&e, key.clone(), owner_of(&e, &key), value);
+            }
+            Action::Transfer(to) => transfer_key(&e, &key, to),
+        }
+    }
+
+    /// Sets `key` like `change_val`, tagged with a `version` chosen by the
+    /// caller. Writes only go through with a version strictly greater than the
+    /// stored one, trapping with `StaleVersion` otherwise, so replaying
+    /// off-chain updates out of order can't roll a value back.
+    pub fn set_ver(e: Env, key: Bytes, value: Identifier, version: u64) {
+        let stored: Option<u64> = e
+            .data()
+            .get(DataKey::ClientVer(key.clone()))
+            .map(|v| v.unwrap());
+        if matches!(stored, Some(stored) if version <= stored) {
+            panic_error!(&e, Error::StaleVersion)
+        }
+
+        Self::change_val(e.clone(), key.clone(), value);
+        e.data().set(DataKey::ClientVer(key), version);
+    }
+
+    /// Returns the version `key` was last set with through `set_ver`, 0 if it
+    /// never was.
+    pub fn get_ver(e: Env, key: Bytes) -> u64 {
+        e.data()
+            .get(DataKey::ClientVer(key))
+            .unwrap_or(Ok(0))
+            .unwrap()
+    }
+
+    /// Starts recovering `key` for `new_owner`, for owners who lost access to
+    /// it. The current owner has `RECOVERY_DELAY` ledgers to `veto` it before
+    /// the admin can `finalize` the transfer. Besides the admin, the guardian
+    /// set by the owner with `set_guard` can start a recovery, which gives the
+    /// owner `GUARDIAN_DELAY` ledgers instead and can be finalized by anyone.
+    pub fn recover(e: Env, key: Bytes, new_owner: Identifier) {
+        let invoker = invoker_id(&e);
+        let guardian = if is_admin(&e, &invoker) {
+            false
+        } else if e
+            .data()
+            .get::<_, Identifier>(DataKey::Guardian(key.clone()))
+            .is_some_and(|guardian| same_id(&guardian.unwrap(), &invoker))
+        {
+            true
+        } else {
+            panic_error!(&e, Error::NotAuthorized)
+        };
+        if !e.data().has(DataKey::Owner(key.clone())) {
+            panic_error!(&e, Error::KeyNotFound)
+        }
+
+        let delay = if guardian {
+            GUARDIAN_DELAY
+        } else {
+            RECOVERY_DELAY
+        };
+        let deadline = now(&e) + u64::from(delay);
+        e.data().set(
+            DataKey::Recovery(key.clone()),
+            Recovery {
+                new_owner: new_owner.clone(),
+                deadline,
+                guardian,
+            },
+        );
+        emit(
+            &e,
+            Level::Full,
+            (TOPIC_RECOVER,),
+            (key, new_owner, deadline),
+        );
+    }
+
+    /// Sets `guardian` as the guardian of `key`, allowing it to `recover` the
+    /// key if its owner loses access to it. Transfers clear the guardian.
+    pub fn set_guard(e: Env, key: Bytes, guardian: Identifier) {
+        require_owner(&e, &key);
+        e.data()
+            .set(DataKey::Guardian(key.clone()), guardian.clone());
+        emit(&e, Level::Full, (TOPIC_GUARDIAN,), (key, guardian));
+    }
+
+    /// Sets `guardians` as the guardian set of `key`, any `threshold` of
+    /// whom can hand the key to a new owner with `social_rcv`. An empty
+    /// `guardians` removes the set, and transfers clear it. Traps with
+    /// `InvalidPolicy` if `threshold` is 0 or above the number of guardians.
+    /// Owner only.
+    pub fn set_guards(e: Env, key: Bytes, guardians: Vec<Identifier>, threshold: u32) {
+        require_owner(&e, &key);
+        if guardians.is_empty() {
+            e.data().remove(KeyExtra::Guardians(key));
+            return;
+        }
+        if threshold == 0 || threshold > guardians.len() {
+            panic_error!(&e, Error::InvalidPolicy)
+        }
+        let quorum = Quorum {
+            signers: guardians,
+            threshold,
+        };
+        e.data().set(KeyExtra::Guardians(key), quorum);
+    }
+
+    /// Hands `key` to `new_owner` right away if the threshold of its guardian
+    /// set signs `(nonce, key, new_owner)`, each with its own nonce. Traps
+    /// with `NotAuthorized` if the key has no guardian set or there aren't
+    /// enough signatures from distinct guardians.
+    pub fn social_rcv(e: Env, key: Bytes, new_owner: Identifier, sigs: Vec<Signature>) {
+        check_live(&e);
+        let guardians: Quorum = e
             .data()
-            .get(key.clone())
-            .unwrap_or_else(|| Ok(Identifier::Contract(e.current_contract())))
+            .get(KeyExtra::Guardians(key.clone()))
+            .unwrap_or_else(|| panic_error!(&e, Error::NotAuthorized))
             .unwrap();
+        let args = (key.clone(), new_owner.clone()).into_val(&e);
+        require_signers(&e, &guardians, &sigs, symbol!("social_rcv"), args);
 
-        if stored_addr == Identifier::Contract(e.current_contract()) {
-            e.data().set(key, value);
-        } else {
-            let invoker_id = match e.invoker() {
-                Address::Account(id) => Identifier::Account(id),
-                Address::Contract(id) => Identifier::Contract(id),
-            };
+        transfer_key(&e, &key, new_owner.clone());
+        emit(&e, Level::Minimal, (TOPIC_RECOVERED,), (key, new_owner));
+    }
+
+    /// Returns the pending recovery of `key`, if any.
+    ///
+    /// Named `rec_state` as a function called `recovery` would clash with the
+    /// `Recovery` type in the contract spec.
+    pub fn rec_state(e: Env, key: Bytes) -> Option<Recovery> {
+        e.data().get(DataKey::Recovery(key)).map(Result::unwrap)
+    }
+
+    /// Cancels the pending recovery of `key`. Only its current owner can veto
+    /// it.
+    pub fn veto(e: Env, key: Bytes) {
+        if !same_id(&owner_of(&e, &key), &invoker_id(&e)) {
+            panic!("you are not allowed to change this value")
+        }
+        if !e.data().has(DataKey::Recovery(key.clone())) {
+            panic_error!(&e, Error::NoRecovery)
+        }
+
+        e.data().remove(DataKey::Recovery(key.clone()));
+        emit(&e, Level::Full, (TOPIC_VETOED,), key);
+    }
+
+    /// Hands `key` to the owner its recovery was started for, trapping with
+    /// `RecoveryPending` until the veto period is over. Admin only, unless the
+    /// recovery was started by the key's guardian.
+    pub fn finalize(e: Env, key: Bytes) {
+        let recovery: Recovery = e
+            .data()
+            .get(DataKey::Recovery(key.clone()))
+            .unwrap_or_else(|| panic_error!(&e, Error::NoRecovery))
+            .unwrap();
+        if !recovery.guardian {
+            require_admin(&e);
+        }
+        if !deadline_passed(&e, recovery.deadline) {
+            panic_error!(&e, Error::RecoveryPending)
+        }
+
+        transfer_key(&e, &key, recovery.new_owner.clone());
+        emit(
+            &e,
+            Level::Minimal,
+            (TOPIC_RECOVERED,),
+            (key, recovery.new_owner),
+        );
+    }
+
+    /// Sets whether creating namespaces is reserved to the admin instead of
+    /// open to anyone on a first-come basis. Admin only.
+    pub fn gate_ns(e: Env, gated: bool) {
+        require_admin(&e);
+        update_settings(&e, |s| s.ns_gated = gated);
+    }
+
+    /// Creates the namespace `ns` owned by `owner`, with open claims. Keys in a
+    /// namespace are independent of the same keys elsewhere and are claimed
+    /// with `set_ns`. Traps with `AlreadyExists` if `ns` is taken.
+    pub fn create_ns(e: Env, ns: Bytes, owner: Identifier) {
+        if settings_of(&e).ns_gated {
+            require_admin(&e);
+        }
+        if ns.len() > MAX_NS_LEN {
+            panic_error!(&e, Error::InvalidKey)
+        }
+        if e.data().has(DataKey::Namespace(ns.clone())) {
+            panic_error!(&e, Error::AlreadyExists)
+        }
+
+        e.data().set(
+            DataKey::Namespace(ns),
+            Namespace {
+                owner,
+                policy: symbol!("open"),
+                fee: BigInt::zero(&e),
+            },
+        );
+    }
+
+    /// Sets the claim policy of `ns` to `open`, `owner` or `fee`, `fee` being
+    /// charged for claims under the `fee` policy. Only the namespace owner can
+    /// change it.
+    pub fn ns_policy(e: Env, ns: Bytes, policy: Symbol, fee: BigInt) {
+        let mut namespace = namespace_of(&e, &ns);
+        if !same_id(&namespace.owner, &invoker_id(&e)) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        if policy != symbol!("open") && policy != symbol!("owner") && policy != symbol!("fee") {
+            panic_error!(&e, Error::InvalidPolicy)
+        }
+
+        namespace.policy = policy;
+        namespace.fee = fee;
+        e.data().set(DataKey::Namespace(ns), namespace);
+    }
+
+    /// Returns the key storing the entry for `key` inside `ns`, which every
+    /// other function accepts to operate on the namespaced entry.
+    pub fn ns_key(e: Env, ns: Bytes, key: Bytes) -> Bytes {
+        namespaced(&e, &ns, &key)
+    }
+
+    /// Sets `key` inside the namespace `ns` like `change_val`, claims being
+    /// subject to the policy of the namespace instead of the registry's.
+    pub fn set_ns(e: Env, ns: Bytes, key: Bytes, value: Identifier) {
+        let full = namespaced(&e, &ns, &key);
+        if e.data().has(DataKey::Owner(full.clone())) {
+            Self::change_val(e, full, value);
+            return;
+        }
+
+        let invoker_id = invoker_id(&e);
+        check_ns_claim(&e, &ns, &key, &invoker_id);
+        write_val(&e, full, invoker_id, value);
+    }
+
+    /// Returns the value of `key` inside the namespace `ns`, trapping like
+    /// `get` for unset keys.
+    pub fn get_ns(e: Env, ns: Bytes, key: Bytes) -> Identifier {
+        read_val(&e, &namespaced(&e, &ns, &key))
+    }
+
+    /// Returns the number of keys owned by `owner`, without going through the
+    /// key index.
+    pub fn key_count(e: Env, owner: Identifier) -> u32 {
+        count_of(&e, &owner)
+    }
+
+    /// Regenerates the indexes and key counts of every owner from the owners
+    /// recorded for the keys of the key index, should they ever drift apart.
+    /// Identifiers that no longer own any key aren't known to the key index,
+    /// so drift in their own index stays. Admin only.
+    pub fn reindex(e: Env) {
+        require_admin(&e);
+
+        let mut owned: Map<Identifier, Vec<Bytes>> = Map::new(&e);
+        for key in keys_of(&e).iter() {
+            let key = key.unwrap();
+            match e.data().get::<_, Identifier>(DataKey::Owner(key.clone())) {
+                Some(owner) => {
+                    let owner = owner.unwrap();
+                    let mut keys = owned
+                        .get(owner.clone())
+                        .unwrap_or_else(|| Ok(Vec::new(&e)))
+                        .unwrap();
+                    keys.push_back(key);
+                    owned.set(owner, keys);
+                }
+                None => e.data().remove(DataKey::OwnerSlot(key)),
+            }
+        }
+
+        for entry in owned.iter() {
+            let (owner, keys) = entry.unwrap();
+            let pages = count_of(&e, &owner).max(keys.len()).div_ceil(INDEX_PAGE);
+            for page in 0..pages {
+                set_owner_page(&e, &owner, page, Vec::new(&e));
+            }
+            e.data().remove(DataKey::OwnerCount(owner.clone()));
+            for key in keys.iter() {
+                index_key(&e, &owner, &key.unwrap());
+            }
+        }
+    }
+
+    /// Panics if the storage of the registry is inconsistent, for
+    /// `invariants::check_invariants` to run from tests. Only built for tests.
+    #[cfg(any(test, feature = "testutils"))]
+    pub fn invariants(e: Env) {
+        invariants::check(&e);
+    }
+
+    /// Returns how many storage records `key` occupies, by category: `owner`
+    /// for the ownership records, `metadata`, `approvals` for grants,
+    /// approvals and co-ownership, `history`, `blob` for the sealed blob and
+    /// `other` for the rest. Deleted keys can still count records kept on
+    /// purpose, such as their version counter and tombstone.
+    pub fn footprint(e: Env, key: Bytes) -> Map<Symbol, u32> {
+        let mut counts = Map::new(&e);
+        for category in [
+            symbol!("owner"),
+            symbol!("metadata"),
+            symbol!("approvals"),
+            symbol!("history"),
+            symbol!("blob"),
+            symbol!("other"),
+        ] {
+            counts.set(category, 0);
+        }
+
+        let mut count = |category: Symbol| {
+            let n: u32 = counts.get_unchecked(category).unwrap();
+            counts.set(category, n + 1);
+        };
+        for (category, _, record) in key_records(&key) {
+            if e.data().has(record) {
+                count(category);
+            }
+        }
+        for (category, _, record) in key_extras(&key) {
+            if e.data().has(record) {
+                count(category);
+            }
+        }
+        counts
+    }
+
+    /// Deletes the optional records of `key` to shrink its footprint while
+    /// keeping it owned: its text records, client version, memo, value
+    /// history and sealed blob. Owner only.
+    pub fn purge_aux(e: Env, key: Bytes) {
+        require_owner(&e, &key);
+        check_writable(&e, &key);
+
+        for (_, lifetime, record) in key_records(&key) {
+            if lifetime == Lifetime::Aux {
+                e.data().remove(record);
+            }
+        }
+        for (_, lifetime, record) in key_extras(&key) {
+            if lifetime == Lifetime::Aux {
+                e.data().remove(record);
+            }
+        }
+    }
+
+    /// Returns page `page` of the keys owned by `owner`, holding up to
+    /// `INDEX_PAGE` keys. Removing a key moves the owner's last key into its
+    /// place, so the order of the keys changes as keys come and go.
+    pub fn owned_page(e: Env, owner: Identifier, page: u32) -> Vec<Bytes> {
+        owner_page(&e, &owner, page)
+    }
+
+    /// Returns the keys owned by `owner` that are private, in the order of
+    /// their index. Only the owner and the admin can list them, others trap
+    /// with `NotAuthorized`, so which keys are private doesn't leak.
+    pub fn priv_keys(e: Env, owner: Identifier) -> Vec<Bytes> {
+        let invoker = invoker_id(&e);
+        if !same_id(&invoker, &owner) && !is_admin(&e, &invoker) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+
+        let mut keys = Vec::new(&e);
+        for page in 0..count_of(&e, &owner).div_ceil(INDEX_PAGE) {
+            for key in owner_page(&e, &owner, page).iter() {
+                let key = key.unwrap();
+                if is_private(&e, &key) {
+                    keys.push_back(key);
+                }
+            }
+        }
+        keys
+    }
+
+    /// Returns the number of pages the keys owned by `id` take up in their
+    /// index. Pages are kept full, so this is `key_count` divided by
+    /// `INDEX_PAGE`, rounded up.
+    pub fn idx_pages(e: Env, id: Identifier) -> u32 {
+        count_of(&e, &id).div_ceil(INDEX_PAGE)
+    }
+
+    /// Registers `parent` as the parent of `child`, allowing it to write to
+    /// every key `child` owns like `child` itself could. Admin only.
+    pub fn set_parent(e: Env, child: Identifier, parent: Identifier) {
+        require_admin(&e);
+        e.data().set(DataKey::Parent(child), parent);
+    }
+
+    /// Splits the value of `key` between `parts`, each weighing a non-zero
+    /// share of `SPLIT_DENOMINATOR`, their weights adding up to exactly that,
+    /// or this traps with `InvalidSplit`. The part with the highest weight,
+    /// the first one among equal weights, becomes the value returned by `get`.
+    /// Setting the value any other way removes the split.
+    pub fn set_split(e: Env, key: Bytes, parts: Vec<Part>) {
+        require_perm(&e, &key, Permission::Update);
+        check_invoker_write(&e, &key);
+
+        let mut total: u32 = 0;
+        let mut primary: Option<Part> = None;
+        for part in parts.iter() {
+            let part = part.unwrap();
+            if part.weight == 0 {
+                panic_error!(&e, Error::InvalidSplit)
+            }
+            total = total.saturating_add(part.weight);
+            if primary.as_ref().is_none_or(|p| part.weight > p.weight) {
+                primary = Some(part);
+            }
+        }
+        let primary = match primary {
+            Some(primary) if total == SPLIT_DENOMINATOR => primary,
+            _ => panic_error!(&e, Error::InvalidSplit),
+        };
+
+        write_val(&e, key.clone(), owner_of(&e, &key), primary.to);
+        e.data().set(DataKey::Split(key), parts);
+    }
+
+    /// Returns the parts the value of `key` is split between, or its value as
+    /// the single part it goes to in full if it isn't split. Traps like `get`
+    /// for unset keys.
+    pub fn get_split(e: Env, key: Bytes) -> Vec<Part> {
+        let value = read_val(&e, &key);
+        e.data()
+            .get(DataKey::Split(key))
+            .unwrap_or_else(|| {
+                Ok(Vec::from_array(
+                    &e,
+                    [Part {
+                        to: value,
+                        weight: SPLIT_DENOMINATOR,
+                    }],
+                ))
+            })
+            .unwrap()
+    }
+
+    /// Returns every key with its owner and value serialized into a single
+    /// blob for `load_snap`, to capture the registry in tests and forks. This
+    /// reads the whole key index, so it is not meant for registries at
+    /// production scale. Admin only.
+    pub fn snapshot(e: Env) -> Bytes {
+        require_admin(&e);
+        records(&e, 0, slot_count(&e)).serialize(&e)
+    }
+
+    /// Replaces the registry's keys with the ones in a `snapshot` blob. Every
+    /// current key is removed like `admin_del` first. Traps with
+    /// `PayloadMismatch` if `blob` holds something other than records, and
+    /// the host traps if it isn't serialized data at all. Admin only.
+    pub fn load_snap(e: Env, blob: Bytes) {
+        require_admin(&e);
+        let entries = Vec::<Record>::deserialize(&e, &blob)
+            .unwrap_or_else(|_| panic_error!(&e, Error::PayloadMismatch));
 
-            if stored_addr != invoker_id {
-                panic!("you are not allowed to change this value")
+        for key in keys_of(&e).iter() {
+            let key = key.unwrap();
+            if e.data().has(DataKey::Owner(key.clone())) {
+                delete_key(&e, &key);
             }
+        }
+        for record in entries.iter() {
+            let record = record.unwrap();
+            write_val(&e, record.key, record.owner, record.value);
+        }
+    }
+
+    /// Adds `key` to the hot set, which records whether its keys are claimed
+    /// in a single entry so `exists_hot` can check them all with one read.
+    /// Admin only.
+    pub fn pin_key(e: Env, key: Bytes) {
+        require_admin(&e);
+        let mut hot = hot_of(&e);
+        hot.set(key.clone(), e.data().has(DataKey::Owner(key)));
+        e.data().set(DataKey::Hot, hot);
+    }
+
+    /// Removes `key` from the hot set. Admin only.
+    pub fn unpin_key(e: Env, key: Bytes) {
+        require_admin(&e);
+        let mut hot = hot_of(&e);
+        hot.remove(key);
+        e.data().set(DataKey::Hot, hot);
+    }
+
+    /// Returns whether each of `keys` is claimed, answering for pinned keys
+    /// from the hot set and reading the others one by one.
+    pub fn exists_hot(e: Env, keys: Vec<Bytes>) -> Vec<bool> {
+        let hot = hot_of(&e);
+        let mut exists = Vec::new(&e);
+        for key in keys.iter() {
+            let key = key.unwrap();
+            exists.push_back(match hot.get(key.clone()) {
+                Some(claimed) => claimed.unwrap(),
+                None => e.data().has(DataKey::Owner(key)),
+            });
+        }
+        exists
+    }
+
+    /// Sets whether contracts are kept from claiming keys for themselves, in
+    /// which case they have to claim them for their users with `claim_for`.
+    /// Off by default. Admin only.
+    pub fn set_strict(e: Env, strict: bool) {
+        require_admin(&e);
+        update_settings(&e, |s| s.strict = strict);
+    }
+
+    /// Claims the unclaimed `key` with `value` on behalf of `beneficiary`,
+    /// who becomes its owner instead of the invoker. This is how contracts
+    /// routing user calls claim keys under `set_strict`. Traps with
+    /// `AlreadyExists` if the key is claimed.
+    pub fn claim_for(e: Env, key: Bytes, value: Identifier, beneficiary: Identifier) {
+        if e.data().has(DataKey::Owner(key.clone())) {
+            panic_error!(&e, Error::AlreadyExists)
+        }
+
+        check_claim_for(&e, &key, &beneficiary);
+        write_val(&e, key, beneficiary, value);
+    }
+
+    /// Limits the size of the values written to the registry to `bytes` once
+    /// serialized, 0 meaning no limit. Values already stored are not affected.
+    /// Admin only.
+    pub fn set_maxval(e: Env, bytes: u32) {
+        require_admin(&e);
+        update_settings(&e, |s| s.max_val = bytes);
+    }
+
+    /// Limits the keys a single identifier can own to `max`, 0 meaning no
+    /// limit. Claims and transfers that would take an owner past it trap with
+    /// `NotAllowed`, while owners already past it keep their keys. Admin
+    /// only.
+    pub fn set_maxown(e: Env, max: u32) {
+        require_admin(&e);
+        update_settings(&e, |s| s.max_owned = max);
+    }
+
+    /// Limits the identifiers `grant` can give permissions on a single key to
+    /// `max`, 0 meaning no limit. Granting to one more traps with
+    /// `NotAllowed`, while keys already past the limit keep their delegates.
+    /// Admin only.
+    pub fn set_maxdlg(e: Env, max: u32) {
+        require_admin(&e);
+        update_settings(&e, |s| s.max_dlg = max);
+    }
+
+    /// Returns the number of identifiers holding permissions on `key`.
+    pub fn dlg_count(e: Env, key: Bytes) -> u32 {
+        grants_of(&e, &key).len()
+    }
+
+    /// Stores `blob` next to `key` as opaque bytes anyone can read, such as a
+    /// ciphertext of recovery data. Only those who can change the value of the
+    /// key can set it, and transfers clear it. Traps with `BlobTooLarge` for
+    /// blobs over the size limit. The blob is untagged, see `set_typed`.
+    pub fn set_sealed(e: Env, key: Bytes, blob: Bytes) {
+        require_perm(&e, &key, Permission::Update);
+        check_invoker_write(&e, &key);
+        check_writable(&e, &key);
+
+        let max = settings_of(&e).max_blob;
+        if blob.len() > max {
+            panic_error!(&e, Error::BlobTooLarge)
+        }
+        e.data().remove(DataKey::BlobType(key.clone()));
+        e.data().set(DataKey::Blob(key), blob);
+    }
 
-            e.data().set(key, value)
+    /// Stores `blob` next to `key` like `set_sealed`, tagged with `tag` so
+    /// readers know how to decode it, such as `i128`, `symbol` or `bytes`.
+    pub fn set_typed(e: Env, key: Bytes, blob: Bytes, tag: Symbol) {
+        Self::set_sealed(e.clone(), key.clone(), blob);
+        e.data().set(DataKey::BlobType(key), tag);
+    }
+
+    /// Returns the tag the blob of `key` was stored with by `set_typed`, or
+    /// `bytes` for blobs stored with `set_sealed`. Traps with `NoValue` if the
+    /// key has no blob.
+    pub fn value_type(e: Env, key: Bytes) -> Symbol {
+        if !e.data().has(DataKey::Blob(key.clone())) {
+            panic_error!(&e, Error::NoValue)
+        }
+        e.data()
+            .get(DataKey::BlobType(key))
+            .unwrap_or(Ok(symbol!("bytes")))
+            .unwrap()
+    }
+
+    /// Returns the blob stored next to `key` with `set_sealed`, if any.
+    pub fn get_sealed(e: Env, key: Bytes) -> Option<Bytes> {
+        e.data().get(DataKey::Blob(key)).map(Result::unwrap)
+    }
+
+    /// Sets the size limit of `set_sealed` blobs. Admin only.
+    pub fn blob_limit(e: Env, bytes: u32) {
+        require_admin(&e);
+        update_settings(&e, |s| s.max_blob = bytes);
+    }
+
+    /// Returns how many keys were claimed and removed since the contract was
+    /// deployed and how many are currently claimed. Claims of removed keys
+    /// count again, while updates and transfers of claimed keys don't count.
+    /// `writes` counts every value written, overwrites included.
+    ///
+    /// Named `get_stats` as a function called `stats` would clash with the
+    /// `Stats` type in the contract spec.
+    pub fn get_stats(e: Env) -> Stats {
+        stats_of(&e)
+    }
+
+    /// Returns how many values were written since the contract was deployed,
+    /// overwrites included. The count never goes down, removals included, and
+    /// writes of the value already stored don't count.
+    pub fn writes(e: Env) -> u64 {
+        stats_of(&e).writes
+    }
+
+    /// Returns whether the contract is initialized, its admin, how many keys
+    /// are claimed and the registry settings, so monitoring tools and clients
+    /// can read them in one call.
+    ///
+    /// Named `get_config` as a function called `config` would clash with the
+    /// `Config` type in the contract spec.
+    pub fn get_config(e: Env) -> Config {
+        let settings = settings_of(&e);
+        Config {
+            init: e.data().has(DataKey::Admin),
+            paused: settings.paused,
+            admin: e
+                .data()
+                .get(DataKey::Admin)
+                .map(Result::unwrap)
+                .filter(|admin| !same_id(admin, &Identifier::Contract(e.current_contract())))
+                .into(),
+            events: settings.verbosity != symbol!("off"),
+            verbosity: settings.verbosity,
+            keys: stats_of(&e).active,
+            phase: settings.phase,
+            max_key: settings.max_key,
+            req_ttl: settings.req_ttl,
+            rsv_ttl: settings.rsv_ttl,
+            tomb_ttl: settings.tomb_ttl,
+            swap_ttl: settings.swap_ttl,
+            token: settings.token,
+            profile: settings.profile,
+            stage: settings.stage,
+            fallback: settings.fallback,
+            ns_gated: settings.ns_gated,
+            strict: settings.strict,
+            max_val: settings.max_val,
+            max_blob: settings.max_blob,
+            time_mode: settings.time_mode,
+            noop_guard: settings.noop_guard,
+            append: settings.append,
+            quorum: settings.quorum,
+            dflt_owner: settings.dflt_owner,
+            max_dlg: settings.max_dlg,
+            max_owned: settings.max_owned,
+            claim_sig: settings.claim_sig,
+            min_bal: settings.min_bal,
+            recv_guard: settings.recv_guard,
         }
     }
 
-    pub fn use_sig(e: Env, sig: Signature, key: Bytes, value: Identifier) {
-        let stored_addr = e
+    /// Returns whether `key` is frozen, locked, private or only writable with
+    /// a signature, all at once.
+    pub fn status(e: Env, key: Bytes) -> KeyStatus {
+        let until: u64 = e
             .data()
-            .get(key.clone())
-            .unwrap_or_else(|| Ok(Identifier::Contract(e.current_contract())))
+            .get(DataKey::Lock(key.clone()))
+            .unwrap_or(Ok(0))
             .unwrap();
+        KeyStatus {
+            frozen: is_frozen(&e, &key),
+            lock_until: if is_locked(&e, &key) { until } else { 0 },
+            private: is_private(&e, &key),
+            sig_only: e.data().has(DataKey::SigOnly(key)),
+        }
+    }
+
+    /// Sets whether deadlines are ledger sequences, with `ledger`, or ledger
+    /// timestamps in seconds, with `time`. This covers every expiry and
+    /// timelock the contract checks, from `lock_for` and `rent_out` deadlines
+    /// to reveal and dispute windows, recovery delays and TTLs, which count
+    /// seconds instead of ledgers in the `time` mode. Deadlines already set
+    /// are not converted. Admin only.
+    pub fn set_tmode(e: Env, mode: Symbol) {
+        require_admin(&e);
+        if mode != symbol!("ledger") && mode != symbol!("time") {
+            panic_error!(&e, Error::InvalidMode)
+        }
+        update_settings(&e, |s| s.time_mode = mode);
+    }
+
+    /// Returns how deadlines are to be read: `ledger` for ledger sequences,
+    /// the default, or `time` for ledger timestamps.
+    pub fn time_mode(e: Env) -> Symbol {
+        time_mode_of(&e)
+    }
+
+    /// Sets whether writes storing the value a key already holds and transfers
+    /// to the current owner trap with `NoOp` instead of silently doing
+    /// nothing. Off by default. Admin only.
+    pub fn noop_guard(e: Env, enabled: bool) {
+        require_admin(&e);
+        update_settings(&e, |s| s.noop_guard = enabled);
+    }
+
+    /// Sets whether keys can only be transferred to contracts that accept them
+    /// by returning true from `can_recv(key, from)`, so keys aren't stranded
+    /// in contracts that can't manage them. Transfers to other contracts trap
+    /// with `NotAllowed`. Off by default. Admin only.
+    pub fn recv_guard(e: Env, enabled: bool) {
+        require_admin(&e);
+        update_settings(&e, |s| s.recv_guard = enabled);
+    }
+
+    /// Records that the invoker owns `key` as of the current ledger and
+    /// returns the attestation's hash, the sha256 of the serialized
+    /// `(contract id, key, owner, ledger)`, which is also published in an
+    /// `attest` event. Owner only.
+    pub fn attest(e: Env, key: Bytes) -> BytesN<32> {
+        require_owner(&e, &key);
+
+        let owner = owner_of(&e, &key);
+        let ledger = e.ledger().sequence();
+        let hash = e.compute_hash_sha256(
+            &(e.current_contract(), key.clone(), owner.clone(), ledger).serialize(&e),
+        );
+        e.data().set(
+            DataKey::Attest(hash.clone()),
+            Attestation { key, owner, ledger },
+        );
+        emit(&e, Level::Full, (TOPIC_ATTEST,), hash.clone());
+        hash
+    }
+
+    /// Returns the attestation with hash `hash`, or `None` if there is none or
+    /// its key is no longer owned by the attested owner.
+    pub fn check_att(e: Env, hash: BytesN<32>) -> Option<Attestation> {
+        let attestation: Attestation = e.data().get(DataKey::Attest(hash))?.unwrap();
+        if !e.data().has(DataKey::Owner(attestation.key.clone()))
+            || !same_id(&owner_of(&e, &attestation.key), &attestation.owner)
+        {
+            return None;
+        }
+        Some(attestation)
+    }
+
+    /// Returns everyone who can change the value of `key` on their own: its
+    /// owner, the owner's parent and those granted `Update`. For co-owned keys
+    /// that is the co-owners, if single co-owners may update the value, and no
+    /// one otherwise. Unclaimed keys have no writers.
+    pub fn writers(e: Env, key: Bytes) -> Vec<Identifier> {
+        let mut writers = Vec::new(&e);
+        if !e.data().has(DataKey::Owner(key.clone())) {
+            return writers;
+        }
+
+        let shares = shares_of(&e, &key);
+        if !shares.is_empty() {
+            if e.data().has(DataKey::SoloUpd(key)) {
+                for share in shares.iter() {
+                    writers.push_back(share.unwrap().owner);
+                }
+            }
+            return writers;
+        }
+
+        let owner = owner_of(&e, &key);
+        writers.push_back(owner.clone());
+        if let Some(parent) = parent_of(&e, &owner) {
+            writers.push_back(parent);
+        }
+        for (who, perms) in grants_of(&e, &key).iter().map(Result::unwrap) {
+            if perms.contains(Permission::Update) && !contains_id(&writers, &who) {
+                writers.push_back(who);
+            }
+        }
+        writers
+    }
+
+    /// Sets the contract `hook` to have `on_update(key, old, new)` called with
+    /// the previous and new value after every write to the value of `key`.
+    /// When the key had no value `old` is the registry's own identifier. A
+    /// trapping hook doesn't revert the write and is reported with a
+    /// `hook_fail` event instead. The hook is dropped when the key is
+    /// transferred or removed. Owner only.
+    ///
+    /// The callback is named `on_update` because contract function names are
+    /// limited to 10 characters.
+    pub fn set_hook(e: Env, key: Bytes, hook: BytesN<32>) {
+        require_owner(&e, &key);
+        e.data().set(DataKey::Hook(key), hook);
+    }
+
+    /// Publishes the `set` events of `key` with `topic` as a second topic, so
+    /// indexers can group keys by an application-defined category. The topic
+    /// is dropped when the key is transferred or removed. Owner only.
+    pub fn set_topic(e: Env, key: Bytes, topic: Symbol) {
+        require_owner(&e, &key);
+        e.data().set(DataKey::Topic(key), topic);
+    }
+
+    /// Removes the hook set with `set_hook`. Owner only.
+    pub fn clear_hook(e: Env, key: Bytes) {
+        require_owner(&e, &key);
+        e.data().remove(DataKey::Hook(key));
+    }
+
+    /// Sets whether the value of `key` can only be written once, in which case
+    /// value writes trap with `ValueLocked` once it has a value. Transfers of
+    /// ownership aren't affected and the flag is kept across them, but dropped
+    /// when the key is removed. Owner only.
+    pub fn write_once(e: Env, key: Bytes, once: bool) {
+        require_owner(&e, &key);
+        if once {
+            e.data().set(DataKey::WriteOnce(key), ());
+        } else {
+            e.data().remove(DataKey::WriteOnce(key));
+        }
+    }
+
+    /// Moves `key` from its owner to `new_id`, another identity of the same
+    /// person, such as the Stellar account taking over from an ed25519 key.
+    /// `sig` must come from the current owner over `(nonce, key, new_id)`.
+    /// Unlike a transfer the key keeps its version, permissions, guardian and
+    /// hooks, and a `rebound` event with the key, the old and the new identity
+    /// records the change. `owner_at` answers `new_id` for the current
+    /// version from then on.
+    pub fn rebind(e: Env, sig: Signature, key: Bytes, new_id: Identifier) {
+        let owner = owner_of(&e, &key);
+        if !same_id(&sig.identifier(&e), &owner) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        if !shares_of(&e, &key).is_empty() {
+            panic_error!(&e, Error::CoOwned)
+        }
+        check_writable(&e, &key);
+        verify_nonce(&e, &sig, symbol!("rebind"), (key.clone(), new_id.clone()));
+
+        move_key(&e, &key, Some(owner.clone()), Some(new_id.clone()));
+        e.data().set(DataKey::Owner(key.clone()), new_id.clone());
+        record_owner(&e, &key, new_id.clone(), version_of(&e, &key));
+        emit(&e, Level::Minimal, (TOPIC_REBOUND,), (key, owner, new_id));
+    }
+
+    /// Makes the registry append-only for good: keys can still be claimed,
+    /// but from then on their value, owner, text and blob can't be changed
+    /// and they can't be removed, by anyone including the admin. Such writes
+    /// trap with `ValueLocked`. Admin only.
+    pub fn set_append(e: Env) {
+        require_admin(&e);
+        update_settings(&e, |s| s.append = true);
+    }
+
+    /// Blocks `id` from being written as a value or receiving keys, trapping
+    /// such writes and transfers with `NotAllowed`. Unlike `disallow`, this
+    /// doesn't stop `id` from claiming keys. Entries already holding `id` are
+    /// left as they are and flagged by `is_blocked`. Admin only.
+    pub fn block_val(e: Env, id: Identifier) {
+        require_admin(&e);
+        e.data().set(Setting::Blocked(id), ());
+    }
+
+    /// Blocks `id` like `block_val` and bars it from claiming and writing
+    /// keys too, namespaced keys and keys it was granted permissions on
+    /// included, whether invoking or signing, trapping with `NotAllowed`. Keys
+    /// it already owns become read-only: they can still be read, but neither
+    /// it nor its delegates can write, transfer or remove them until
+    /// `unblock`. Admin operations such as `admin_del` still apply to them.
+    /// Admin only.
+    pub fn block(e: Env, id: Identifier) {
+        require_admin(&e);
+        e.data().set(Setting::Blocked(id.clone()), ());
+        e.data().set(Setting::Barred(id), ());
+    }
 
-        if stored_addr == Identifier::Contract(e.current_contract()) {
-            e.data().set(key, value);
+    /// Lifts a block set with `block_val` or `block`. Both are lifted at once,
+    /// so an identifier blocked with `block` can be written as a value again
+    /// too. Admin only.
+    pub fn unblock(e: Env, id: Identifier) {
+        require_admin(&e);
+        e.data().remove(Setting::Blocked(id.clone()));
+        e.data().remove(Setting::Barred(id));
+    }
+
+    /// Restricts `id` to what it already has: it keeps its keys and can keep
+    /// updating them, and keys already pointing at it keep doing so, but it
+    /// can't claim or receive keys anymore nor become the value of another
+    /// key. Such acquisitions trap with `NotAllowed`. Admin only.
+    pub fn restrict(e: Env, id: Identifier) {
+        require_admin(&e);
+        e.data().set(Setting::Restricted(id), ());
+    }
+
+    /// Lifts a restriction set with `restrict`. Admin only.
+    pub fn unrestrict(e: Env, id: Identifier) {
+        require_admin(&e);
+        e.data().remove(Setting::Restricted(id));
+    }
+
+    /// Returns how `id` is limited: `banned` if it was blocked with `block`,
+    /// `blocked` if it was only blocked as a value with `block_val`,
+    /// `restricted` if it was restricted with `restrict` and `none`
+    /// otherwise.
+    pub fn restr_of(e: Env, id: Identifier) -> Symbol {
+        if e.data().has(Setting::Barred(id.clone())) {
+            symbol!("banned")
+        } else if e.data().has(Setting::Blocked(id.clone())) {
+            symbol!("blocked")
+        } else if e.data().has(Setting::Restricted(id)) {
+            symbol!("restricted")
+        } else {
+            symbol!("none")
+        }
+    }
+
+    /// Returns whether the value of `key` is an identifier blocked with
+    /// `block_val`, false if the key holds no value.
+    pub fn is_blocked(e: Env, key: Bytes) -> bool {
+        value_blocked(&e, &key)
+    }
+
+    /// Makes `owner` the implicit owner of every unset key: only `owner` can
+    /// claim them then, past the phase, allowlist and reserved prefixes, and
+    /// claims by anyone else are refused as writes to someone else's key.
+    /// Setting it back to the contract restores open claims. Admin only.
+    pub fn set_downer(e: Env, owner: Identifier) {
+        require_admin(&e);
+        if same_id(&owner, &unclaimed(&e)) {
+            update_settings(&e, |s| s.dflt_owner = MaybeId::None);
+        } else {
+            update_settings(&e, |s| s.dflt_owner = MaybeId::Some(owner));
+        }
+    }
+
+    /// Returns the implicit owner of unset keys, the contract itself unless
+    /// the admin changed it with `set_downer`.
+    pub fn dflt_owner(e: Env) -> Identifier {
+        default_owner(&e)
+    }
+
+    /// Reserves a nonce for the signer of `sig`, which covers `(nonce)` with
+    /// their current nonce, and returns it. The reserved nonce is taken from
+    /// the sequence, so relayers preparing payloads in parallel can each sign
+    /// with their own instead of racing for the current one. `use_sig`
+    /// accepts it once, within `RESERVE_TTL` ledgers of the reservation or
+    /// as many as the admin set with `set_rsvttl`.
+    pub fn rsv_nonce(e: Env, sig: Signature) -> BigInt {
+        verify_nonce(&e, &sig, symbol!("rsv_nonce"), ());
+
+        let signer = sig.identifier(&e);
+        let nonce = nonce_of(&e, &signer);
+        bump_nonce(&e, signer.clone());
+
+        let mut reserved = reserved_of(&e, &signer);
+        reserved.set(nonce.clone(), now(&e));
+        set_reserved(&e, &signer, reserved);
+        nonce
+    }
+
+    /// Rents `key` out to the signer of `renter` until `until`, for
+    /// `price` tokens the renter must have approved the contract to spend.
+    /// The renter agrees by signing `(nonce, key, until, price)` with their
+    /// current nonce, so approvals given for offers or fees can't be spent on
+    /// rentals the renter never asked for. Until then only the renter can
+    /// change the value of the key, the owner included, while ownership stays
+    /// put. Once the rental runs out the owner is back in control, and the
+    /// value stays whatever the renter last set. The price is held in escrow
+    /// until `end_rent`, and removing the key in the meantime traps with
+    /// `ValueLocked`. Traps with `AlreadyExists` if the key is already rented
+    /// out and with `InvalidPolicy` if `until` has passed or `price` is
+    /// negative. Owner only.
+    pub fn rent_out(e: Env, key: Bytes, renter: Signature, until: u64, price: BigInt) {
+        require_owner(&e, &key);
+        if e.data().has(KeyExtra::Rental(key.clone())) {
+            panic_error!(&e, Error::AlreadyExists)
+        }
+        if expired(&e, until) || price < 0 {
+            panic_error!(&e, Error::InvalidPolicy)
+        }
+        verify_nonce(
+            &e,
+            &renter,
+            symbol!("rent_out"),
+            (key.clone(), until, price.clone()),
+        );
+
+        let renter = renter.identifier(&e);
+        if !price.is_zero() {
+            token_client(&e).xfer_from(
+                &Signature::Invoker,
+                &BigInt::zero(&e),
+                &renter,
+                &Identifier::Contract(e.current_contract()),
+                &price,
+            );
+        }
+        let rental = Rental {
+            renter,
+            start: now(&e),
+            until,
+            price,
+        };
+        e.data().set(KeyExtra::Rental(key), rental);
+    }
+
+    /// Ends the rental of `key` and pays out its escrow: the owner gets the
+    /// share of the price for the ledgers the rental ran and the renter gets
+    /// the rest back. A rental that ran out can be ended by the owner or the
+    /// renter alone, passing `Signature::Invoker`. Before that it takes both:
+    /// one of them invokes and the other signs `(nonce, key)` with `sig`.
+    /// Traps with `NoOffer` if the key isn't rented out.
+    pub fn end_rent(e: Env, key: Bytes, sig: Signature) {
+        let rental = rental_of(&e, &key).unwrap_or_else(|| panic_error!(&e, Error::NoOffer));
+        let owner = owner_of(&e, &key);
+        let invoker = invoker_id(&e);
+        let signer = sig.identifier(&e);
+        let agreed = if expired(&e, rental.until) {
+            same_id(&invoker, &owner) || same_id(&invoker, &rental.renter)
         } else {
-            if stored_addr != sig.identifier(&e) {
-                panic!("you are not allowed to change this value")
+            same_id(&invoker, &owner) && same_id(&signer, &rental.renter)
+                || same_id(&invoker, &rental.renter) && same_id(&signer, &owner)
+        };
+        if !agreed {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        verify_nonce(&e, &sig, symbol!("end_rent"), (key.clone(),));
+        e.data().remove(KeyExtra::Rental(key));
+
+        if rental.price.is_zero() {
+            return;
+        }
+        let elapsed = now(&e).min(rental.until) - rental.start;
+        let earned = match rental.until - rental.start {
+            0 => rental.price.clone(),
+            duration => rental.price.clone() * elapsed / duration,
+        };
+        let refund = rental.price - earned.clone();
+        let token = token_client(&e);
+        if !earned.is_zero() {
+            token.xfer(&Signature::Invoker, &BigInt::zero(&e), &owner, &earned);
+        }
+        if !refund.is_zero() {
+            token.xfer(
+                &Signature::Invoker,
+                &BigInt::zero(&e),
+                &rental.renter,
+                &refund,
+            );
+        }
+    }
+
+    /// Returns the renter of `key` and when its rental runs until, if it
+    /// is rented out and the rental hasn't run out yet.
+    ///
+    /// Named `get_rental` as a function called `rental` would clash with the
+    /// `Rental` type in the contract spec.
+    pub fn get_rental(e: Env, key: Bytes) -> Option<(Identifier, u64)> {
+        active_rental(&e, &key).map(|rental| (rental.renter, rental.until))
+    }
+
+    /// Opens an empty journal of admin operations and returns its id. Admin
+    /// only.
+    pub fn jrnl_begin(e: Env) -> u32 {
+        require_admin(&e);
+        let id: u32 = e.data().get(Setting::Journals).unwrap_or(Ok(0)).unwrap();
+        e.data().set(Setting::Journals, id + 1);
+        e.data().set(Setting::Journal(id), Vec::<AdminOp>::new(&e));
+        id
+    }
+
+    /// Records `op` at the end of the journal `id` without applying it. Traps
+    /// with `NoRequest` if there is no such journal. Admin only.
+    pub fn jrnl_add(e: Env, id: u32, op: AdminOp) {
+        require_admin(&e);
+        let mut ops = journal_of(&e, id);
+        ops.push_back(op);
+        e.data().set(Setting::Journal(id), ops);
+    }
+
+    /// Applies the operations of the journal `id` in order and removes it,
+    /// failing with `NoRequest` if there is no such journal.
+    /// Every operation is checked before the first is applied, and if one
+    /// would fail its error is returned with nothing applied and the journal
+    /// kept for `jrnl_abort`. An operation that only fails because of an
+    /// earlier one in the same journal still traps, undoing the whole
+    /// journal. Admin only.
+    pub fn jrnl_apply(e: Env, id: u32) -> Result<(), Error> {
+        require_admin(&e);
+        let ops: Vec<AdminOp> = e
+            .data()
+            .get(Setting::Journal(id))
+            .ok_or(Error::NoRequest)?
+            .unwrap();
+        for op in ops.iter() {
+            check_op(&e, &op.unwrap())?;
+        }
+
+        e.data().remove(Setting::Journal(id));
+        for op in ops.iter() {
+            match op.unwrap() {
+                AdminOp::Phase(phase) => Self::set_phase(e.clone(), phase),
+                AdminOp::Reassign(KeyOwner { key, owner }) => {
+                    Self::resolve(e.clone(), key, symbol!("uphold"), Some(owner))
+                }
+                AdminOp::Dismiss(key) => Self::resolve(e.clone(), key, symbol!("dismiss"), None),
+                AdminOp::Token(token) => Self::set_token(e.clone(), token),
+                AdminOp::Allow(id) => Self::allow(e.clone(), id),
+                AdminOp::Disallow(id) => Self::disallow(e.clone(), id),
+                AdminOp::Block(id) => Self::block_val(e.clone(), id),
+                AdminOp::Unblock(id) => Self::unblock(e.clone(), id),
             }
+        }
+        Ok(())
+    }
+
+    /// Drops the journal `id` without applying any of it. Traps with
+    /// `NoRequest` if there is no such journal. Admin only.
+    pub fn jrnl_abort(e: Env, id: u32) {
+        require_admin(&e);
+        journal_of(&e, id);
+        e.data().remove(Setting::Journal(id));
+    }
 
-            verify(&e, &sig, symbol!("change"), (key.clone(), value.clone()));
+    /// Makes `value` what `get_or` returns for the keys owned by the signer
+    /// of `sig` that were claimed without a value, ahead of the registry
+    /// default, or removes the wildcard if `value` is `None`. The signature
+    /// covers `(nonce, value)`, and a value `change_val` would refuse traps
+    /// the same way.
+    pub fn set_wild(e: Env, sig: Signature, value: Option<Identifier>) {
+        verify_nonce(&e, &sig, symbol!("set_wild"), (value.clone(),));
 
-            e.data().set(key, value)
+        let id = sig.identifier(&e);
+        match value {
+            Some(value) => {
+                enforce(&e, valid_value(&e, &value));
+                e.data().set(KeyExtra::Wildcard(id), value)
+            }
+            None => e.data().remove(KeyExtra::Wildcard(id)),
         }
     }
 
-    pub fn get(e: Env, key: Bytes) -> Identifier {
+    /// Returns the wildcard `id` set with `set_wild`, if any.
+    pub fn wildcard(e: Env, id: Identifier) -> Option<Identifier> {
         e.data()
-            .get(key)
-            .unwrap_or_else(|| panic!("Key does not exist"))
-            .unwrap()
+            .get(KeyExtra::Wildcard(id))
+            .map(|value| value.unwrap())
+    }
+
+    /// Sets the identifier whose signatures `redeem` accepts, so claims can be
+    /// pre-authorized off-chain for the users meant to redeem them. Admin
+    /// only.
+    pub fn set_csig(e: Env, id: Identifier) {
+        require_admin(&e);
+        update_settings(&e, |s| s.claim_sig = MaybeId::Some(id));
+    }
+
+    /// Claims `key` for the invoker without setting a value, like `hold_key`,
+    /// on the strength of `sig`, a signature by the identifier set with
+    /// `set_csig` over `(key, invoker)`. Signatures by anyone else trap with
+    /// `NotAuthorized`, and as the signer isn't the future owner no nonce is
+    /// involved: each key can be redeemed once, later redemptions trapping
+    /// with `AlreadyExists` like claims of claimed keys.
+    pub fn redeem(e: Env, sig: Signature, key: Bytes) {
+        let signer = Option::from(settings_of(&e).claim_sig)
+            .unwrap_or_else(|| panic_error!(&e, Error::NotAuthorized));
+        if !same_id(&sig.identifier(&e), &signer) {
+            panic_error!(&e, Error::NotAuthorized)
+        }
+        if e.data().has(KeyExtra::Redeemed(key.clone()))
+            || e.data().has(DataKey::Owner(key.clone()))
+        {
+            panic_error!(&e, Error::AlreadyExists)
+        }
+
+        let invoker_id = invoker_id(&e);
+        verify(
+            &e,
+            &sig,
+            symbol!("redeem"),
+            (key.clone(), invoker_id.clone()),
+        );
+        check_claim(&e, &key, &invoker_id);
+        e.data().set(KeyExtra::Redeemed(key.clone()), ());
+        touch(&e, &key, invoker_id.clone());
+        emit(&e, Level::Minimal, (TOPIC_HELD,), (key, invoker_id));
     }
 }
 
+pub mod token;
+
+pub mod topics;
+
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+
+#[cfg(any(test, feature = "testutils"))]
+pub mod invariants;
+
 #[cfg(test)]
 mod test;
@@ -1,6 +1,29 @@
-use crate::{ExampleContract, ExampleContractClient};
-use soroban_auth::Identifier;
-use soroban_sdk::{bytes, testutils::Accounts, Env};
+use crate::invariants::check_invariants;
+use crate::testutils::{
+    build_change_args, parse_events, sign_account, DeleteEvent, HookFailEvent, NonceEvent,
+    ReboundEvent, RegistryEvent, SetEvent, TransferEvent,
+};
+use crate::topics::{
+    TOPIC_ACCEPTED, TOPIC_ATTEST, TOPIC_DELETE, TOPIC_DISPUTE, TOPIC_HELD, TOPIC_HOOK_FAIL,
+    TOPIC_NONCE, TOPIC_PROPOSED, TOPIC_REBOUND, TOPIC_RECOVERED, TOPIC_RELEASED, TOPIC_RENOUNCED,
+    TOPIC_RESOLVED, TOPIC_SET, TOPIC_TRANSFER, TOPIC_VERBOSITY, TOPIC_VETOED,
+};
+use crate::{
+    same_id, token, Action, AdminOp, Attestation, Config, DataKey, Entry, Error, ExampleContract,
+    ExampleContractClient, KeyMeta, KeyOwner, KeyStatus, MaybeId, MaybeMin, MaybeQuorum,
+    MaybeToken, OfferKey, PageKey, Part, Permission, Record, Recovery, Share, SignerState, Stats,
+    StoredEntry, DISPUTE_WINDOW, GUARDIAN_DELAY, INDEX_PAGE, MAX_BLOB, MAX_MEMO, MAX_SUBSCRIBERS,
+    OWNER_HISTORY, RECOVERY_DELAY, REQUEST_TTL, RESERVE_TTL, REVEAL_WINDOW, SPLIT_DENOMINATOR,
+    STATE_VERSION, SWAP_TTL, TOMBSTONE_TTL,
+};
+use soroban_auth::{Identifier, Signature};
+use soroban_sdk::{
+    bytes, contractimpl,
+    serde::Serialize,
+    symbol,
+    testutils::{Accounts, ContractFunctionSet, Events, Ledger},
+    AccountId, BigInt, Bytes, BytesN, Env, IntoVal, Map, RawVal, Symbol, TryIntoVal, Vec,
+};
 
 #[test]
 fn test_change_val() {
@@ -33,6 +56,7 @@ fn test_use_sig() {
 
     client.with_source_account(&user).use_sig(
         &soroban_auth::Signature::Invoker,
+        &BigInt::zero(&e),
         &bytes!(&e, 0x68656c6c6f),
         &Identifier::Account(user.clone()),
     );
@@ -69,3 +93,8737 @@ fn test_invalid_invoker() {
         &Identifier::Account(user2.clone()),
     );
 }
+
+#[test]
+fn test_get_meta() {
+    let e = Env::default();
+    e.ledger().with_mut(|l| l.sequence_number = 10);
+
+    let user = e.accounts().generate();
+    let value = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(value.clone()),
+    );
+
+    assert_eq!(
+        client
+            .with_source_account(&user)
+            .get_meta(&bytes!(&e, 0x68656c6c6f)),
+        KeyMeta {
+            value: Identifier::Account(value.clone()),
+            owner: Identifier::Account(user.clone()),
+            modified: 10,
+            frozen: false,
+            private: false,
+        }
+    );
+
+    client
+        .with_source_account(&user)
+        .set_priv(&bytes!(&e, 0x68656c6c6f), &true);
+    client
+        .with_source_account(&user)
+        .freeze(&bytes!(&e, 0x68656c6c6f));
+
+    assert_eq!(
+        client
+            .with_source_account(&user)
+            .get_meta(&bytes!(&e, 0x68656c6c6f)),
+        KeyMeta {
+            value: Identifier::Account(value),
+            owner: Identifier::Account(user),
+            modified: 10,
+            frozen: true,
+            private: true,
+        }
+    );
+}
+
+#[test]
+fn test_entry() {
+    let e = Env::default();
+    e.ledger().with_mut(|l| l.sequence_number = 5);
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+
+    e.ledger().with_mut(|l| l.sequence_number = 8);
+    client.with_source_account(&user).use_sig(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(&e),
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(e.accounts().generate()),
+    );
+    client
+        .with_source_account(&user)
+        .freeze(&bytes!(&e, 0x68656c6c6f));
+
+    assert_eq!(
+        client.get_entry(&bytes!(&e, 0x68656c6c6f)),
+        Entry {
+            owner: Identifier::Account(user),
+            expires: 0,
+            modified: 8,
+            locked: true,
+            version: 2,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(1))")]
+fn test_entry_missing_key() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.get_entry(&bytes!(&e, 0x68656c6c6f));
+}
+
+#[test]
+fn test_push_pop_val() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let a = e.accounts().generate();
+    let b = e.accounts().generate();
+    let c = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    for id in [&a, &b, &c] {
+        client
+            .with_source_account(&user)
+            .push_val(&bytes!(&e, 0x67726f7570), &Identifier::Account(id.clone()));
+    }
+
+    let client = client.with_source_account(&user);
+    assert_eq!(
+        client.pop_val(&bytes!(&e, 0x67726f7570)),
+        Identifier::Account(c)
+    );
+    assert_eq!(
+        client.pop_val(&bytes!(&e, 0x67726f7570)),
+        Identifier::Account(b)
+    );
+    assert_eq!(
+        client.pop_val(&bytes!(&e, 0x67726f7570)),
+        Identifier::Account(a)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(2))")]
+fn test_pop_val_empty() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.with_source_account(&user).push_val(
+        &bytes!(&e, 0x67726f7570),
+        &Identifier::Account(user.clone()),
+    );
+    client
+        .with_source_account(&user)
+        .pop_val(&bytes!(&e, 0x67726f7570));
+    client
+        .with_source_account(&user)
+        .pop_val(&bytes!(&e, 0x67726f7570));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(3))")]
+fn test_reserved_value() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+
+    // A third party tricking the owner into writing the contract identifier
+    // cannot free the key for themselves.
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Contract(contract_id.clone()),
+    );
+}
+
+#[test]
+fn test_noop_write() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+
+    assert_eq!(client.get_entry(&bytes!(&e, 0x68656c6c6f)).version, 1);
+
+    client
+        .with_source_account(&user)
+        .remove_val(&bytes!(&e, 0x68656c6c6f));
+    client.with_source_account(&other).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(other.clone()),
+    );
+
+    assert_eq!(
+        client.get(&bytes!(&e, 0x68656c6c6f)),
+        Identifier::Account(other)
+    );
+}
+
+#[test]
+fn test_get_admin() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    assert_eq!(client.get_admin(), Identifier::Account(admin));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(4))")]
+fn test_get_admin_before_initialize() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.get_admin();
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(4))")]
+fn test_admin_method_before_initialize() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.with_source_account(&user).pause();
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(7))")]
+fn test_pause() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).pause();
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+fn commit_hash(e: &Env, key: &Bytes, salt: &Bytes) -> BytesN<32> {
+    let mut preimage = key.clone();
+    preimage.append(salt);
+    e.compute_hash_sha256(&preimage)
+}
+
+#[test]
+fn test_commit_reveal() {
+    let e = Env::default();
+    e.ledger().with_mut(|l| l.sequence_number = 1);
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let hash = commit_hash(&e, &bytes!(&e, 0x68656c6c6f), &bytes!(&e, 0x73616c74));
+    client.with_source_account(&user).commit(&hash);
+
+    assert_eq!(
+        client.commit_of(&hash),
+        Some((Identifier::Account(user.clone()), 1))
+    );
+
+    e.ledger().with_mut(|l| l.sequence_number = 2);
+    client.with_source_account(&user).reveal(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+        &bytes!(&e, 0x73616c74),
+    );
+
+    assert_eq!(client.commit_of(&hash), None);
+    assert_eq!(
+        client.get(&bytes!(&e, 0x68656c6c6f)),
+        Identifier::Account(user)
+    );
+}
+
+#[test]
+fn test_cancel_commit() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let hash = commit_hash(&e, &bytes!(&e, 0x68656c6c6f), &bytes!(&e, 0x73616c74));
+    client.with_source_account(&user).commit(&hash);
+    client.with_source_account(&user).cancel_cmt(&hash);
+
+    assert_eq!(client.commit_of(&hash), None);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_cancel_commit_stranger() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let stranger = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let hash = commit_hash(&e, &bytes!(&e, 0x68656c6c6f), &bytes!(&e, 0x73616c74));
+    client.with_source_account(&user).commit(&hash);
+    client.with_source_account(&stranger).cancel_cmt(&hash);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(11))")]
+fn test_sweep_commit_before_expiry() {
+    let e = Env::default();
+    e.ledger().with_mut(|l| l.sequence_number = 1);
+
+    let user = e.accounts().generate();
+    let stranger = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let hash = commit_hash(&e, &bytes!(&e, 0x68656c6c6f), &bytes!(&e, 0x73616c74));
+    client.with_source_account(&user).commit(&hash);
+
+    e.ledger()
+        .with_mut(|l| l.sequence_number = 1 + REVEAL_WINDOW);
+    client.with_source_account(&stranger).sweep_cmt(&hash);
+}
+
+#[test]
+fn test_sweep_commit_after_expiry() {
+    let e = Env::default();
+    e.ledger().with_mut(|l| l.sequence_number = 1);
+
+    let user = e.accounts().generate();
+    let stranger = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let hash = commit_hash(&e, &bytes!(&e, 0x68656c6c6f), &bytes!(&e, 0x73616c74));
+    client.with_source_account(&user).commit(&hash);
+
+    e.ledger()
+        .with_mut(|l| l.sequence_number = 2 + REVEAL_WINDOW);
+    client.with_source_account(&stranger).sweep_cmt(&hash);
+
+    assert_eq!(client.commit_of(&hash), None);
+}
+
+#[test]
+fn test_use_hashed() {
+    let e = Env::default();
+
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user);
+    let hash = e.compute_hash_sha256(&(key.clone(), value.clone()).serialize(&e));
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::zero(&e), hash.clone()),
+    );
+
+    client.use_hashed(&sig, &key, &value, &hash);
+
+    assert_eq!(client.get(&key), value);
+    assert_eq!(client.get_entry(&key).owner, signer_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(12))")]
+fn test_use_hashed_payload_mismatch() {
+    let e = Env::default();
+
+    let (_, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let hash = e.compute_hash_sha256(&(key.clone(), Identifier::Account(user)).serialize(&e));
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::zero(&e), hash.clone()),
+    );
+
+    client.use_hashed(&sig, &key, &Identifier::Account(other), &hash);
+}
+
+fn setup_token(e: &Env, admin: &AccountId) -> (BytesN<32>, token::Client) {
+    let id = e.register_contract_token(None);
+    let token = token::Client::new(e, &id);
+    token.init(
+        &Identifier::Account(admin.clone()),
+        &token::TokenMetadata {
+            name: bytes!(e, 0x6e616d65),
+            symbol: bytes!(e, 0x73796d),
+            decimals: 7,
+        },
+    );
+    (id, token)
+}
+
+/// Registers the contract with `admin` and an escrow token, minting `amount`
+/// to `bidder` and approving the contract to spend it.
+fn setup_offers(
+    e: &Env,
+    admin: &AccountId,
+    bidder: &AccountId,
+    amount: u32,
+) -> (ExampleContractClient, token::Client) {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let (token_id, token) = setup_token(e, admin);
+    client.with_source_account(admin).set_token(&token_id);
+    token.with_source_account(admin).mint(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(e),
+        &Identifier::Account(bidder.clone()),
+        &BigInt::from_u32(e, amount),
+    );
+    token.with_source_account(bidder).approve(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(e),
+        &Identifier::Contract(contract_id),
+        &BigInt::from_u32(e, amount),
+    );
+
+    (client, token)
+}
+
+#[test]
+fn test_accept_offer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let owner = e.accounts().generate();
+    let bidder = e.accounts().generate();
+
+    let (client, token) = setup_offers(&e, &admin, &bidder, 100);
+
+    client.with_source_account(&owner).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(owner.clone()),
+    );
+    client
+        .with_source_account(&bidder)
+        .make_offer(&bytes!(&e, 0x68656c6c6f), &BigInt::from_u32(&e, 60));
+
+    assert_eq!(
+        token.balance(&Identifier::Contract(client.contract_id.clone())),
+        BigInt::from_u32(&e, 60)
+    );
+
+    client.with_source_account(&owner).take_offer(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(bidder.clone()),
+    );
+
+    assert_eq!(
+        client.get_entry(&bytes!(&e, 0x68656c6c6f)).owner,
+        Identifier::Account(bidder.clone())
+    );
+    assert_eq!(
+        token.balance(&Identifier::Account(owner)),
+        BigInt::from_u32(&e, 60)
+    );
+    assert_eq!(
+        token.balance(&Identifier::Account(bidder)),
+        BigInt::from_u32(&e, 40)
+    );
+}
+
+#[test]
+fn test_cancel_offer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let owner = e.accounts().generate();
+    let bidder = e.accounts().generate();
+
+    let (client, token) = setup_offers(&e, &admin, &bidder, 100);
+
+    client.with_source_account(&owner).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(owner.clone()),
+    );
+    client
+        .with_source_account(&bidder)
+        .make_offer(&bytes!(&e, 0x68656c6c6f), &BigInt::from_u32(&e, 60));
+    client
+        .with_source_account(&bidder)
+        .drop_offer(&bytes!(&e, 0x68656c6c6f));
+
+    assert_eq!(
+        token.balance(&Identifier::Account(bidder)),
+        BigInt::from_u32(&e, 100)
+    );
+    assert_eq!(
+        client.get_entry(&bytes!(&e, 0x68656c6c6f)).owner,
+        Identifier::Account(owner)
+    );
+}
+
+#[test]
+fn test_restore() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let first = e.accounts().generate();
+    let second = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(first.clone()),
+    );
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68656c6c6f), &Identifier::Account(second));
+    client
+        .with_source_account(&user)
+        .restore(&bytes!(&e, 0x68656c6c6f));
+
+    assert_eq!(
+        client.get(&bytes!(&e, 0x68656c6c6f)),
+        Identifier::Account(first)
+    );
+    assert!(client.history(&bytes!(&e, 0x68656c6c6f)).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(20))")]
+fn test_restore_write_once() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(e.accounts().generate()));
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(e.accounts().generate()));
+    client.with_source_account(&user).write_once(&key, &true);
+    client.with_source_account(&user).restore(&key);
+}
+
+#[test]
+fn test_restore_notifies() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let watcher_id = e.register_contract(None, Watcher);
+    let watcher = WatcherClient::new(&e, &watcher_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let first = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &first);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(e.accounts().generate()));
+    client
+        .with_source_account(&user)
+        .subscribe(&key, &Identifier::Contract(watcher_id));
+
+    client.with_source_account(&user).restore(&key);
+    assert_eq!(watcher.seen(&key), Some(first));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(16))")]
+fn test_restore_exhausted() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let first = e.accounts().generate();
+    let second = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68656c6c6f), &Identifier::Account(first));
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68656c6c6f), &Identifier::Account(second));
+    client
+        .with_source_account(&user)
+        .restore(&bytes!(&e, 0x68656c6c6f));
+    client
+        .with_source_account(&user)
+        .restore(&bytes!(&e, 0x68656c6c6f));
+}
+
+fn setup_phase(e: &Env, admin: &AccountId, phase: Symbol) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(admin).set_phase(&phase);
+    client
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(17))")]
+fn test_phase_closed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let client = setup_phase(&e, &admin, symbol!("closed"));
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+fn test_phase_allowlist() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let client = setup_phase(&e, &admin, symbol!("allowlist"));
+    client
+        .with_source_account(&admin)
+        .allow(&Identifier::Account(user.clone()));
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+
+    // Removing the identifier from the allowlist keeps its existing keys
+    // updatable, even once registration is closed.
+    client
+        .with_source_account(&admin)
+        .disallow(&Identifier::Account(user.clone()));
+    client
+        .with_source_account(&admin)
+        .set_phase(&symbol!("closed"));
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(admin.clone()),
+    );
+
+    assert_eq!(
+        client.get(&bytes!(&e, 0x68656c6c6f)),
+        Identifier::Account(admin)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_phase_allowlist_not_listed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let client = setup_phase(&e, &admin, symbol!("allowlist"));
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_phase_allowlist_removed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let client = setup_phase(&e, &admin, symbol!("allowlist"));
+    client
+        .with_source_account(&admin)
+        .allow(&Identifier::Account(user.clone()));
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+    client
+        .with_source_account(&admin)
+        .disallow(&Identifier::Account(user.clone()));
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x776f726c64),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+fn test_phase_open() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let client = setup_phase(&e, &admin, symbol!("open"));
+
+    client.with_source_account(&user).use_sig(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(&e),
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+
+    assert_eq!(
+        client.get(&bytes!(&e, 0x68656c6c6f)),
+        Identifier::Account(user)
+    );
+}
+
+#[test]
+fn test_keys_page() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let keys = [
+        bytes!(&e, 0x01),
+        bytes!(&e, 0x02),
+        bytes!(&e, 0x03),
+        bytes!(&e, 0x04),
+        bytes!(&e, 0x05),
+    ];
+    for key in keys.iter() {
+        client
+            .with_source_account(&user)
+            .change_val(key, &Identifier::Account(user.clone()));
+    }
+
+    let mut paged = Vec::new(&e);
+    let mut start = 0;
+    while start < client.total_keys() {
+        let page = client.keys_page(&start, &2);
+        assert!(page.len() <= 2);
+        paged.append(&page);
+        start += 2;
+    }
+
+    assert_eq!(paged, Vec::from_array(&e, keys));
+    assert!(client.keys_page(&10, &2).is_empty());
+}
+
+#[test]
+fn test_data_key_collision() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let attacker = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+
+    // Keys whose bytes encode exactly like internal records.
+    let owner_key = DataKey::Owner(key.clone()).serialize(&e);
+    let admin_key = DataKey::Admin.serialize(&e);
+    let value_key = DataKey::Value(key.clone()).serialize(&e);
+    assert_eq!(client.keys_page(&0, &10).len(), 1);
+
+    for k in [owner_key, admin_key, value_key] {
+        client
+            .with_source_account(&attacker)
+            .change_val(&k, &Identifier::Account(attacker.clone()));
+        assert_eq!(client.get(&k), Identifier::Account(attacker.clone()));
+    }
+
+    assert_eq!(client.get_admin(), Identifier::Account(admin.clone()));
+    assert_eq!(client.get(&key), Identifier::Account(user.clone()));
+    assert_eq!(
+        client.get_meta(&key).owner,
+        Identifier::Account(user.clone())
+    );
+
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(admin.clone()));
+    assert_eq!(client.get(&key), Identifier::Account(admin));
+    assert_eq!(client.keys_page(&0, &10).len(), 4);
+}
+
+#[test]
+fn test_nonce() {
+    let e = Env::default();
+
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (client.nonce(&signer_id), key.clone(), value.clone()),
+    );
+    client.use_sig(&sig, &BigInt::zero(&e), &key, &value);
+    assert_eq!(client.nonce(&signer_id), BigInt::from_u32(&e, 1));
+
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("revoke"),
+        (client.nonce(&signer_id),),
+    );
+    client.revoke(&sig);
+    assert_eq!(client.nonce(&signer_id), BigInt::from_u32(&e, 2));
+}
+
+#[test]
+#[should_panic]
+fn test_revoked_signature() {
+    let e = Env::default();
+
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user);
+    let nonce = client.nonce(&signer_id);
+    let change = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (nonce.clone(), key.clone(), value.clone()),
+    );
+    let revoke = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("revoke"),
+        (nonce.clone(),),
+    );
+
+    client.revoke(&revoke);
+    client.use_sig(&change, &nonce, &key, &value);
+}
+
+pub struct Consumer;
+
+#[contractimpl]
+impl Consumer {
+    pub fn gated(e: Env, registry: BytesN<32>, key: Bytes, id: Identifier) -> Symbol {
+        if ExampleContractClient::new(&e, registry).is_owner(&key, &id) {
+            symbol!("granted")
+        } else {
+            symbol!("denied")
+        }
+    }
+
+    pub fn lookup(e: Env, registry: BytesN<32>, key: Bytes) -> Option<Identifier> {
+        ExampleContractClient::new(&e, registry).get_opt(&key)
+    }
+}
+
+#[test]
+fn test_is_owner_cross_contract() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let consumer = ConsumerClient::new(&e, e.register_contract(None, Consumer));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+
+    assert_eq!(
+        consumer.gated(&contract_id, &key, &user_id),
+        symbol!("denied")
+    );
+
+    client.with_source_account(&user).change_val(&key, &user_id);
+
+    assert_eq!(
+        consumer.gated(&contract_id, &key, &user_id),
+        symbol!("granted")
+    );
+    assert_eq!(
+        consumer.gated(&contract_id, &key, &Identifier::Account(other)),
+        symbol!("denied")
+    );
+}
+
+fn ed25519_owned(
+    e: &Env,
+) -> (
+    ExampleContractClient,
+    Identifier,
+    impl soroban_auth::testutils::ed25519::Identifier
+        + soroban_sdk::testutils::ed25519::Sign<soroban_auth::SignaturePayload, Signature = [u8; 64]>,
+) {
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(e);
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    let value = Identifier::Account(user);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::zero(e), key.clone(), value.clone()),
+    );
+    client.use_sig(&sig, &BigInt::zero(e), &key, &value);
+
+    (client, signer_id, signer)
+}
+
+#[test]
+fn test_ed25519_owner_use_sig() {
+    let e = Env::default();
+    let (client, signer_id, signer) = ed25519_owned(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(e.accounts().generate());
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &client.contract_id,
+        symbol!("change"),
+        (client.nonce(&signer_id), key.clone(), value.clone()),
+    );
+    client.use_sig(&sig, &client.nonce(&signer_id), &key, &value);
+
+    assert_eq!(client.get(&key), value);
+    assert_eq!(client.get_entry(&key).owner, signer_id);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_ed25519_owner_account_invoker() {
+    let e = Env::default();
+    let (client, _, _) = ed25519_owned(&e);
+
+    let user = e.accounts().generate();
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+fn count_events(e: &Env, name: Symbol) -> u32 {
+    let mut count = 0;
+    for event in e.events().all().iter() {
+        let (_, topics, _) = event.unwrap();
+        let topic: Symbol = topics.get_unchecked(0).unwrap().try_into_val(e).unwrap();
+        if topic == name {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[test]
+fn test_nonce_event() {
+    let e = Env::default();
+
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::zero(&e), key.clone(), value.clone()),
+    );
+    client.use_sig(&sig, &BigInt::zero(&e), &key, &value);
+
+    assert_eq!(count_events(&e, TOPIC_NONCE), 1);
+    assert_eq!(count_events(&e, TOPIC_SET), 1);
+
+    assert_eq!(
+        parse_events(&e, &contract_id),
+        [
+            RegistryEvent::Nonce(NonceEvent {
+                id: signer_id.clone(),
+                nonce: BigInt::from_u32(&e, 1),
+            }),
+            RegistryEvent::Set(SetEvent {
+                hash: client.entry_hash(&key),
+                key,
+                owner: signer_id,
+                value,
+                texts: false,
+            }),
+        ]
+    );
+}
+
+#[test]
+fn test_nonce_event_invoker() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.with_source_account(&user).use_sig(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(&e),
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+
+    assert_eq!(count_events(&e, TOPIC_NONCE), 0);
+    assert_eq!(count_events(&e, TOPIC_SET), 1);
+}
+
+#[test]
+fn test_lock_for() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&user).lock_for(&key, &10);
+    assert!(client.get_entry(&key).locked);
+
+    e.ledger().with_mut(|l| l.sequence_number = 10);
+    assert!(!client.get_entry(&key).locked);
+
+    let other = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &other);
+    assert_eq!(client.get(&key), other);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(20))")]
+fn test_lock_for_blocks_writes() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&user).lock_for(&key, &10);
+
+    e.ledger().with_mut(|l| l.sequence_number = 9);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(e.accounts().generate()));
+}
+
+fn setup_reserved(e: &Env, admin: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client
+        .with_source_account(admin)
+        .reserve(&bytes!(e, 0x5f5f));
+    client.with_source_account(admin).reserve(&bytes!(e, 0x5f));
+    client
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(21))")]
+fn test_reserved_prefix() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_reserved(&e, &admin);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x5f5f6b6579),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+fn test_reserved_prefix_admin() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let client = setup_reserved(&e, &admin);
+
+    let key = bytes!(&e, 0x5f5f6b6579);
+    let admin_id = Identifier::Account(admin.clone());
+    client
+        .with_source_account(&admin)
+        .change_val(&key, &admin_id);
+    assert_eq!(client.get(&key), admin_id);
+}
+
+#[test]
+fn test_reserved_prefix_released() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_reserved(&e, &admin);
+    client
+        .with_source_account(&admin)
+        .release(&bytes!(&e, 0x5f5f));
+    client
+        .with_source_account(&admin)
+        .release(&bytes!(&e, 0x5f));
+
+    let key = bytes!(&e, 0x5f5f6b6579);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.get(&key), user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(21))")]
+fn test_reserved_prefix_overlapping() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_reserved(&e, &admin);
+
+    // The overlapping `_` reservation still applies on its own.
+    client
+        .with_source_account(&admin)
+        .release(&bytes!(&e, 0x5f5f));
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x5f5f6b6579),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+fn test_delete_event() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(other);
+    client.with_source_account(&user).change_val(&key, &value);
+    client.with_source_account(&user).remove_val(&key);
+
+    assert_eq!(count_events(&e, TOPIC_DELETE), 1);
+
+    assert_eq!(
+        parse_events(&e, &contract_id).last(),
+        Some(&RegistryEvent::Delete(DeleteEvent { key, value }))
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(22))")]
+fn test_burn() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&user).burn(&key);
+
+    assert_eq!(client.keys_page(&0, &10).len(), 0);
+    client.get(&key);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(22))")]
+fn test_burn_reclaim() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&admin).burn(&key);
+
+    client.with_source_account(&user).change_val(&key, &user_id);
+}
+
+#[test]
+#[should_panic(expected = "Key does not exist: Bytes(104, 101, 108, 108, 111)")]
+fn test_get_never_existed() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.get(&bytes!(&e, 0x68656c6c6f));
+}
+
+#[test]
+fn test_create_val() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).create_val(&key, &user_id);
+
+    assert_eq!(client.get(&key), user_id);
+    assert_eq!(client.get_entry(&key).owner, user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(23))")]
+fn test_create_val_existing() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).create_val(&key, &user_id);
+    client
+        .with_source_account(&user)
+        .create_val(&key, &Identifier::Account(e.accounts().generate()));
+}
+
+fn setup_grant(
+    e: &Env,
+    owner: &AccountId,
+    who: &AccountId,
+    perm: Permission,
+) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(owner)
+        .change_val(&key, &Identifier::Account(owner.clone()));
+    client.with_source_account(owner).grant(
+        &key,
+        &Identifier::Account(who.clone()),
+        &Vec::from_array(e, [perm]),
+    );
+    client
+}
+
+#[test]
+fn test_grant_metadata() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let editor = e.accounts().generate();
+    let client = setup_grant(&e, &owner, &editor, Permission::Metadata);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&editor)
+        .set_text(&key, &symbol!("url"), &bytes!(&e, 0x6869));
+    assert_eq!(client.text(&key, &symbol!("url")), Some(bytes!(&e, 0x6869)));
+    assert_eq!(
+        client.perms(&key, &Identifier::Account(editor.clone())),
+        Vec::from_array(&e, [Permission::Metadata])
+    );
+
+    client.with_source_account(&owner).ungrant(
+        &key,
+        &Identifier::Account(editor.clone()),
+        &Permission::Metadata,
+    );
+    assert_eq!(
+        client.perms(&key, &Identifier::Account(editor)),
+        Vec::new(&e)
+    );
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_grant_metadata_update() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let editor = e.accounts().generate();
+    let client = setup_grant(&e, &owner, &editor, Permission::Metadata);
+
+    client.with_source_account(&editor).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(editor.clone()),
+    );
+}
+
+#[test]
+fn test_grant_update() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let editor = e.accounts().generate();
+    let client = setup_grant(&e, &owner, &editor, Permission::Update);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(editor.clone());
+    client.with_source_account(&editor).change_val(&key, &value);
+
+    assert_eq!(client.get(&key), value);
+    assert_eq!(client.get_entry(&key).owner, Identifier::Account(owner));
+}
+
+#[test]
+fn test_set_events() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_events(&false);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).remove_val(&key);
+    assert_eq!(
+        parse_events(&e, &contract_id),
+        [RegistryEvent::Verbosity(symbol!("off"))]
+    );
+
+    client.with_source_account(&admin).set_events(&true);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).remove_val(&key);
+    assert_eq!(count_events(&e, TOPIC_SET), 1);
+    assert_eq!(count_events(&e, TOPIC_DELETE), 1);
+}
+
+#[test]
+fn test_keys_page_removed() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let user_id = Identifier::Account(user.clone());
+    for i in 0..25u32 {
+        client
+            .with_source_account(&user)
+            .change_val(&i.serialize(&e), &user_id);
+    }
+    for i in [0u32, 7, 8, 24] {
+        client
+            .with_source_account(&user)
+            .remove_val(&i.serialize(&e));
+    }
+    // Claiming a removed key again reuses its slot.
+    client
+        .with_source_account(&user)
+        .change_val(&7u32.serialize(&e), &user_id);
+
+    assert_eq!(client.total_keys(), 25);
+    assert_eq!(client.key_at(&7), 7u32.serialize(&e));
+
+    let mut paged = Vec::new(&e);
+    let mut start = 0;
+    while start < client.total_keys() {
+        // Removing a key mid-walk doesn't shift the keys after it.
+        if start == 10 {
+            client
+                .with_source_account(&user)
+                .remove_val(&5u32.serialize(&e));
+        }
+        paged.append(&client.keys_page(&start, &4));
+        start += 4;
+    }
+
+    let mut expected = Vec::new(&e);
+    for i in 0..25u32 {
+        if ![0u32, 8, 24].contains(&i) {
+            expected.push_back(i.serialize(&e));
+        }
+    }
+    assert_eq!(paged, expected);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(1))")]
+fn test_key_at_removed() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).remove_val(&key);
+
+    client.key_at(&0);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(24))")]
+fn test_sig_only() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).sig_only(&key, &true);
+
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(e.accounts().generate()));
+}
+
+#[test]
+fn test_sig_only_use_sig() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).sig_only(&key, &true);
+
+    let value = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).use_sig(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(&e),
+        &key,
+        &value,
+    );
+    assert_eq!(client.get(&key), value);
+}
+
+pub struct Holder;
+
+#[contractimpl]
+impl Holder {
+    pub fn claim(e: Env, registry: BytesN<32>, key: Bytes, value: Identifier) {
+        ExampleContractClient::new(&e, registry).change_val(&key, &value);
+    }
+
+    pub fn update(e: Env, registry: BytesN<32>, key: Bytes, value: Identifier) {
+        ExampleContractClient::new(&e, registry).use_sig(
+            &soroban_auth::Signature::Invoker,
+            &BigInt::zero(&e),
+            &key,
+            &value,
+        );
+    }
+
+    pub fn give(e: Env, registry: BytesN<32>, key: Bytes, to: Identifier) {
+        ExampleContractClient::new(&e, registry).transfer(&key, &to);
+    }
+
+    pub fn route(e: Env, registry: BytesN<32>, key: Bytes, value: Identifier, user: Identifier) {
+        ExampleContractClient::new(&e, registry).claim_for(&key, &value, &user);
+    }
+}
+
+fn setup_holder(e: &Env) -> (ExampleContractClient, BytesN<32>, HolderClient) {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    let holder_id = e.register_contract(None, Holder);
+    let holder = HolderClient::new(e, &holder_id);
+    (client, holder_id, holder)
+}
+
+#[test]
+fn test_contract_owner() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let (client, holder_id, holder) = setup_holder(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let registry = client.contract_id.clone();
+    let user_id = Identifier::Account(user.clone());
+    holder.claim(&registry, &key, &user_id);
+    assert_eq!(
+        client.get_entry(&key).owner,
+        Identifier::Contract(holder_id.clone())
+    );
+
+    let value = Identifier::Account(e.accounts().generate());
+    holder.update(&registry, &key, &value);
+    assert_eq!(client.get(&key), value);
+
+    holder.give(&registry, &key, &user_id);
+    assert_eq!(client.get_entry(&key).owner, user_id);
+
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.get(&key), user_id);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_contract_owner_after_transfer() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let (client, _, holder) = setup_holder(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let registry = client.contract_id.clone();
+    let user_id = Identifier::Account(user.clone());
+    holder.claim(&registry, &key, &user_id);
+    holder.give(&registry, &key, &user_id);
+
+    holder.update(
+        &registry,
+        &key,
+        &Identifier::Account(e.accounts().generate()),
+    );
+}
+
+#[test]
+fn test_xfer_set() {
+    let e = Env::default();
+
+    let seller = e.accounts().generate();
+    let buyer = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let buyer_id = Identifier::Account(buyer.clone());
+    let value = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&seller)
+        .change_val(&key, &Identifier::Account(seller.clone()));
+    client
+        .with_source_account(&seller)
+        .xfer_set(&key, &buyer_id, &value);
+
+    assert!(client.is_owner(&key, &buyer_id));
+    assert_eq!(client.get(&key), value);
+    assert_eq!(client.get_fresh(&key).unwrap().1, 2);
+    assert_eq!(
+        client.owner_at(&key, &1),
+        Identifier::Account(seller.clone())
+    );
+    assert_eq!(client.owner_at(&key, &2), buyer_id);
+    check_invariants(&e, &contract_id);
+
+    client
+        .with_source_account(&buyer)
+        .change_val(&key, &buyer_id);
+    assert_eq!(client.get(&key), buyer_id);
+}
+
+#[test]
+fn test_xfer_set_same_value() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let seller = e.accounts().generate();
+    let client = setup_noop(&e, &admin, &seller, true);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let buyer_id = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&seller).xfer_set(
+        &key,
+        &buyer_id,
+        &Identifier::Account(seller.clone()),
+    );
+
+    assert!(client.is_owner(&key, &buyer_id));
+    assert_eq!(client.get(&key), Identifier::Account(seller));
+    assert_eq!(client.get_fresh(&key).unwrap().1, 2);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_xfer_set_old_owner() {
+    let e = Env::default();
+
+    let seller = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let seller_id = Identifier::Account(seller.clone());
+    client
+        .with_source_account(&seller)
+        .change_val(&key, &seller_id);
+    client.with_source_account(&seller).xfer_set(
+        &key,
+        &Identifier::Account(e.accounts().generate()),
+        &Identifier::Account(e.accounts().generate()),
+    );
+
+    client
+        .with_source_account(&seller)
+        .change_val(&key, &seller_id);
+}
+
+#[test]
+fn test_sym() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let value = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&user)
+        .change_sym(&symbol!("hello"), &value);
+    assert_eq!(client.get_sym(&symbol!("hello")), value);
+    assert_eq!(client.get(&bytes!(&e, 0x68656c6c6f)), value);
+
+    let other = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x776f726c64), &other);
+    assert_eq!(client.get_sym(&symbol!("world")), other);
+}
+
+#[test]
+fn test_sym_collision() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+
+    let value = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&user)
+        .change_sym(&symbol!("hello"), &value);
+    assert_eq!(client.get(&key), value);
+    assert_eq!(client.total_keys(), 1);
+}
+
+#[test]
+fn test_del_vals() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let user_id = Identifier::Account(user.clone());
+    let keys = [bytes!(&e, 0x01), bytes!(&e, 0x02), bytes!(&e, 0x03)];
+    for key in keys.iter() {
+        client.with_source_account(&user).change_val(key, &user_id);
+    }
+
+    // 0x04 was never set and is skipped.
+    client.with_source_account(&user).del_vals(&Vec::from_array(
+        &e,
+        [keys[0].clone(), bytes!(&e, 0x04), keys[2].clone()],
+    ));
+
+    assert_eq!(
+        client.keys_page(&0, &10),
+        Vec::from_array(&e, [keys[1].clone()])
+    );
+    assert_eq!(count_events(&e, TOPIC_DELETE), 2);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_del_vals_unauthorized() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x01), &Identifier::Account(user.clone()));
+    client
+        .with_source_account(&other)
+        .change_val(&bytes!(&e, 0x02), &Identifier::Account(other.clone()));
+
+    client
+        .with_source_account(&user)
+        .del_vals(&Vec::from_array(&e, [bytes!(&e, 0x01), bytes!(&e, 0x02)]));
+}
+
+fn setup_sealed(e: &Env, admin: &AccountId) -> (Vec<Record>, BytesN<32>) {
+    let old = ExampleContractClient::new(e, e.register_contract(None, ExampleContract));
+    old.initialize(&Identifier::Account(admin.clone()));
+
+    for i in 0..5u32 {
+        let user = e.accounts().generate();
+        old.with_source_account(&user)
+            .change_val(&i.serialize(e), &Identifier::Account(user.clone()));
+    }
+    let hash = old.with_source_account(admin).seal();
+    assert_eq!(old.state_hash(), Some(hash.clone()));
+
+    let mut entries = Vec::new(e);
+    let mut start = 0;
+    while start < old.total_keys() {
+        entries.append(&old.export(&start, &2));
+        start += 2;
+    }
+    (entries, hash)
+}
+
+#[test]
+fn test_seal_ingest() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let (entries, hash) = setup_sealed(&e, &admin);
+    assert_eq!(entries.len(), 5);
+
+    let new = ExampleContractClient::new(&e, e.register_contract(None, ExampleContract));
+    new.initialize(&Identifier::Account(admin.clone()));
+    assert_eq!(new.state_ver(), STATE_VERSION);
+    new.with_source_account(&admin).ingest(&entries, &hash);
+
+    for record in entries.iter() {
+        let record = record.unwrap();
+        assert_eq!(new.get(&record.key), record.value);
+        assert_eq!(new.get_entry(&record.key).owner, record.owner);
+    }
+    assert_eq!(new.export(&0, &10), entries);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(12))")]
+fn test_ingest_hash_mismatch() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let (mut entries, hash) = setup_sealed(&e, &admin);
+    entries.pop_back();
+
+    let new = ExampleContractClient::new(&e, e.register_contract(None, ExampleContract));
+    new.initialize(&Identifier::Account(admin.clone()));
+    new.with_source_account(&admin).ingest(&entries, &hash);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(25))")]
+fn test_unpause_sealed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).seal();
+
+    client.with_source_account(&admin).unpause();
+}
+
+#[test]
+fn test_owns() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    assert!(!client.owns(&user_id, &key));
+
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert!(client.owns(&user_id, &key));
+    assert!(!client.owns(&Identifier::Account(other), &key));
+}
+
+fn setup_dispute(e: &Env, admin: &AccountId, user: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(admin).dispute(&key);
+    client
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(26))")]
+fn test_dispute_blocks_owner() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_dispute(&e, &admin, &user);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(admin.clone()),
+    );
+}
+
+#[test]
+fn test_dispute_dismiss() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_dispute(&e, &admin, &user);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&admin)
+        .resolve(&key, &symbol!("dismiss"), &None);
+
+    let value = Identifier::Account(admin.clone());
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(client.get(&key), value);
+    assert_eq!(count_events(&e, TOPIC_DISPUTE), 1);
+    assert_eq!(count_events(&e, TOPIC_RESOLVED), 1);
+}
+
+#[test]
+fn test_dispute_uphold() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let claimant = e.accounts().generate();
+    let client = setup_dispute(&e, &admin, &user);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let claimant_id = Identifier::Account(claimant.clone());
+    client.with_source_account(&admin).resolve(
+        &key,
+        &symbol!("uphold"),
+        &Some(claimant_id.clone()),
+    );
+    assert_eq!(client.get_entry(&key).owner, claimant_id);
+
+    client
+        .with_source_account(&claimant)
+        .change_val(&key, &claimant_id);
+    assert_eq!(client.get(&key), claimant_id);
+}
+
+#[test]
+fn test_dispute_lapses() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_dispute(&e, &admin, &user);
+
+    e.ledger().with_mut(|l| l.sequence_number = DISPUTE_WINDOW);
+    let value = Identifier::Account(admin.clone());
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68656c6c6f), &value);
+    assert_eq!(client.get(&bytes!(&e, 0x68656c6c6f)), value);
+}
+
+fn setup_maxlen(e: &Env, admin: &AccountId, max: u32) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(admin).set_maxlen(&max);
+    client
+}
+
+#[test]
+fn test_maxlen() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_maxlen(&e, &admin, 5);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.get(&key), user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(29))")]
+fn test_maxlen_too_long() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_maxlen(&e, &admin, 4);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+fn test_maxlen_unlimited() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_maxlen(&e, &admin, 0);
+
+    let key = Bytes::from_array(&e, &[0x61; 256]);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.get(&key), user_id);
+}
+
+#[test]
+fn test_check_sig() {
+    let e = Env::default();
+    let (client, signer_id, signer) = ed25519_owned(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let challenge = bytes!(&e, 0x6368616c6c656e6765);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &client.contract_id,
+        symbol!("login"),
+        (key.clone(), challenge.clone()),
+    );
+    let nonce = client.nonce(&signer_id);
+
+    assert!(client.check_sig(&key, &sig, &challenge));
+    assert_eq!(client.nonce(&signer_id), nonce);
+    assert!(!client.check_sig(&bytes!(&e, 0x01), &sig, &challenge));
+}
+
+#[test]
+fn test_check_sig_not_owner() {
+    let e = Env::default();
+    let (client, _, _) = ed25519_owned(&e);
+    let (_, stranger) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let challenge = bytes!(&e, 0x6368616c6c656e6765);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &stranger,
+        &client.contract_id,
+        symbol!("login"),
+        (key.clone(), challenge.clone()),
+    );
+
+    assert!(!client.check_sig(&key, &sig, &challenge));
+}
+
+#[test]
+fn test_check_sig_account() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let challenge = bytes!(&e, 0x6368616c6c656e6765);
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &Identifier::Account(owner.clone()));
+
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    let public_key = match signer_id {
+        Identifier::Ed25519(public_key) => public_key,
+        _ => unreachable!(),
+    };
+    let args = (key.clone(), challenge.clone()).into_val(&e);
+    let sig = sign_account(&e, &owner, &[signer], &contract_id, symbol!("login"), args);
+
+    // Keys that aren't signers of the account carry no weight.
+    assert!(!client.check_sig(&key, &sig, &challenge));
+
+    e.accounts().set_signer_weight(&owner, &public_key, 1);
+    e.accounts().set_thresholds(&owner, 1, 1, 1);
+    assert!(client.check_sig(&key, &sig, &challenge));
+}
+
+#[test]
+#[should_panic]
+fn test_check_sig_forged() {
+    let e = Env::default();
+    let (client, _, signer) = ed25519_owned(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &client.contract_id,
+        symbol!("login"),
+        (key.clone(), bytes!(&e, 0x01)),
+    );
+
+    client.check_sig(&key, &sig, &bytes!(&e, 0x02));
+}
+
+#[test]
+fn test_get_pair() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let buyer = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let buyer_id = Identifier::Account(buyer);
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&user).transfer(&key, &buyer_id);
+
+    assert_eq!(client.get_pair(&key), (user_id, buyer_id));
+}
+
+fn setup_default(e: &Env, admin: &AccountId) -> (ExampleContractClient, Identifier) {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let treasury = Identifier::Account(e.accounts().generate());
+    client.with_source_account(admin).set_dflt(&treasury);
+    (client, treasury)
+}
+
+#[test]
+fn test_get_or() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, treasury) = setup_default(&e, &admin);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    assert_eq!(client.get_or(&key), treasury);
+
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.get_or(&key), user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(1))")]
+fn test_get_or_no_default() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.get_or(&bytes!(&e, 0x68656c6c6f));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(1))")]
+fn test_get_or_cleared() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let (client, _) = setup_default(&e, &admin);
+    client.with_source_account(&admin).clear_dflt();
+
+    client.get_or(&bytes!(&e, 0x68656c6c6f));
+}
+
+#[test]
+fn test_admin_del() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&admin).admin_del(&key);
+
+    assert!(!client.is_owner(&key, &Identifier::Account(user)));
+    assert!(client.keys_page(&0, &10).is_empty());
+    assert_eq!(count_events(&e, TOPIC_DELETE), 1);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_admin_del_not_admin() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).admin_del(&key);
+}
+
+#[test]
+fn test_claim_once_retry() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let request_id = BytesN::from_array(&e, &[1; 32]);
+    client
+        .with_source_account(&user)
+        .claim_once(&key, &user_id, &request_id);
+    client
+        .with_source_account(&user)
+        .claim_once(&key, &user_id, &request_id);
+
+    assert_eq!(client.get(&key), user_id);
+    assert_eq!(count_events(&e, TOPIC_SET), 1);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(30))")]
+fn test_claim_once_reused() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let user_id = Identifier::Account(user.clone());
+    let request_id = BytesN::from_array(&e, &[1; 32]);
+    client
+        .with_source_account(&user)
+        .claim_once(&bytes!(&e, 0x01), &user_id, &request_id);
+    client
+        .with_source_account(&user)
+        .claim_once(&bytes!(&e, 0x02), &user_id, &request_id);
+}
+
+#[test]
+fn test_prune_req() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_reqttl(&10);
+
+    let user_id = Identifier::Account(user.clone());
+    let request_id = BytesN::from_array(&e, &[1; 32]);
+    client
+        .with_source_account(&user)
+        .claim_once(&bytes!(&e, 0x01), &user_id, &request_id);
+
+    e.ledger().with_mut(|l| l.sequence_number = 11);
+    client.prune_req(&request_id);
+
+    // The pruned request id can be used again.
+    client
+        .with_source_account(&user)
+        .claim_once(&bytes!(&e, 0x02), &user_id, &request_id);
+    assert_eq!(client.get(&bytes!(&e, 0x02)), user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(31))")]
+fn test_prune_req_pending() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let request_id = BytesN::from_array(&e, &[1; 32]);
+    client.with_source_account(&user).claim_once(
+        &bytes!(&e, 0x01),
+        &Identifier::Account(user.clone()),
+        &request_id,
+    );
+
+    client.prune_req(&request_id);
+}
+
+pub struct Watcher;
+
+#[contractimpl]
+impl Watcher {
+    pub fn watch(e: Env, registry: BytesN<32>, key: Bytes) {
+        ExampleContractClient::new(&e, registry)
+            .subscribe(&key, &Identifier::Contract(e.current_contract()));
+    }
+
+    pub fn on_change(e: Env, key: Bytes, value: Identifier) {
+        e.data().set(key, value);
+    }
+
+    pub fn on_data(e: Env, key: Bytes, value: Identifier, data: Bytes) {
+        e.data().set(key.clone(), value);
+        e.data().set((key, symbol!("data")), data);
+    }
+
+    pub fn seen(e: Env, key: Bytes) -> Option<Identifier> {
+        e.data().get(key).map(Result::unwrap)
+    }
+
+    pub fn seen_data(e: Env, key: Bytes) -> Option<Bytes> {
+        e.data().get((key, symbol!("data"))).map(Result::unwrap)
+    }
+}
+
+#[test]
+fn test_subscribe() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let watcher_id = e.register_contract(None, Watcher);
+    let watcher = WatcherClient::new(&e, &watcher_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &value);
+    client
+        .with_source_account(&user)
+        .subscribe(&key, &Identifier::Contract(watcher_id));
+    assert_eq!(watcher.seen(&key), None);
+
+    let value = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(watcher.seen(&key), Some(value));
+
+    let value = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).use_sig(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(&e),
+        &key,
+        &value,
+    );
+    assert_eq!(watcher.seen(&key), Some(value));
+}
+
+#[test]
+fn test_subscribe_data() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let watcher_id = e.register_contract(None, Watcher);
+    let watcher = WatcherClient::new(&e, &watcher_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(&user)
+        .subscribe(&key, &Identifier::Contract(watcher_id));
+
+    let value = Identifier::Account(e.accounts().generate());
+    let data = bytes!(&e, 0x72656e616d6564);
+    client
+        .with_source_account(&user)
+        .data_val(&key, &value, &data);
+    assert_eq!(watcher.seen(&key), Some(value));
+    assert_eq!(watcher.seen_data(&key), Some(data.clone()));
+
+    // Writes without data don't forward any.
+    let value = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(watcher.seen(&key), Some(value));
+    assert_eq!(watcher.seen_data(&key), Some(data));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(33))")]
+fn test_subscribe_cap() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    for _ in 0..=MAX_SUBSCRIBERS {
+        let watcher = Identifier::Contract(e.register_contract(None, Watcher));
+        client.with_source_account(&user).subscribe(&key, &watcher);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_subscribe_stranger() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    WatcherClient::new(&e, e.register_contract(None, Watcher)).watch(&contract_id, &key);
+}
+
+#[test]
+fn test_subscribe_trapping() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+
+    // The registry has no `on_change`, so notifying it fails.
+    let broken = e.register_contract(None, ExampleContract);
+    client
+        .with_source_account(&user)
+        .subscribe(&key, &Identifier::Contract(broken.clone()));
+
+    let value = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(client.get(&key), value);
+    assert_eq!(
+        parse_events(&e, &contract_id).last(),
+        Some(&RegistryEvent::HookFail(HookFailEvent {
+            key,
+            hook: broken
+        }))
+    );
+}
+
+fn setup_co_owned(
+    e: &Env,
+    major: &AccountId,
+    minor: &AccountId,
+    solo: bool,
+) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(major)
+        .change_val(&key, &Identifier::Account(major.clone()));
+    client.with_source_account(major).set_owners(
+        &key,
+        &Vec::from_array(
+            e,
+            [
+                Share {
+                    owner: Identifier::Account(major.clone()),
+                    weight: 60,
+                },
+                Share {
+                    owner: Identifier::Account(minor.clone()),
+                    weight: 40,
+                },
+            ],
+        ),
+        &solo,
+    );
+    client
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(37))")]
+fn test_set_owners_duplicate() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let owner_id = Identifier::Account(owner.clone());
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &owner_id);
+    client.with_source_account(&owner).set_owners(
+        &key,
+        &Vec::from_array(
+            &e,
+            [
+                Share {
+                    owner: owner_id.clone(),
+                    weight: 60,
+                },
+                Share {
+                    owner: owner_id,
+                    weight: 40,
+                },
+            ],
+        ),
+        &false,
+    );
+}
+
+fn action_hash(e: &Env, action: &Action) -> BytesN<32> {
+    e.compute_hash_sha256(&action.clone().serialize(e))
+}
+
+#[test]
+fn test_co_owned_majority() {
+    let e = Env::default();
+
+    let major = e.accounts().generate();
+    let minor = e.accounts().generate();
+    let buyer = Identifier::Account(e.accounts().generate());
+    let client = setup_co_owned(&e, &major, &minor, false);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let action = Action::Transfer(buyer.clone());
+    client
+        .with_source_account(&major)
+        .approve(&key, &action_hash(&e, &action));
+    client.execute(&key, &action);
+
+    assert_eq!(client.get_entry(&key).owner, buyer);
+    assert!(client.co_owners(&key).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(36))")]
+fn test_co_owned_no_majority() {
+    let e = Env::default();
+
+    let major = e.accounts().generate();
+    let minor = e.accounts().generate();
+    let client = setup_co_owned(&e, &major, &minor, false);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let action = Action::Transfer(Identifier::Account(minor.clone()));
+    client
+        .with_source_account(&minor)
+        .approve(&key, &action_hash(&e, &action));
+    client.execute(&key, &action);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(36))")]
+fn test_co_owned_unapprove() {
+    let e = Env::default();
+
+    let major = e.accounts().generate();
+    let minor = e.accounts().generate();
+    let client = setup_co_owned(&e, &major, &minor, false);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let action = Action::SetValue(Identifier::Account(minor.clone()));
+    let hash = action_hash(&e, &action);
+    client.with_source_account(&major).approve(&key, &hash);
+    client.with_source_account(&minor).approve(&key, &hash);
+    client.with_source_account(&major).unapprove(&key, &hash);
+    client.execute(&key, &action);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(35))")]
+fn test_co_owned_transfer() {
+    let e = Env::default();
+
+    let major = e.accounts().generate();
+    let minor = e.accounts().generate();
+    let client = setup_co_owned(&e, &major, &minor, true);
+
+    client.with_source_account(&major).transfer(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(minor.clone()),
+    );
+}
+
+#[test]
+fn test_co_owned_solo_update() {
+    let e = Env::default();
+
+    let major = e.accounts().generate();
+    let minor = e.accounts().generate();
+    let client = setup_co_owned(&e, &major, &minor, true);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(minor.clone());
+    client.with_source_account(&minor).change_val(&key, &value);
+    assert_eq!(client.get(&key), value);
+    assert_eq!(client.get_entry(&key).owner, Identifier::Account(major));
+}
+
+#[test]
+fn test_set_ver() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let other = Identifier::Account(e.accounts().generate());
+    assert_eq!(client.get_ver(&key), 0);
+
+    client
+        .with_source_account(&user)
+        .set_ver(&key, &Identifier::Account(user.clone()), &3);
+    client.with_source_account(&user).set_ver(&key, &other, &7);
+
+    assert_eq!(client.get(&key), other);
+    assert_eq!(client.get_ver(&key), 7);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(38))")]
+fn test_set_ver_equal() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    client.with_source_account(&user).set_ver(&key, &value, &3);
+    client.with_source_account(&user).set_ver(&key, &value, &3);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(38))")]
+fn test_set_ver_lower() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    client.with_source_account(&user).set_ver(&key, &value, &3);
+    client.with_source_account(&user).set_ver(&key, &value, &2);
+}
+
+#[test]
+fn test_get_fresh() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    assert_eq!(client.get_fresh(&key), None);
+    assert!(!client.is_fresh(&key, &0));
+
+    let value = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &value);
+    let (cached, version) = client.get_fresh(&key).unwrap();
+    assert_eq!(cached, value);
+    assert!(client.is_fresh(&key, &version));
+
+    let other = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &other);
+    assert!(!client.is_fresh(&key, &version));
+    assert_eq!(client.get_fresh(&key), Some((other, version + 1)));
+
+    client.with_source_account(&user).remove_val(&key);
+    assert!(!client.is_fresh(&key, &(version + 1)));
+    assert_eq!(client.get_fresh(&key), None);
+
+    client.with_source_account(&user).change_val(&key, &value);
+    assert!(!client.is_fresh(&key, &version));
+}
+
+fn setup_recovery(
+    e: &Env,
+    admin: &AccountId,
+    user: &AccountId,
+    rescuer: &Identifier,
+) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(admin).recover(&key, rescuer);
+    client
+}
+
+#[test]
+fn test_recovery() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let rescuer = Identifier::Account(e.accounts().generate());
+    let client = setup_recovery(&e, &admin, &user, &rescuer);
+
+    e.ledger().with_mut(|l| l.sequence_number += RECOVERY_DELAY);
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.with_source_account(&admin).finalize(&key);
+
+    assert_eq!(client.get_entry(&key).owner, rescuer);
+    assert_eq!(client.get(&key), Identifier::Account(user));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(39))")]
+fn test_recovery_vetoed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let rescuer = Identifier::Account(e.accounts().generate());
+    let client = setup_recovery(&e, &admin, &user, &rescuer);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.with_source_account(&user).veto(&key);
+
+    e.ledger().with_mut(|l| l.sequence_number += RECOVERY_DELAY);
+    client.with_source_account(&admin).finalize(&key);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(40))")]
+fn test_recovery_too_early() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let rescuer = Identifier::Account(e.accounts().generate());
+    let client = setup_recovery(&e, &admin, &user, &rescuer);
+
+    e.ledger()
+        .with_mut(|l| l.sequence_number += RECOVERY_DELAY - 1);
+    client
+        .with_source_account(&admin)
+        .finalize(&bytes!(&e, 0x68656c6c6f));
+}
+
+#[test]
+fn test_namespaces() {
+    let e = Env::default();
+
+    let alice = e.accounts().generate();
+    let bob = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let (first, second) = (bytes!(&e, 0x6161), bytes!(&e, 0x6262));
+    client.create_ns(&first, &Identifier::Account(alice.clone()));
+    client.create_ns(&second, &Identifier::Account(bob.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&alice)
+        .set_ns(&first, &key, &Identifier::Account(alice.clone()));
+    client
+        .with_source_account(&bob)
+        .set_ns(&second, &key, &Identifier::Account(bob.clone()));
+
+    assert_eq!(
+        client.get_ns(&first, &key),
+        Identifier::Account(alice.clone())
+    );
+    assert_eq!(
+        client.get_ns(&second, &key),
+        Identifier::Account(bob.clone())
+    );
+    assert_eq!(
+        client.get_entry(&client.ns_key(&second, &key)).owner,
+        Identifier::Account(bob)
+    );
+    assert!(!client.is_owner(&key, &Identifier::Account(alice)));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_namespace_owner_policy() {
+    let e = Env::default();
+
+    let alice = e.accounts().generate();
+    let bob = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let ns = bytes!(&e, 0x6161);
+    client.create_ns(&ns, &Identifier::Account(alice.clone()));
+    client
+        .with_source_account(&alice)
+        .ns_policy(&ns, &symbol!("owner"), &BigInt::zero(&e));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&alice)
+        .set_ns(&ns, &key, &Identifier::Account(alice.clone()));
+    client.with_source_account(&bob).set_ns(
+        &ns,
+        &bytes!(&e, 0x6869),
+        &Identifier::Account(bob.clone()),
+    );
+}
+
+#[test]
+fn test_namespace_fee_policy() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let alice = e.accounts().generate();
+    let bidder = e.accounts().generate();
+    let (client, token) = setup_offers(&e, &admin, &bidder, 10);
+
+    let ns = bytes!(&e, 0x6161);
+    client.create_ns(&ns, &Identifier::Account(alice.clone()));
+    client
+        .with_source_account(&alice)
+        .ns_policy(&ns, &symbol!("fee"), &BigInt::from_u32(&e, 4));
+
+    client.with_source_account(&bidder).set_ns(
+        &ns,
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(bidder.clone()),
+    );
+    assert_eq!(
+        token.balance(&Identifier::Account(alice)),
+        BigInt::from_u32(&e, 4)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(21))")]
+fn test_namespace_plain_claim() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let ns = bytes!(&e, 0x6161);
+    client.create_ns(&ns, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).change_val(
+        &client.ns_key(&ns, &bytes!(&e, 0x68656c6c6f)),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+fn test_key_count() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (user_id, other_id) = (
+        Identifier::Account(user.clone()),
+        Identifier::Account(other.clone()),
+    );
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let (first, second) = (bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869));
+    client
+        .with_source_account(&user)
+        .change_val(&first, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&second, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&second, &other_id);
+    assert_eq!(client.key_count(&user_id), 2);
+    assert_eq!(client.key_count(&other_id), 0);
+
+    client
+        .with_source_account(&user)
+        .transfer(&first, &other_id);
+    assert_eq!(client.key_count(&user_id), 1);
+    assert_eq!(client.key_count(&other_id), 1);
+
+    client.with_source_account(&user).remove_val(&second);
+    assert_eq!(client.key_count(&user_id), 0);
+
+    client.with_source_account(&other).remove_val(&first);
+    assert_eq!(client.key_count(&other_id), 0);
+    assert_eq!(client.keys_page(&0, &client.total_keys()).len(), 0);
+    check_invariants(&e, &contract_id);
+}
+
+/// Writes values under their raw key bytes like registries on the previous
+/// layout did.
+pub struct Legacy;
+
+#[contractimpl]
+impl Legacy {
+    pub fn seed(e: Env, key: Bytes, value: Identifier) {
+        e.data().set(key, value);
+    }
+}
+
+/// The registry with `Legacy` mixed in, to seed its storage with records on the
+/// previous layout.
+struct Seeded;
+
+impl ContractFunctionSet for Seeded {
+    fn call(&self, func: &Symbol, env: Env, args: &[RawVal]) -> Option<RawVal> {
+        Legacy
+            .call(func, env.clone(), args)
+            .or_else(|| ExampleContract.call(func, env, args))
+    }
+}
+
+#[test]
+fn test_migrate() {
+    let e = Env::default();
+
+    let user = Identifier::Account(e.accounts().generate());
+
+    let contract_id = e.register_contract(None, Seeded);
+    let key = bytes!(&e, 0x68656c6c6f);
+    LegacyClient::new(&e, &contract_id).seed(&key, &user);
+
+    let client = ExampleContractClient::new(&e, &contract_id);
+    assert_eq!(client.get_stored(&bytes!(&e, 0x6869)), None);
+    assert_eq!(client.get_stored(&key), Some(StoredEntry::V0(user.clone())));
+
+    client.migrate(&key);
+    assert_eq!(
+        client.get_stored(&key),
+        Some(StoredEntry::V1(client.get_entry(&key)))
+    );
+    assert_eq!(client.get_entry(&key).owner, user);
+    assert_eq!(client.get(&key), user);
+    check_invariants(&e, &contract_id);
+}
+
+#[test]
+fn test_parent_write() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, holder_id, holder) = setup_holder(&e);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&admin)
+        .set_parent(&user_id, &Identifier::Contract(holder_id.clone()));
+
+    let value = Identifier::Contract(holder_id);
+    holder.claim(&client.contract_id, &key, &value);
+    assert_eq!(client.get(&key), value);
+    assert_eq!(client.get_entry(&key).owner, user_id);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_parent_unrelated() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, holder_id, _) = setup_holder(&e);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&admin)
+        .set_parent(&user_id, &Identifier::Contract(holder_id));
+
+    let other = HolderClient::new(&e, e.register_contract(None, Holder));
+    other.claim(&client.contract_id, &key, &user_id);
+}
+
+fn setup_split(e: &Env, user: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client
+        .with_source_account(user)
+        .change_val(&bytes!(e, 0x68656c6c6f), &Identifier::Account(user.clone()));
+    client
+}
+
+fn parts(e: &Env, weights: &[u32]) -> Vec<Part> {
+    let mut parts = Vec::new(e);
+    for weight in weights {
+        parts.push_back(Part {
+            to: Identifier::Account(e.accounts().generate()),
+            weight: *weight,
+        });
+    }
+    parts
+}
+
+#[test]
+fn test_split() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_split(&e, &user);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let split = parts(&e, &[2_000, 5_000, 3_000]);
+    client.with_source_account(&user).set_split(&key, &split);
+
+    assert_eq!(client.get_split(&key), split);
+    assert_eq!(client.get(&key), split.get_unchecked(1).unwrap().to);
+
+    let value = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(
+        client.get_split(&key),
+        Vec::from_array(
+            &e,
+            [Part {
+                to: value,
+                weight: SPLIT_DENOMINATOR,
+            }]
+        )
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(43))")]
+fn test_split_zero_weight() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_split(&e, &user);
+
+    client
+        .with_source_account(&user)
+        .set_split(&bytes!(&e, 0x68656c6c6f), &parts(&e, &[10_000, 0]));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(43))")]
+fn test_split_bad_total() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_split(&e, &user);
+
+    client
+        .with_source_account(&user)
+        .set_split(&bytes!(&e, 0x68656c6c6f), &parts(&e, &[7_000, 2_000]));
+}
+
+#[test]
+fn test_snapshot() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let (first, second) = (bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869));
+    let other_id = Identifier::Account(other.clone());
+    client
+        .with_source_account(&user)
+        .change_val(&first, &other_id);
+    client
+        .with_source_account(&other)
+        .change_val(&second, &other_id);
+    let blob = client.with_source_account(&admin).snapshot();
+
+    client.with_source_account(&user).remove_val(&first);
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x6162), &other_id);
+    assert_eq!(client.keys_page(&0, &client.total_keys()).len(), 2);
+
+    client.with_source_account(&admin).load_snap(&blob);
+    assert_eq!(
+        client.keys_page(&0, &client.total_keys()),
+        Vec::from_array(&e, [first.clone(), second.clone()])
+    );
+    assert_eq!(
+        client.get_pair(&first),
+        (other_id.clone(), Identifier::Account(user))
+    );
+    assert_eq!(client.get_pair(&second), (other_id.clone(), other_id));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(12))")]
+fn test_snapshot_malformed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    client
+        .with_source_account(&admin)
+        .load_snap(&symbol!("hello").serialize(&e));
+}
+
+fn setup_guardian(e: &Env, user: &AccountId, guardian: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(user)
+        .set_guard(&key, &Identifier::Account(guardian.clone()));
+    client
+}
+
+#[test]
+fn test_guardian_recovery() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let guardian = e.accounts().generate();
+    let client = setup_guardian(&e, &user, &guardian);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let rescued = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&guardian)
+        .recover(&key, &rescued);
+    assert_eq!(
+        client.rec_state(&key),
+        Some(Recovery {
+            new_owner: rescued.clone(),
+            deadline: u64::from(GUARDIAN_DELAY),
+            guardian: true,
+        })
+    );
+
+    e.ledger().with_mut(|l| l.sequence_number += GUARDIAN_DELAY);
+    client
+        .with_source_account(&e.accounts().generate())
+        .finalize(&key);
+
+    assert_eq!(client.get_entry(&key).owner, rescued);
+    assert_eq!(client.rec_state(&key), None);
+    assert_eq!(count_events(&e, TOPIC_RECOVERED), 1);
+}
+
+#[test]
+fn test_guardian_cancel() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let guardian = e.accounts().generate();
+    let client = setup_guardian(&e, &user, &guardian);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&guardian)
+        .recover(&key, &Identifier::Account(guardian.clone()));
+    client.with_source_account(&user).veto(&key);
+
+    assert_eq!(client.rec_state(&key), None);
+    assert_eq!(count_events(&e, TOPIC_VETOED), 1);
+    assert_eq!(client.get_entry(&key).owner, Identifier::Account(user));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_guardian_other() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let guardian = e.accounts().generate();
+    let client = setup_guardian(&e, &user, &guardian);
+
+    let other = e.accounts().generate();
+    client.with_source_account(&other).recover(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(other.clone()),
+    );
+}
+
+#[test]
+fn test_get_owner() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    assert_eq!(client.get_owner(&key), Identifier::Contract(contract_id));
+
+    let user_id = Identifier::Account(user.clone());
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(e.accounts().generate()));
+    assert_eq!(client.get_owner(&key), user_id);
+}
+
+#[test]
+#[should_panic(expected = "Key does not exist")]
+fn test_get_owner_unset_get() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.get_owner(&key);
+    client.get(&key);
+}
+
+#[test]
+fn test_exists_hot() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let (pinned, other) = (bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869));
+    let user_id = Identifier::Account(user.clone());
+    client
+        .with_source_account(&user)
+        .change_val(&pinned, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&other, &user_id);
+    client.with_source_account(&admin).pin_key(&pinned);
+
+    let keys = Vec::from_array(&e, [pinned.clone(), other, bytes!(&e, 0x6162)]);
+    assert_eq!(
+        client.exists_hot(&keys),
+        Vec::from_array(&e, [true, true, false])
+    );
+
+    client.with_source_account(&user).remove_val(&pinned);
+    assert_eq!(
+        client.exists_hot(&keys),
+        Vec::from_array(&e, [false, true, false])
+    );
+
+    client
+        .with_source_account(&admin)
+        .change_val(&pinned, &user_id);
+    assert_eq!(
+        client.exists_hot(&keys),
+        Vec::from_array(&e, [true, true, false])
+    );
+}
+
+fn setup_renounced(e: &Env, admin: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(admin).renounce();
+    client
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(4))")]
+fn test_renounce() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let client = setup_renounced(&e, &admin);
+
+    assert_eq!(count_events(&e, TOPIC_RENOUNCED), 1);
+    client.with_source_account(&admin).pause();
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(5))")]
+fn test_renounce_initialize() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let client = setup_renounced(&e, &admin);
+
+    client.initialize(&Identifier::Account(admin.clone()));
+}
+
+#[test]
+fn test_router_permissive() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let (client, holder_id, holder) = setup_holder(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    holder.claim(
+        &client.contract_id,
+        &key,
+        &Identifier::Account(user.clone()),
+    );
+    assert_eq!(
+        client.get_entry(&key).owner,
+        Identifier::Contract(holder_id)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(44))")]
+fn test_router_strict() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, _, holder) = setup_holder(&e);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_strict(&true);
+
+    holder.claim(
+        &client.contract_id,
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+fn test_router_claim_for() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, holder_id, holder) = setup_holder(&e);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_strict(&true);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let value = Identifier::Contract(holder_id);
+    holder.route(&client.contract_id, &key, &value, &user_id);
+
+    assert_eq!(client.get_pair(&key), (value, user_id.clone()));
+    client.with_source_account(&user).remove_val(&key);
+}
+
+fn setup_maxval(
+    e: &Env,
+    admin: &AccountId,
+    value: &Identifier,
+    slack: i32,
+) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let size = value.clone().serialize(e).len() as i32 + slack;
+    client.with_source_account(admin).set_maxval(&(size as u32));
+    client
+}
+
+#[test]
+fn test_maxval_under() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let value = Identifier::Account(user.clone());
+    let client = setup_maxval(&e, &admin, &value, 0);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(client.get(&key), value);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(45))")]
+fn test_maxval_over() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let value = Identifier::Account(user.clone());
+    let client = setup_maxval(&e, &admin, &value, -1);
+
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68656c6c6f), &value);
+}
+
+fn setup_blob(e: &Env, user: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client
+        .with_source_account(user)
+        .change_val(&bytes!(e, 0x68656c6c6f), &Identifier::Account(user.clone()));
+    client
+}
+
+#[test]
+fn test_sealed_blob() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let blob = bytes!(&e, 0xdeadbeef);
+    assert_eq!(client.get_sealed(&key), None);
+    client.with_source_account(&user).set_sealed(&key, &blob);
+    assert_eq!(client.get_sealed(&key), Some(blob));
+
+    client
+        .with_source_account(&user)
+        .transfer(&key, &Identifier::Account(e.accounts().generate()));
+    assert_eq!(client.get_sealed(&key), None);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(46))")]
+fn test_sealed_blob_too_large() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+
+    let blob = Bytes::from_array(&e, &[0; MAX_BLOB as usize + 1]);
+    client
+        .with_source_account(&user)
+        .set_sealed(&bytes!(&e, 0x68656c6c6f), &blob);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_sealed_blob_other() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+
+    client
+        .with_source_account(&e.accounts().generate())
+        .set_sealed(&bytes!(&e, 0x68656c6c6f), &bytes!(&e, 0xdeadbeef));
+}
+
+fn setup_batch(e: &Env, user: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    for key in [bytes!(e, 0x68656c6c6f), bytes!(e, 0x6869)] {
+        client
+            .with_source_account(user)
+            .change_val(&key, &Identifier::Account(user.clone()));
+    }
+    client
+}
+
+#[test]
+fn test_batch_xfer() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_batch(&e, &user);
+
+    let keys = Vec::from_array(&e, [bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869)]);
+    let owners = Vec::from_array(
+        &e,
+        [
+            Identifier::Account(e.accounts().generate()),
+            Identifier::Account(e.accounts().generate()),
+        ],
+    );
+    client.with_source_account(&user).batch_xfer(&keys, &owners);
+
+    for (key, owner) in keys.iter().zip(owners.iter()) {
+        assert_eq!(client.get_entry(&key.unwrap()).owner, owner.unwrap());
+    }
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(47))")]
+fn test_batch_xfer_mismatch() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_batch(&e, &user);
+
+    client.with_source_account(&user).batch_xfer(
+        &Vec::from_array(&e, [bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869)]),
+        &Vec::from_array(&e, [Identifier::Account(e.accounts().generate())]),
+    );
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_batch_xfer_not_owned() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let client = setup_batch(&e, &user);
+    client
+        .with_source_account(&other)
+        .change_val(&bytes!(&e, 0x6162), &Identifier::Account(other.clone()));
+
+    let to = Identifier::Account(other);
+    client.with_source_account(&user).batch_xfer(
+        &Vec::from_array(&e, [bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6162)]),
+        &Vec::from_array(&e, [to.clone(), to]),
+    );
+}
+
+#[test]
+fn test_stats() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    assert_eq!(client.get_stats(), Stats::default());
+
+    let (first, second) = (bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869));
+    let (user_id, other_id) = (
+        Identifier::Account(user.clone()),
+        Identifier::Account(other.clone()),
+    );
+    client
+        .with_source_account(&user)
+        .change_val(&first, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&first, &other_id);
+    client
+        .with_source_account(&user)
+        .change_val(&second, &user_id);
+    client
+        .with_source_account(&user)
+        .transfer(&second, &other_id);
+    client.with_source_account(&user).remove_val(&first);
+    client
+        .with_source_account(&other)
+        .change_val(&first, &other_id);
+    client.with_source_account(&other).remove_val(&second);
+
+    assert_eq!(
+        client.get_stats(),
+        Stats {
+            claims: 3,
+            removals: 2,
+            active: 1,
+            writes: 4,
+            offers: 0,
+        }
+    );
+}
+
+#[test]
+fn test_status() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    let mut status = KeyStatus {
+        frozen: false,
+        lock_until: 0,
+        private: false,
+        sig_only: false,
+    };
+    assert_eq!(client.status(&key), status);
+
+    client.with_source_account(&user).set_priv(&key, &true);
+    status.private = true;
+    assert_eq!(client.status(&key), status);
+
+    client.with_source_account(&user).sig_only(&key, &true);
+    status.sig_only = true;
+    assert_eq!(client.status(&key), status);
+
+    client.with_source_account(&user).lock_for(&key, &10);
+    status.lock_until = 10;
+    assert_eq!(client.status(&key), status);
+
+    e.ledger().with_mut(|l| l.sequence_number = 10);
+    status.lock_until = 0;
+    assert_eq!(client.status(&key), status);
+
+    client.with_source_account(&user).freeze(&key);
+    status.frozen = true;
+    assert_eq!(client.status(&key), status);
+}
+
+fn setup_release(e: &Env, admin: &AccountId, user: &AccountId) -> ExampleContractClient {
+    let client = setup_phase(e, admin, symbol!("allowlist"));
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(admin).allow(&user_id);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client.with_source_account(user).change_val(&key, &user_id);
+    client
+        .with_source_account(user)
+        .set_text(&key, &symbol!("url"), &bytes!(e, 0x6869));
+    client
+}
+
+#[test]
+fn test_release_to() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_release(&e, &admin, &user);
+
+    let successor = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&admin).allow(&successor);
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .release_to(&key, &successor);
+
+    assert_eq!(client.get_entry(&key).owner, successor);
+    assert_eq!(client.text(&key, &symbol!("url")), None);
+    assert_eq!(count_events(&e, TOPIC_RELEASED), 1);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_release_to_not_allowed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_release(&e, &admin, &user);
+
+    client.with_source_account(&user).release_to(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(e.accounts().generate()),
+    );
+}
+
+#[test]
+fn test_handover() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &client.contract_id,
+        symbol!("accept"),
+        (client.nonce(&signer_id), key.clone(), signer_id.clone()),
+    );
+    client
+        .with_source_account(&user)
+        .handover(&key, &signer_id, &sig);
+
+    assert_eq!(client.get_entry(&key).owner, signer_id.clone());
+    assert_eq!(client.nonce(&signer_id), BigInt::from_u32(&e, 1));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_handover_other_signer() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let recipient = Identifier::Account(e.accounts().generate());
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &client.contract_id,
+        symbol!("accept"),
+        (client.nonce(&signer_id), key.clone(), recipient.clone()),
+    );
+    client
+        .with_source_account(&user)
+        .handover(&key, &recipient, &sig);
+}
+
+#[test]
+#[should_panic]
+fn test_handover_bad_signature() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &client.contract_id,
+        symbol!("accept"),
+        (
+            client.nonce(&signer_id),
+            bytes!(&e, 0x6869),
+            signer_id.clone(),
+        ),
+    );
+    client
+        .with_source_account(&user)
+        .handover(&key, &signer_id, &sig);
+}
+
+#[test]
+#[should_panic]
+fn test_handover_replayed() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &client.contract_id,
+        symbol!("accept"),
+        (client.nonce(&signer_id), key.clone(), signer_id.clone()),
+    );
+    client
+        .with_source_account(&user)
+        .handover(&key, &signer_id, &sig);
+
+    // Once the key is back with its previous owner, the consent it was
+    // handed over with can't be used again.
+    let user_id = Identifier::Account(user.clone());
+    let rebind_sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &client.contract_id,
+        symbol!("rebind"),
+        (client.nonce(&signer_id), key.clone(), user_id.clone()),
+    );
+    client.rebind(&rebind_sig, &key, &user_id);
+    assert_eq!(client.get_owner(&key), user_id);
+
+    client
+        .with_source_account(&user)
+        .handover(&key, &signer_id, &sig);
+}
+
+fn setup_gated(e: &Env, user: &AccountId, reader: &Identifier) -> ExampleContractClient {
+    let client = setup_blob(e, user);
+    client.with_source_account(user).set_gated(
+        &bytes!(e, 0x68656c6c6f),
+        &symbol!("contact"),
+        &bytes!(e, 0x6869),
+        reader,
+    );
+    client
+}
+
+#[test]
+fn test_gated_owner() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let reader = Identifier::Account(e.accounts().generate());
+    let client = setup_gated(&e, &user, &reader);
+
+    assert_eq!(
+        client.with_source_account(&user).get_gated(
+            &soroban_auth::Signature::Invoker,
+            &bytes!(&e, 0x68656c6c6f),
+            &symbol!("contact")
+        ),
+        bytes!(&e, 0x6869)
+    );
+}
+
+#[test]
+fn test_gated_reader() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let (reader_id, reader) = soroban_auth::testutils::ed25519::generate(&e);
+    let client = setup_gated(&e, &user, &reader_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &reader,
+        &client.contract_id,
+        symbol!("gated"),
+        (client.nonce(&reader_id), key.clone(), symbol!("contact")),
+    );
+    assert_eq!(
+        client.get_gated(&sig, &key, &symbol!("contact")),
+        bytes!(&e, 0x6869)
+    );
+    assert_eq!(client.nonce(&reader_id), BigInt::zero(&e));
+}
+
+#[test]
+#[should_panic]
+fn test_gated_replay() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let (reader_id, reader) = soroban_auth::testutils::ed25519::generate(&e);
+    let client = setup_gated(&e, &user, &reader_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &reader,
+        &client.contract_id,
+        symbol!("gated"),
+        (client.nonce(&reader_id), key.clone(), symbol!("contact")),
+    );
+    client.get_gated(&sig, &key, &symbol!("contact"));
+
+    // Using the nonce for anything else retires the read signature.
+    let rsv = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &reader,
+        &client.contract_id,
+        symbol!("rsv_nonce"),
+        (client.nonce(&reader_id),),
+    );
+    client.rsv_nonce(&rsv);
+    client.get_gated(&sig, &key, &symbol!("contact"));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_gated_stranger() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let reader = Identifier::Account(e.accounts().generate());
+    let client = setup_gated(&e, &user, &reader);
+
+    client
+        .with_source_account(&e.accounts().generate())
+        .get_gated(
+            &soroban_auth::Signature::Invoker,
+            &bytes!(&e, 0x68656c6c6f),
+            &symbol!("contact"),
+        );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(48))")]
+fn test_hold_key_no_value() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.with_source_account(&user).hold_key(&key);
+    assert_eq!(client.get_owner(&key), Identifier::Account(user));
+    client.get(&key);
+}
+
+#[test]
+fn test_hold_key_set() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.with_source_account(&user).hold_key(&key);
+
+    let value = Identifier::Account(other.clone());
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(client.get_pair(&key), (value, Identifier::Account(user)));
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_hold_key_other() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.with_source_account(&user).hold_key(&key);
+    client
+        .with_source_account(&other)
+        .change_val(&key, &Identifier::Account(other.clone()));
+}
+
+fn setup_time_mode(
+    e: &Env,
+    admin: &AccountId,
+    user: &AccountId,
+    mode: Symbol,
+) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(admin).set_tmode(&mode);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(user).lock_for(&key, &1_000);
+    client
+}
+
+#[test]
+fn test_time_mode_ledger() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_time_mode(&e, &admin, &user, symbol!("ledger"));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    assert_eq!(client.time_mode(), symbol!("ledger"));
+    e.ledger().with_mut(|l| l.timestamp = 1_000);
+    assert_eq!(client.status(&key).lock_until, 1_000);
+
+    e.ledger().with_mut(|l| l.sequence_number = 1_000);
+    assert_eq!(client.status(&key).lock_until, 0);
+}
+
+#[test]
+fn test_time_mode_time() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_time_mode(&e, &admin, &user, symbol!("time"));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    assert_eq!(client.time_mode(), symbol!("time"));
+    e.ledger().with_mut(|l| l.sequence_number = 1_000);
+    assert_eq!(client.status(&key).lock_until, 1_000);
+
+    e.ledger().with_mut(|l| l.timestamp = 1_000);
+    assert_eq!(client.status(&key).lock_until, 0);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(admin.clone()));
+}
+
+#[test]
+fn test_time_mode_handoff() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client
+        .with_source_account(&admin)
+        .set_tmode(&symbol!("time"));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(&user)
+        .prop_xfer(&key, &Identifier::Account(other.clone()), &10);
+
+    e.ledger().with_mut(|l| {
+        l.sequence_number = 100;
+        l.timestamp = 10;
+    });
+    client.with_source_account(&other).acpt_xfer(&key);
+    assert_eq!(client.get_owner(&key), Identifier::Account(other));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(49))")]
+fn test_time_mode_invalid() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    setup_time_mode(&e, &admin, &user, symbol!("hours"));
+}
+
+fn setup_noop(e: &Env, admin: &AccountId, user: &AccountId, guard: bool) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(admin).noop_guard(&guard);
+
+    client
+        .with_source_account(user)
+        .change_val(&bytes!(e, 0x68656c6c6f), &Identifier::Account(user.clone()));
+    client
+}
+
+#[test]
+fn test_noop_guard_off() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_noop(&e, &admin, &user, false);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.get_entry(&key).version, 1);
+    client.with_source_account(&user).transfer(&key, &user_id);
+    assert_eq!(client.get_entry(&key).owner, user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(50))")]
+fn test_noop_guard_write() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_noop(&e, &admin, &user, true);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(50))")]
+fn test_noop_guard_transfer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_noop(&e, &admin, &user, true);
+
+    client.with_source_account(&user).transfer(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+fn test_attest() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+
+    e.ledger().with_mut(|l| l.sequence_number = 7);
+    let key = bytes!(&e, 0x68656c6c6f);
+    let hash = client.with_source_account(&user).attest(&key);
+
+    assert_eq!(
+        client.check_att(&hash),
+        Some(Attestation {
+            key: key.clone(),
+            owner: Identifier::Account(user.clone()),
+            ledger: 7,
+        })
+    );
+    assert_eq!(count_events(&e, TOPIC_ATTEST), 1);
+    assert_eq!(client.check_att(&BytesN::from_array(&e, &[0; 32])), None);
+}
+
+#[test]
+fn test_attest_transferred() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let hash = client.with_source_account(&user).attest(&key);
+    client
+        .with_source_account(&user)
+        .transfer(&key, &Identifier::Account(e.accounts().generate()));
+
+    assert_eq!(client.check_att(&hash), None);
+}
+
+#[test]
+fn test_writers() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let editor = Identifier::Account(e.accounts().generate());
+    let curator = Identifier::Account(e.accounts().generate());
+    assert_eq!(client.writers(&key), Vec::from_array(&e, [user_id.clone()]));
+
+    client.with_source_account(&user).grant(
+        &key,
+        &editor,
+        &Vec::from_array(&e, [Permission::Update]),
+    );
+    client.with_source_account(&user).grant(
+        &key,
+        &curator,
+        &Vec::from_array(&e, [Permission::Metadata]),
+    );
+    assert_eq!(
+        client.writers(&key),
+        Vec::from_array(&e, [user_id.clone(), editor.clone()])
+    );
+
+    client
+        .with_source_account(&user)
+        .ungrant(&key, &editor, &Permission::Update);
+    assert_eq!(client.writers(&key), Vec::from_array(&e, [user_id]));
+    assert!(client.writers(&bytes!(&e, 0x6869)).is_empty());
+}
+
+#[test]
+fn test_set_admin() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let next = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let next_id = Identifier::Account(next.clone());
+    client.with_source_account(&admin).set_admin(&next_id);
+    assert_eq!(client.get_admin(), Identifier::Account(admin));
+
+    client.with_source_account(&next).accept_adm();
+    assert_eq!(client.get_admin(), next_id);
+    assert_eq!(count_events(&e, TOPIC_PROPOSED), 1);
+    assert_eq!(count_events(&e, TOPIC_ACCEPTED), 1);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_set_admin_not_admin() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let other = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    client
+        .with_source_account(&other)
+        .set_admin(&Identifier::Account(other.clone()));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_accept_adm_not_proposed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let next = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    client
+        .with_source_account(&admin)
+        .set_admin(&Identifier::Account(next));
+    client
+        .with_source_account(&e.accounts().generate())
+        .accept_adm();
+}
+
+#[test]
+fn test_get_config() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    assert_eq!(
+        client.get_config(),
+        Config {
+            init: false,
+            paused: false,
+            admin: MaybeId::None,
+            events: true,
+            verbosity: symbol!("full"),
+            keys: 0,
+            phase: symbol!("open"),
+            max_key: 0,
+            req_ttl: REQUEST_TTL,
+            rsv_ttl: RESERVE_TTL,
+            tomb_ttl: TOMBSTONE_TTL,
+            swap_ttl: SWAP_TTL,
+            token: MaybeToken::None,
+            profile: symbol!("none"),
+            stage: symbol!("live"),
+            fallback: MaybeId::None,
+            ns_gated: false,
+            strict: false,
+            max_val: 0,
+            max_blob: MAX_BLOB,
+            time_mode: symbol!("ledger"),
+            noop_guard: false,
+            append: false,
+            quorum: MaybeQuorum::None,
+            dflt_owner: MaybeId::None,
+            max_dlg: 0,
+            max_owned: 0,
+            claim_sig: MaybeId::None,
+            min_bal: MaybeMin::None,
+            recv_guard: false,
+        }
+    );
+
+    let admin_id = Identifier::Account(admin.clone());
+    client.initialize(&admin_id);
+    let user_id = Identifier::Account(user.clone());
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68656c6c6f), &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x6869), &user_id);
+    client.with_source_account(&admin).pause();
+
+    assert_eq!(
+        client.get_config(),
+        Config {
+            init: true,
+            paused: true,
+            admin: MaybeId::Some(admin_id.clone()),
+            events: true,
+            verbosity: symbol!("full"),
+            keys: 2,
+            phase: symbol!("open"),
+            max_key: 0,
+            req_ttl: REQUEST_TTL,
+            rsv_ttl: RESERVE_TTL,
+            tomb_ttl: TOMBSTONE_TTL,
+            swap_ttl: SWAP_TTL,
+            token: MaybeToken::None,
+            profile: symbol!("none"),
+            stage: symbol!("live"),
+            fallback: MaybeId::None,
+            ns_gated: false,
+            strict: false,
+            max_val: 0,
+            max_blob: MAX_BLOB,
+            time_mode: symbol!("ledger"),
+            noop_guard: false,
+            append: false,
+            quorum: MaybeQuorum::None,
+            dflt_owner: MaybeId::None,
+            max_dlg: 0,
+            max_owned: 0,
+            claim_sig: MaybeId::None,
+            min_bal: MaybeMin::None,
+            recv_guard: false,
+        }
+    );
+}
+
+pub struct Recorder;
+
+#[contractimpl]
+impl Recorder {
+    pub fn on_update(e: Env, key: Bytes, old: Identifier, new: Identifier) {
+        let mut calls = Self::calls(e.clone(), key.clone());
+        calls.push_back((old, new));
+        e.data().set(key, calls);
+    }
+
+    pub fn calls(e: Env, key: Bytes) -> Vec<(Identifier, Identifier)> {
+        e.data()
+            .get(key)
+            .unwrap_or_else(|| Ok(Vec::new(&e)))
+            .unwrap()
+    }
+}
+
+#[test]
+fn test_set_hook() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let recorder_id = e.register_contract(None, Recorder);
+    let recorder = RecorderClient::new(&e, &recorder_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&user)
+        .set_hook(&key, &recorder_id);
+
+    let other_id = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&user)
+        .change_val(&key, &other_id);
+    assert_eq!(
+        recorder.calls(&key),
+        Vec::from_array(&e, [(user_id.clone(), other_id.clone())])
+    );
+
+    client.with_source_account(&user).clear_hook(&key);
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(recorder.calls(&key).len(), 1);
+}
+
+#[test]
+fn test_set_hook_failing() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    // Watcher has no `on_update`, so calling it as a hook fails.
+    let hook_id = e.register_contract(None, Watcher);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&user).set_hook(&key, &hook_id);
+
+    let other_id = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&user)
+        .change_val(&key, &other_id);
+    assert_eq!(client.get(&key), other_id);
+    assert_eq!(count_events(&e, TOPIC_HOOK_FAIL), 1);
+}
+
+fn setup_write_once(e: &Env, user: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client.with_source_account(user).hold_key(&key);
+    client.with_source_account(user).write_once(&key, &true);
+    client
+        .with_source_account(user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+}
+
+#[test]
+fn test_write_once() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_write_once(&e, &user);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    assert_eq!(client.get(&key), user_id);
+
+    let other_id = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).transfer(&key, &other_id);
+    assert_eq!(client.get_owner(&key), other_id);
+    assert_eq!(client.get(&key), user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(20))")]
+fn test_write_once_second_write() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_write_once(&e, &user);
+
+    client.with_source_account(&user).use_sig(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(&e),
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(e.accounts().generate()),
+    );
+}
+
+#[test]
+fn test_get_config_settings() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let admin_id = Identifier::Account(admin.clone());
+    client.initialize(&admin_id);
+
+    let token = BytesN::from_array(&e, &[7; 32]);
+    client
+        .with_source_account(&admin)
+        .set_phase(&symbol!("closed"));
+    client.with_source_account(&admin).set_maxlen(&16);
+    client.with_source_account(&admin).set_reqttl(&50);
+    client.with_source_account(&admin).set_token(&token);
+    client.with_source_account(&admin).set_events(&false);
+    client.with_source_account(&admin).set_strict(&true);
+    client.with_source_account(&admin).set_maxval(&64);
+    client.with_source_account(&admin).noop_guard(&true);
+    client.with_source_account(&admin).pause();
+
+    assert_eq!(
+        client.get_config(),
+        Config {
+            init: true,
+            paused: true,
+            admin: MaybeId::Some(admin_id.clone()),
+            events: false,
+            verbosity: symbol!("off"),
+            keys: 0,
+            phase: symbol!("closed"),
+            max_key: 16,
+            req_ttl: 50,
+            rsv_ttl: RESERVE_TTL,
+            tomb_ttl: TOMBSTONE_TTL,
+            swap_ttl: SWAP_TTL,
+            token: MaybeToken::Some(token),
+            profile: symbol!("none"),
+            stage: symbol!("live"),
+            fallback: MaybeId::None,
+            ns_gated: false,
+            strict: true,
+            max_val: 64,
+            max_blob: MAX_BLOB,
+            time_mode: symbol!("ledger"),
+            noop_guard: true,
+            append: false,
+            quorum: MaybeQuorum::None,
+            dflt_owner: MaybeId::None,
+            max_dlg: 0,
+            max_owned: 0,
+            claim_sig: MaybeId::None,
+            min_bal: MaybeMin::None,
+            recv_guard: false,
+        }
+    );
+}
+
+#[test]
+fn test_get_opt() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let consumer = ConsumerClient::new(&e, e.register_contract(None, Consumer));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+
+    assert_eq!(consumer.lookup(&contract_id, &key), Some(user_id));
+    assert_eq!(consumer.lookup(&contract_id, &bytes!(&e, 0x6869)), None);
+}
+
+#[test]
+fn test_rebind() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::zero(&e), key.clone(), signer_id.clone()),
+    );
+    client.use_sig(&sig, &BigInt::zero(&e), &key, &signer_id);
+    let version = client.get_fresh(&key).unwrap().1;
+
+    let account = e.accounts().generate();
+    let account_id = Identifier::Account(account.clone());
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("rebind"),
+        (client.nonce(&signer_id), key.clone(), account_id.clone()),
+    );
+    client.rebind(&sig, &key, &account_id);
+
+    assert_eq!(client.get_owner(&key), account_id);
+    assert_eq!(client.get(&key), signer_id);
+    assert_eq!(client.get_fresh(&key).unwrap().1, version);
+    assert_eq!(client.owner_at(&key, &version), account_id);
+    assert_eq!(client.key_count(&signer_id), 0);
+    assert_eq!(client.key_count(&account_id), 1);
+
+    assert_eq!(count_events(&e, TOPIC_REBOUND), 1);
+    assert_eq!(
+        parse_events(&e, &contract_id).last(),
+        Some(&RegistryEvent::Rebound(ReboundEvent {
+            key: key.clone(),
+            old: signer_id,
+            new: account_id.clone(),
+        }))
+    );
+
+    client
+        .with_source_account(&account)
+        .transfer(&key, &Identifier::Account(e.accounts().generate()));
+    assert_eq!(client.owner_at(&key, &version), account_id);
+    check_invariants(&e, &contract_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_rebind_stranger() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+    let (stranger_id, stranger) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &stranger,
+        &client.contract_id,
+        symbol!("rebind"),
+        (client.nonce(&stranger_id), key.clone(), stranger_id.clone()),
+    );
+    client.rebind(&sig, &key, &stranger_id);
+}
+
+#[test]
+fn test_value_type() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+
+    let (first, second) = (bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869));
+    client
+        .with_source_account(&user)
+        .change_val(&second, &Identifier::Account(user.clone()));
+
+    client.with_source_account(&user).set_typed(
+        &first,
+        &Bytes::from_array(&e, &1_i128.to_be_bytes()),
+        &symbol!("i128"),
+    );
+    client.with_source_account(&user).set_typed(
+        &second,
+        &symbol!("hello").serialize(&e),
+        &symbol!("symbol"),
+    );
+    assert_eq!(client.value_type(&first), symbol!("i128"));
+    assert_eq!(client.value_type(&second), symbol!("symbol"));
+
+    client
+        .with_source_account(&user)
+        .set_sealed(&first, &bytes!(&e, 0x01));
+    assert_eq!(client.value_type(&first), symbol!("bytes"));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(48))")]
+fn test_value_type_no_blob() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+    client.value_type(&bytes!(&e, 0x68656c6c6f));
+}
+
+fn setup_append(e: &Env, admin: &AccountId, user: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(admin).set_append();
+
+    client
+        .with_source_account(user)
+        .change_val(&bytes!(e, 0x68656c6c6f), &Identifier::Account(user.clone()));
+    client
+}
+
+#[test]
+fn test_set_append() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_append(&e, &admin, &user);
+
+    let user_id = Identifier::Account(user.clone());
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x6869), &user_id);
+    assert_eq!(client.get(&bytes!(&e, 0x68656c6c6f)), user_id);
+    assert_eq!(client.get(&bytes!(&e, 0x6869)), user_id);
+    assert_eq!(client.total_keys(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(20))")]
+fn test_set_append_update() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_append(&e, &admin, &user);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(e.accounts().generate()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(20))")]
+fn test_set_append_admin_del() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_append(&e, &admin, &user);
+
+    client
+        .with_source_account(&admin)
+        .admin_del(&bytes!(&e, 0x68656c6c6f));
+}
+
+#[test]
+fn test_xfer_with() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let (first, second) = (bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869));
+    let delegate = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&user)
+        .change_val(&second, &Identifier::Account(user.clone()));
+    for key in [&first, &second] {
+        client.with_source_account(&user).grant(
+            key,
+            &delegate,
+            &Vec::from_array(&e, [Permission::Update]),
+        );
+    }
+
+    for (key, keep) in [(&first, false), (&second, true)] {
+        let sig = soroban_auth::testutils::ed25519::sign(
+            &e,
+            &signer,
+            &client.contract_id,
+            symbol!("xfer_with"),
+            (
+                client.nonce(&signer_id),
+                key.clone(),
+                signer_id.clone(),
+                keep,
+            ),
+        );
+        client
+            .with_source_account(&user)
+            .xfer_with(key, &signer_id, &keep, &sig);
+        assert_eq!(client.get_owner(key), signer_id);
+    }
+
+    assert_eq!(client.perms(&first, &delegate), Vec::new(&e));
+    assert_eq!(
+        client.perms(&second, &delegate),
+        Vec::from_array(&e, [Permission::Update])
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_xfer_with_keep_not_signed() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &client.contract_id,
+        symbol!("xfer_with"),
+        (
+            client.nonce(&signer_id),
+            key.clone(),
+            signer_id.clone(),
+            false,
+        ),
+    );
+    client
+        .with_source_account(&user)
+        .xfer_with(&key, &signer_id, &true, &sig);
+}
+
+fn owned_keys(client: &ExampleContractClient, owner: &Identifier) -> Vec<Bytes> {
+    let mut keys = Vec::new(&client.env);
+    for page in 0..client.idx_pages(owner) {
+        keys.append(&client.owned_page(owner, &page));
+    }
+    keys
+}
+
+#[test]
+fn test_index_pages() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let user_id = Identifier::Account(user.clone());
+    let count = 2 * INDEX_PAGE + 5;
+    let mut keys = Vec::new(&e);
+    for i in 0..count {
+        let key = Bytes::from_array(&e, &i.to_be_bytes());
+        client.with_source_account(&user).change_val(&key, &user_id);
+        keys.push_back(key);
+    }
+    assert_eq!(client.idx_pages(&user_id), 3);
+    assert_eq!(client.total_keys(), count);
+    assert_eq!(
+        client.keys_page(&(INDEX_PAGE - 2), &4),
+        keys.slice(INDEX_PAGE - 2..INDEX_PAGE + 2)
+    );
+
+    let owned = owned_keys(&client, &user_id);
+    assert_eq!(owned.len(), count);
+    for key in keys.iter() {
+        assert!(owned.contains(key.unwrap()));
+    }
+
+    let removed = [0, INDEX_PAGE, 2 * INDEX_PAGE + 1, count - 1, 1, 2];
+    for i in removed {
+        client
+            .with_source_account(&user)
+            .remove_val(&keys.get_unchecked(i).unwrap());
+    }
+    assert_eq!(client.idx_pages(&user_id), 2);
+    assert_eq!(client.key_count(&user_id), count - 6);
+
+    let owned = owned_keys(&client, &user_id);
+    assert_eq!(owned.len(), count - 6);
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(owned.contains(key.unwrap()), !removed.contains(&(i as u32)));
+    }
+    assert_eq!(client.keys_page(&0, &INDEX_PAGE).len(), INDEX_PAGE - 3);
+}
+
+fn setup_prof(e: &Env, admin: &AccountId, profile: Symbol) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(admin).set_prof(&profile);
+    client
+}
+
+#[test]
+fn test_set_prof() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let user_id = Identifier::Account(user.clone());
+
+    let client = setup_prof(&e, &admin, symbol!("none"));
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x00ff), &user_id);
+
+    let client = setup_prof(&e, &admin, symbol!("printable"));
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x48692021), &user_id);
+
+    let client = setup_prof(&e, &admin, symbol!("dns_label"));
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x612d31), &user_id);
+    assert_eq!(client.get(&bytes!(&e, 0x612d31)), user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(29))")]
+fn test_set_prof_printable() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_prof(&e, &admin, symbol!("printable"));
+
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68690a), &Identifier::Account(user.clone()));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(29))")]
+fn test_set_prof_dns_upper() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_prof(&e, &admin, symbol!("dns_label"));
+
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x4869), &Identifier::Account(user.clone()));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(29))")]
+fn test_set_prof_dns_hyphen() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_prof(&e, &admin, symbol!("dns_label"));
+
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68692d), &Identifier::Account(user.clone()));
+}
+
+#[test]
+fn test_set_prof_existing() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_prof(&e, &admin, symbol!("none"));
+
+    let key = bytes!(&e, 0x4869);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(&admin)
+        .set_prof(&symbol!("dns_label"));
+
+    let other_id = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&user)
+        .change_val(&key, &other_id);
+    assert_eq!(client.get(&key), other_id);
+}
+
+#[test]
+#[should_panic(expected = "this key is frozen")]
+fn test_set_final() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).set_final(&key, &value);
+    assert_eq!(client.get(&key), value);
+    assert!(client.status(&key).frozen);
+
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+}
+
+#[test]
+#[should_panic(expected = "this key is frozen")]
+fn test_frozen_same_value() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &value);
+    client.with_source_account(&user).freeze(&key);
+
+    let mut split = Vec::new(&e);
+    split.push_back(Part {
+        to: value,
+        weight: 10_000,
+    });
+    client.with_source_account(&user).set_split(&key, &split);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_set_final_not_owner() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let client = setup_blob(&e, &user);
+
+    client.with_source_account(&other).set_final(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(other.clone()),
+    );
+}
+
+#[test]
+fn test_can_change() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let owner = e.accounts().generate();
+    let editor = e.accounts().generate();
+    let owner_id = Identifier::Account(owner.clone());
+    let editor_id = Identifier::Account(editor.clone());
+    let stranger_id = Identifier::Account(e.accounts().generate());
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    assert!(client.can_change(&key, &owner_id));
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &owner_id);
+
+    // Only the owner and identities granted `Update` get past the checks, and
+    // both dry runs agree with the writes that follow.
+    assert!(client.can_change(&key, &owner_id));
+    assert!(!client.can_change(&key, &editor_id));
+    assert!(!client.can_change(&key, &stranger_id));
+    client.with_source_account(&owner).grant(
+        &key,
+        &editor_id,
+        &Vec::from_array(&e, [Permission::Update]),
+    );
+    assert!(client.can_change(&key, &editor_id));
+    client
+        .with_source_account(&editor)
+        .change_val(&key, &editor_id);
+    assert_eq!(client.get(&key), editor_id);
+
+    client.with_source_account(&owner).sig_only(&key, &true);
+    assert!(!client.can_change(&key, &owner_id));
+    client.with_source_account(&owner).sig_only(&key, &false);
+
+    client.with_source_account(&admin).pause();
+    assert!(!client.can_change(&key, &owner_id));
+    client.with_source_account(&admin).unpause();
+    assert!(client.can_change(&key, &owner_id));
+}
+
+#[test]
+#[should_panic(expected = "this key is frozen")]
+fn test_can_change_frozen() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &value);
+    client.with_source_account(&user).freeze(&key);
+
+    assert!(!client.can_change(&key, &value));
+    client.with_source_account(&user).change_val(&key, &value);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(20))")]
+fn test_can_change_locked() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &value);
+    client.with_source_account(&user).lock_for(&key, &100);
+
+    assert!(!client.can_change(&key, &value));
+    client.with_source_account(&user).change_val(&key, &value);
+}
+
+#[test]
+fn test_can_claim() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let user_id = Identifier::Account(user.clone());
+    let client = setup_phase(&e, &admin, symbol!("open"));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    assert!(client.can_claim(&key, &user_id));
+    client.with_source_account(&user).change_val(&key, &user_id);
+
+    // Claimed keys can't be claimed again, even by their owner, who can still
+    // change them.
+    assert!(!client.can_claim(&key, &user_id));
+    assert!(client.can_change(&key, &user_id));
+
+    let other = bytes!(&e, 0x776f726c64);
+    client
+        .with_source_account(&admin)
+        .set_phase(&symbol!("closed"));
+    assert!(!client.can_claim(&other, &user_id));
+    assert!(client.can_change(&key, &user_id));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(17))")]
+fn test_can_claim_closed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_phase(&e, &admin, symbol!("closed"));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    assert!(!client.can_claim(&key, &value));
+    client.with_source_account(&user).change_val(&key, &value);
+}
+
+#[test]
+fn test_writes() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    assert_eq!(client.writes(), 0);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let other_id = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &other_id);
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.writes(), 3);
+
+    // Writing the stored value again changes nothing, and removing the key
+    // keeps its writes counted.
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&user).remove_val(&key);
+    assert_eq!(client.writes(), 3);
+
+    client.with_source_account(&user).use_sig(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(&e),
+        &key,
+        &user_id,
+    );
+    assert_eq!(client.writes(), 4);
+    assert_eq!(client.get_stats().writes, 4);
+}
+
+#[test]
+fn test_claimer_of() {
+    let e = Env::default();
+    e.ledger().with_mut(|l| l.sequence_number = 5);
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let (user_id, other_id) = (
+        Identifier::Account(user.clone()),
+        Identifier::Account(other.clone()),
+    );
+    client.with_source_account(&user).change_val(&key, &user_id);
+
+    // Updates and transfers keep the original claim.
+    e.ledger().with_mut(|l| l.sequence_number = 8);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &other_id);
+    client.with_source_account(&user).transfer(&key, &other_id);
+    assert_eq!(client.get_owner(&key), other_id);
+    assert_eq!(client.claimer_of(&key), (user_id.clone(), 5));
+
+    // A key claimed again after its removal is a new registration.
+    client.with_source_account(&other).remove_val(&key);
+    e.ledger().with_mut(|l| l.sequence_number = 13);
+    client
+        .with_source_account(&other)
+        .change_val(&key, &other_id);
+    assert_eq!(client.claimer_of(&key), (other_id, 13));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(1))")]
+fn test_claimer_of_unclaimed() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.claimer_of(&bytes!(&e, 0x68656c6c6f));
+}
+
+#[test]
+fn test_link_owner() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let (user_id, other_id) = (
+        Identifier::Account(user.clone()),
+        Identifier::Account(other.clone()),
+    );
+    let value = Identifier::Contract(BytesN::from_array(&e, &[1; 32]));
+    client.with_source_account(&user).change_val(&key, &value);
+
+    // Linking sets the value to the owner, and transfers carry it along.
+    client.with_source_account(&user).link_owner(&key, &true);
+    assert_eq!(client.get(&key), user_id);
+    client.with_source_account(&user).transfer(&key, &other_id);
+    assert_eq!(client.get(&key), other_id);
+
+    // Once unlinked, transfers leave the value alone again.
+    client.with_source_account(&other).link_owner(&key, &false);
+    client.with_source_account(&other).change_val(&key, &value);
+    client.with_source_account(&other).transfer(&key, &user_id);
+    assert_eq!(client.get_owner(&key), user_id);
+    assert_eq!(client.get(&key), value);
+}
+
+#[test]
+fn test_sim_sig() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user);
+    let nonce = client.nonce(&signer_id);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (nonce.clone(), key.clone(), value.clone()),
+    );
+
+    // Simulating leaves the key unset and the nonce unused, so the signature
+    // still goes through afterwards.
+    assert!(client.sim_sig(&sig, &key, &value, &nonce));
+    assert!(!client.sim_sig(&sig, &key, &value, &BigInt::from_u32(&e, 1)));
+    assert_eq!(client.get_opt(&key), None);
+    assert_eq!(client.nonce(&signer_id), nonce);
+
+    client.use_sig(&sig, &nonce, &key, &value);
+    assert_eq!(client.get(&key), value);
+    assert!(!client.sim_sig(&sig, &key, &value, &nonce));
+}
+
+#[test]
+fn test_sim_sig_account() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (_, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    let nonce = client.nonce(&value);
+    let args = (nonce.clone(), key.clone(), value.clone()).into_val(&e);
+    let sig = sign_account(&e, &user, &[signer], &contract_id, symbol!("change"), args);
+
+    assert!(!client.sim_sig(&sig, &key, &value, &nonce));
+}
+
+#[test]
+#[should_panic]
+fn test_sim_sig_forged() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let nonce = client.nonce(&signer_id);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (nonce.clone(), key.clone(), signer_id.clone()),
+    );
+
+    client.sim_sig(
+        &sig,
+        &key,
+        &Identifier::Account(e.accounts().generate()),
+        &nonce,
+    );
+}
+
+#[test]
+fn test_find_keys() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    // Keys under `app/` alternate with others across two index pages.
+    let value = Identifier::Account(user.clone());
+    let mut expected = Vec::new(&e);
+    for i in 0..12 {
+        let mut key = if i % 2 == 0 {
+            Bytes::from_slice(&e, b"app/")
+        } else {
+            Bytes::from_slice(&e, b"web/")
+        };
+        key.push(b'a' + i);
+        if i % 2 == 0 {
+            expected.push_back(key.clone());
+        }
+        client.with_source_account(&user).change_val(&key, &value);
+    }
+    client
+        .with_source_account(&user)
+        .remove_val(&Bytes::from_slice(&e, b"app/a"));
+    expected.pop_front();
+
+    let prefix = Bytes::from_slice(&e, b"app/");
+    assert_eq!(client.find_keys(&prefix, &0, &100), expected);
+
+    let mut walked = client.find_keys(&prefix, &0, &5);
+    walked.append(&client.find_keys(&prefix, &5, &5));
+    walked.append(&client.find_keys(&prefix, &10, &5));
+    assert_eq!(walked, expected);
+
+    assert!(client
+        .find_keys(&Bytes::from_slice(&e, b"none/"), &0, &100)
+        .is_empty());
+}
+
+/// Registers the contract with an escrowed offer by `bidder` on a key of
+/// `user`, and starts winding the registry down.
+fn setup_shutdown(
+    e: &Env,
+    admin: &AccountId,
+    bidder: &AccountId,
+    user: &AccountId,
+) -> (ExampleContractClient, token::Client) {
+    let (client, token) = setup_offers(e, admin, bidder, 100);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(bidder)
+        .make_offer(&key, &BigInt::from_u32(e, 60));
+
+    client.with_source_account(admin).wind_down();
+    (client, token)
+}
+
+#[test]
+fn test_shutdown() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let bidder = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, token) = setup_shutdown(&e, &admin, &bidder, &user);
+    assert_eq!(client.get_config().stage, symbol!("winding"));
+
+    // Refunding the same offer twice pays it out once.
+    let key = bytes!(&e, 0x68656c6c6f);
+    let bidder_id = Identifier::Account(bidder.clone());
+    let offer = OfferKey {
+        key: key.clone(),
+        bidder: bidder_id.clone(),
+    };
+    client.refund(&Vec::from_array(&e, [offer.clone(), offer]));
+    assert_eq!(token.balance(&bidder_id), BigInt::from_u32(&e, 100));
+    assert_eq!(client.get_stats().offers, 0);
+
+    client.with_source_account(&admin).terminate();
+    assert_eq!(client.get_config().stage, symbol!("ended"));
+    assert_eq!(
+        parse_events(&e, &client.contract_id).last(),
+        Some(&RegistryEvent::Ended)
+    );
+    assert_eq!(client.get(&key), Identifier::Account(user));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(7))")]
+fn test_shutdown_claim() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let bidder = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, _) = setup_shutdown(&e, &admin, &bidder, &user);
+
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x6869), &Identifier::Account(user.clone()));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(14))")]
+fn test_terminate_escrowed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let bidder = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, _) = setup_shutdown(&e, &admin, &bidder, &user);
+
+    client.with_source_account(&admin).terminate();
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(25))")]
+fn test_terminated_owner() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let bidder = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, _) = setup_shutdown(&e, &admin, &bidder, &user);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.refund(&Vec::from_array(
+        &e,
+        [OfferKey {
+            key: key.clone(),
+            bidder: Identifier::Account(bidder.clone()),
+        }],
+    ));
+    client.with_source_account(&admin).terminate();
+
+    client.with_source_account(&user).freeze(&key);
+}
+
+#[test]
+fn test_set_topic() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let (key, other) = (bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869));
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&other, &user_id);
+    client
+        .with_source_account(&user)
+        .set_topic(&key, &symbol!("profile"));
+
+    let value = Identifier::Contract(BytesN::from_array(&e, &[1; 32]));
+    client.with_source_account(&user).change_val(&key, &value);
+    client.with_source_account(&user).change_val(&other, &value);
+
+    // Only the key with a topic gets it, after the default one.
+    let events = e.events().all();
+    let (_, topics, _) = events.get_unchecked(events.len() - 2).unwrap();
+    let topic: Symbol = topics.get_unchecked(1).unwrap().try_into_val(&e).unwrap();
+    assert_eq!(topic, symbol!("profile"));
+    let (_, topics, _) = events.last().unwrap().unwrap();
+    assert_eq!(topics.len(), 1);
+    assert_eq!(count_events(&e, TOPIC_SET), 4);
+}
+
+#[test]
+fn test_entry_hash() {
+    use sha2::{Digest, Sha256};
+
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let user_id = Identifier::Account(user.clone());
+    let other_id = Identifier::Contract(BytesN::from_array(&e, &[1; 32]));
+    let (key, other) = (bytes!(&e, 0x68656c6c6f), bytes!(&e, 0x6869));
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&other, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &other_id);
+    client
+        .with_source_account(&user)
+        .transfer(&other, &other_id);
+
+    // Recompute the hashes outside the contract from the documented layout.
+    for (key, owner, version) in [(key, user_id.clone(), 2_u32), (other, other_id, 2)] {
+        let entry = (contract_id.clone(), key.clone(), owner, version, 0_u32).serialize(&e);
+        let mut preimage = [0; 256];
+        entry.copy_into_slice(&mut preimage[..entry.len() as usize]);
+        let expected: [u8; 32] = Sha256::digest(&preimage[..entry.len() as usize]).into();
+        assert_eq!(client.entry_hash(&key).to_array(), expected);
+    }
+
+    // `set` and `transfer` events carry the hash of the entry after the change.
+    let mut events = parse_events(&e, &contract_id);
+    match events.pop().unwrap() {
+        RegistryEvent::Transfer(transfer) => {
+            assert_eq!(transfer.hash, client.entry_hash(&bytes!(&e, 0x6869)))
+        }
+        _ => panic!("expected a transfer event"),
+    }
+    match events.pop().unwrap() {
+        RegistryEvent::Set(set) => {
+            assert_eq!(set.hash, client.entry_hash(&bytes!(&e, 0x68656c6c6f)))
+        }
+        _ => panic!("expected a set event"),
+    }
+}
+
+#[test]
+fn test_batch_large() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let user_id = Identifier::Account(user.clone());
+    let other_id = Identifier::Account(e.accounts().generate());
+    let mut keys = Vec::new(&e);
+    let mut owners = Vec::new(&e);
+    for i in 0..12 {
+        let key = Bytes::from_array(&e, &[b'k', b'a' + i]);
+        client.with_source_account(&user).change_val(&key, &user_id);
+        keys.push_back(key);
+        owners.push_back(other_id.clone());
+    }
+
+    // The invoker hands over the first half and removes the rest.
+    client
+        .with_source_account(&user)
+        .batch_xfer(&keys.slice(..6), &owners.slice(..6));
+    assert_eq!(client.get_owner(&keys.get_unchecked(5).unwrap()), other_id);
+    client.with_source_account(&user).del_vals(&keys.slice(6..));
+    assert_eq!(client.get_opt(&keys.get_unchecked(11).unwrap()), None);
+    assert_eq!(client.key_count(&user_id), 0);
+}
+
+#[test]
+fn test_set_meta() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&user)
+        .set_text(&key, &symbol!("url"), &bytes!(&e, 0x01));
+
+    // The records are replaced as a whole, along with the value.
+    let value = Identifier::Contract(BytesN::from_array(&e, &[1; 32]));
+    let mut texts = Map::new(&e);
+    texts.set(symbol!("avatar"), bytes!(&e, 0x02));
+    client
+        .with_source_account(&user)
+        .set_meta(&key, &value, &texts);
+    assert_eq!(client.get(&key), value);
+    assert_eq!(client.text(&key, &symbol!("url")), None);
+    assert_eq!(
+        client.text(&key, &symbol!("avatar")),
+        Some(bytes!(&e, 0x02))
+    );
+    match parse_events(&e, &contract_id).pop().unwrap() {
+        RegistryEvent::Set(set) => assert!(set.texts),
+        _ => panic!("expected a set event"),
+    }
+
+    // Keeping the value while clearing the records still counts as a write.
+    client
+        .with_source_account(&user)
+        .set_meta(&key, &value, &Map::new(&e));
+    assert_eq!(client.text(&key, &symbol!("avatar")), None);
+    assert_eq!(client.get_entry(&key).version, 3);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_set_meta_update_only() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let editor = e.accounts().generate();
+    let client = setup_grant(&e, &owner, &editor, Permission::Update);
+
+    client.with_source_account(&editor).set_meta(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(editor.clone()),
+        &Map::new(&e),
+    );
+}
+
+/// Sets up a registry with a two of three quorum of ed25519 signers, and
+/// returns the pause signatures of the first two.
+fn setup_quorum(e: &Env, admin: &AccountId) -> (ExampleContractClient, Signature, Signature) {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let (first_id, first) = soroban_auth::testutils::ed25519::generate(e);
+    let (second_id, second) = soroban_auth::testutils::ed25519::generate(e);
+    let third_id = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(admin)
+        .set_quorum(&Vec::from_array(e, [first_id, second_id, third_id]), &2);
+
+    let nonce = BigInt::zero(e);
+    let first = soroban_auth::testutils::ed25519::sign(
+        e,
+        &first,
+        &contract_id,
+        symbol!("pause"),
+        (nonce.clone(),),
+    );
+    let second = soroban_auth::testutils::ed25519::sign(
+        e,
+        &second,
+        &contract_id,
+        symbol!("pause"),
+        (nonce,),
+    );
+    (client, first, second)
+}
+
+#[test]
+fn test_quorum_pause() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let (client, first, second) = setup_quorum(&e, &admin);
+    assert_eq!(client.get_quorum().unwrap().threshold, 2);
+
+    client.q_pause(&Vec::from_array(&e, [first, second]));
+    assert!(client.get_config().paused);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_quorum_pause_short() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let (client, first, _) = setup_quorum(&e, &admin);
+
+    client.q_pause(&Vec::from_array(&e, [first]));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_quorum_pause_same_signer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let (client, first, _) = setup_quorum(&e, &admin);
+
+    client.q_pause(&Vec::from_array(&e, [first.clone(), first]));
+}
+
+#[test]
+fn test_set_uses() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let editor = e.accounts().generate();
+    let client = setup_grant(&e, &owner, &editor, Permission::Update);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let editor_id = Identifier::Account(editor.clone());
+    assert_eq!(client.approval(&key, &editor_id), Some((0, u32::MAX)));
+    client
+        .with_source_account(&owner)
+        .set_uses(&key, &editor_id, &2);
+
+    let value = Identifier::Contract(BytesN::from_array(&e, &[1; 32]));
+    client.with_source_account(&editor).change_val(&key, &value);
+    client
+        .with_source_account(&editor)
+        .push_val(&key, &editor_id);
+    assert_eq!(client.approval(&key, &editor_id), Some((0, 0)));
+    assert!(!client.can_change(&key, &editor_id));
+
+    // Changes by the owner don't count, and a top-up restores access.
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &editor_id);
+    client
+        .with_source_account(&owner)
+        .set_uses(&key, &editor_id, &1);
+    client.with_source_account(&editor).change_val(&key, &value);
+    assert_eq!(client.approval(&key, &editor_id), Some((0, 0)));
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_set_uses_exhausted() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let editor = e.accounts().generate();
+    let client = setup_grant(&e, &owner, &editor, Permission::Update);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let editor_id = Identifier::Account(editor.clone());
+    client
+        .with_source_account(&owner)
+        .set_uses(&key, &editor_id, &1);
+
+    let value = Identifier::Contract(BytesN::from_array(&e, &[1; 32]));
+    client.with_source_account(&editor).change_val(&key, &value);
+    client
+        .with_source_account(&editor)
+        .change_val(&key, &editor_id);
+}
+
+#[test]
+fn test_key_age() {
+    let e = Env::default();
+    e.ledger().with_mut(|l| l.sequence_number = 5);
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    assert_eq!(client.key_age(&key), 0);
+
+    e.ledger().with_mut(|l| l.sequence_number = 25);
+    assert_eq!(client.key_age(&key), 20);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(1))")]
+fn test_key_age_unset() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.key_age(&bytes!(&e, 0x68656c6c6f));
+}
+
+#[test]
+fn test_change_val_previous() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let other_id = Identifier::Contract(BytesN::from_array(&e, &[1; 32]));
+    assert_eq!(
+        client.with_source_account(&user).change_val(&key, &user_id),
+        None
+    );
+    assert_eq!(
+        client
+            .with_source_account(&user)
+            .change_val(&key, &other_id),
+        Some(user_id.clone())
+    );
+
+    // Writing the stored value again is a no-op that still reports it.
+    let previous = client.with_source_account(&user).use_sig(
+        &soroban_auth::Signature::Invoker,
+        &BigInt::zero(&e),
+        &key,
+        &other_id,
+    );
+    assert_eq!(previous, Some(other_id));
+}
+
+#[test]
+fn test_xfer_from() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let relayer = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let relayer_id = Identifier::Account(relayer.clone());
+    let new_owner = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&user)
+        .approve_to(&key, &relayer_id);
+    assert_eq!(client.spender(&key), Some(relayer_id));
+
+    client
+        .with_source_account(&relayer)
+        .xfer_from(&key, &new_owner);
+    assert_eq!(client.get_owner(&key), new_owner);
+    assert_eq!(client.get(&key), user_id);
+    assert_eq!(client.spender(&key), None);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_xfer_from_used() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let relayer = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&user)
+        .approve_to(&key, &Identifier::Account(relayer.clone()));
+
+    let relayer_id = Identifier::Account(relayer.clone());
+    client
+        .with_source_account(&relayer)
+        .xfer_from(&key, &relayer_id);
+    client
+        .with_source_account(&relayer)
+        .xfer_from(&key, &user_id);
+}
+
+fn setup_blocked(e: &Env, admin: &AccountId, blocked: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client
+        .with_source_account(admin)
+        .block_val(&Identifier::Account(blocked.clone()));
+    client
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_blocked_write() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let bad = e.accounts().generate();
+    let client = setup_blocked(&e, &admin, &bad);
+
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68656c6c6f), &Identifier::Account(bad));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_blocked_transfer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let bad = e.accounts().generate();
+    let client = setup_blocked(&e, &admin, &bad);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(&user)
+        .transfer(&key, &Identifier::Account(bad));
+}
+
+#[test]
+fn test_blocked_existing() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let bad = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let bad_id = Identifier::Account(bad);
+    client.with_source_account(&user).change_val(&key, &bad_id);
+    assert!(!client.is_blocked(&key));
+
+    client.with_source_account(&admin).block_val(&bad_id);
+    assert!(client.is_blocked(&key));
+    assert_eq!(client.get(&key), bad_id);
+    assert!(!client.is_blocked(&bytes!(&e, 0x6f74686572)));
+
+    client.with_source_account(&admin).unblock(&bad_id);
+    assert!(!client.is_blocked(&key));
+    let other = bytes!(&e, 0x6f74686572);
+    client
+        .with_source_account(&user)
+        .change_val(&other, &bad_id);
+    assert_eq!(client.get(&other), bad_id);
+}
+
+#[test]
+fn test_default_owner() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    assert_eq!(
+        client.dflt_owner(),
+        Identifier::Contract(contract_id.clone())
+    );
+    let user_id = Identifier::Account(user.clone());
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x6f6e65), &user_id);
+    assert_eq!(client.get_owner(&bytes!(&e, 0x6f6e65)), user_id);
+
+    let admin_id = Identifier::Account(admin.clone());
+    client.with_source_account(&admin).set_downer(&admin_id);
+    client
+        .with_source_account(&admin)
+        .set_phase(&symbol!("closed"));
+    assert_eq!(client.dflt_owner(), admin_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&admin)
+        .change_val(&key, &user_id);
+    assert_eq!(client.get_owner(&key), admin_id);
+    assert_eq!(client.get(&key), user_id);
+
+    client
+        .with_source_account(&admin)
+        .set_downer(&Identifier::Contract(contract_id.clone()));
+    assert_eq!(client.dflt_owner(), Identifier::Contract(contract_id));
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_default_owner_others() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client
+        .with_source_account(&admin)
+        .set_downer(&Identifier::Account(admin.clone()));
+
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x68656c6c6f), &Identifier::Account(user));
+}
+
+#[test]
+fn test_reserved_nonces() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let reserve = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("rsv_nonce"),
+        (BigInt::zero(&e),),
+    );
+    let first = client.rsv_nonce(&reserve);
+    let reserve = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("rsv_nonce"),
+        (BigInt::from_u32(&e, 2),),
+    );
+    let second = client.rsv_nonce(&reserve);
+    assert_eq!(first, BigInt::from_u32(&e, 1));
+    assert_eq!(second, BigInt::from_u32(&e, 3));
+    assert_eq!(client.nonce(&signer_id), BigInt::from_u32(&e, 4));
+
+    let one = bytes!(&e, 0x6f6e65);
+    let two = bytes!(&e, 0x74776f);
+    let value = Identifier::Account(e.accounts().generate());
+    let sig_one = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (first.clone(), one.clone(), value.clone()),
+    );
+    let sig_two = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (second.clone(), two.clone(), value.clone()),
+    );
+
+    // The payloads go through in the opposite order they were prepared in,
+    // without consuming the sequential nonce.
+    assert!(client.sim_sig(&sig_two, &two, &value, &second));
+    client.use_sig(&sig_two, &second, &two, &value);
+    client.use_sig(&sig_one, &first, &one, &value);
+    assert_eq!(client.get(&one), value);
+    assert_eq!(client.get(&two), value);
+    assert_eq!(client.nonce(&signer_id), BigInt::from_u32(&e, 4));
+    assert!(!client.sim_sig(&sig_one, &one, &value, &first));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_reserved_nonce_reused() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (_, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let kept = client
+        .with_source_account(&user)
+        .rsv_nonce(&Signature::Invoker);
+    assert_eq!(kept, BigInt::zero(&e));
+    let reserve = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("rsv_nonce"),
+        (BigInt::zero(&e),),
+    );
+    let nonce = client.rsv_nonce(&reserve);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (nonce.clone(), key.clone(), value.clone()),
+    );
+    client.use_sig(&sig, &nonce, &key, &value);
+    client.use_sig(&sig, &nonce, &key, &value);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_reserved_nonce_expired() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_rsvttl(&10);
+    let (_, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let reserve = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("rsv_nonce"),
+        (BigInt::zero(&e),),
+    );
+    let nonce = client.rsv_nonce(&reserve);
+    e.ledger().with_mut(|l| l.sequence_number += 11);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(admin);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (nonce.clone(), key.clone(), value.clone()),
+    );
+    client.use_sig(&sig, &nonce, &key, &value);
+}
+
+#[test]
+fn test_verify_get() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(e.accounts().generate());
+    let user = e.accounts().generate();
+    client.with_source_account(&user).change_val(&key, &value);
+    client.with_source_account(&user).set_priv(&key, &true);
+    client.with_source_account(&user).transfer(&key, &signer_id);
+
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("read"),
+        (BigInt::zero(&e), key.clone()),
+    );
+    assert_eq!(client.verify_get(&sig, &key), value);
+    assert_eq!(client.nonce(&signer_id), BigInt::zero(&e));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_verify_get_unauthorized() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (_, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user = e.accounts().generate();
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).set_priv(&key, &true);
+
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("read"),
+        (BigInt::zero(&e), key.clone()),
+    );
+    client.verify_get(&sig, &key);
+}
+
+#[test]
+fn test_rental() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let owner = e.accounts().generate();
+    let renter = e.accounts().generate();
+    let (client, token) = setup_offers(&e, &admin, &renter, 100);
+    let owner_id = Identifier::Account(owner.clone());
+    let renter_id = Identifier::Account(renter.clone());
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &owner_id);
+    assert_eq!(client.get_rental(&key), None);
+
+    e.ledger().with_mut(|l| l.sequence_number = 10);
+    let price = BigInt::from_u32(&e, 100);
+    let sig = rent_sig(&e, &client.contract_id, &renter, &key, 30, &price);
+    client
+        .with_source_account(&owner)
+        .rent_out(&key, &sig, &30, &price);
+    assert_eq!(token.balance(&renter_id), BigInt::zero(&e));
+    assert_eq!(client.get_rental(&key), Some((renter_id.clone(), 30)));
+
+    client
+        .with_source_account(&renter)
+        .change_val(&key, &renter_id);
+    assert_eq!(client.get(&key), renter_id);
+    assert_eq!(client.get_owner(&key), owner_id);
+
+    // Once the rental runs out the value stays until the owner changes it.
+    e.ledger().with_mut(|l| l.sequence_number = 31);
+    assert_eq!(client.get_rental(&key), None);
+    assert_eq!(client.get(&key), renter_id);
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &owner_id);
+    assert_eq!(client.get(&key), owner_id);
+
+    client
+        .with_source_account(&owner)
+        .end_rent(&key, &Signature::Invoker);
+    assert_eq!(token.balance(&owner_id), BigInt::from_u32(&e, 100));
+    assert_eq!(token.balance(&renter_id), BigInt::zero(&e));
+}
+
+/// Returns the signature of `renter` agreeing to rent `key` until `until` for
+/// `price`, signed by a key added to the account. Invoking as the account
+/// resets it, so this goes after the renter's last invocation before the
+/// rental.
+fn rent_sig(
+    e: &Env,
+    contract_id: &BytesN<32>,
+    renter: &AccountId,
+    key: &Bytes,
+    until: u64,
+    price: &BigInt,
+) -> Signature {
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(e);
+    let public_key = match signer_id {
+        Identifier::Ed25519(public_key) => public_key,
+        _ => unreachable!(),
+    };
+    e.accounts().set_signer_weight(renter, &public_key, 1);
+    e.accounts().set_thresholds(renter, 1, 1, 1);
+
+    let nonce = BigInt::zero(e);
+    let args = (nonce, key.clone(), until, price.clone()).into_val(e);
+    sign_account(e, renter, &[signer], contract_id, symbol!("rent_out"), args)
+}
+
+/// Registers the contract with `owner` holding a key rented out to `renter`
+/// from ledger 10 until ledger 30, returning the key.
+fn setup_rental(e: &Env, owner: &AccountId, renter: &AccountId) -> (ExampleContractClient, Bytes) {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(owner)
+        .change_val(&key, &Identifier::Account(owner.clone()));
+    e.ledger().with_mut(|l| l.sequence_number = 10);
+    let sig = rent_sig(e, &contract_id, renter, &key, 30, &BigInt::zero(e));
+    client
+        .with_source_account(owner)
+        .rent_out(&key, &sig, &30, &BigInt::zero(e));
+    (client, key)
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_rental_owner_locked() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let renter = e.accounts().generate();
+    let (client, key) = setup_rental(&e, &owner, &renter);
+
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &Identifier::Account(owner.clone()));
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_rental_before() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let renter = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &Identifier::Account(owner.clone()));
+    client
+        .with_source_account(&renter)
+        .change_val(&key, &Identifier::Account(renter.clone()));
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_rental_over() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let renter = e.accounts().generate();
+    let (client, key) = setup_rental(&e, &owner, &renter);
+
+    e.ledger().with_mut(|l| l.sequence_number = 31);
+    client
+        .with_source_account(&renter)
+        .change_val(&key, &Identifier::Account(renter.clone()));
+}
+
+#[test]
+fn test_rental_ended_early() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (renter_id, renter) = soroban_auth::testutils::ed25519::generate(&e);
+    let owner_id = Identifier::Account(owner.clone());
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &owner_id);
+    let agreed = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &renter,
+        &contract_id,
+        symbol!("rent_out"),
+        (BigInt::zero(&e), key.clone(), 30u64, BigInt::zero(&e)),
+    );
+    client
+        .with_source_account(&owner)
+        .rent_out(&key, &agreed, &30, &BigInt::zero(&e));
+
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &renter,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::from_u32(&e, 1), key.clone(), renter_id.clone()),
+    );
+    client.use_sig(&sig, &BigInt::from_u32(&e, 1), &key, &renter_id);
+    assert_eq!(client.get(&key), renter_id);
+
+    let consent = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &renter,
+        &contract_id,
+        symbol!("end_rent"),
+        (BigInt::from_u32(&e, 2), key.clone()),
+    );
+    client.with_source_account(&owner).end_rent(&key, &consent);
+    assert_eq!(client.get_rental(&key), None);
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &owner_id);
+    assert_eq!(client.get(&key), owner_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_rental_end_unagreed() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let renter = e.accounts().generate();
+    let (client, key) = setup_rental(&e, &owner, &renter);
+
+    client
+        .with_source_account(&owner)
+        .end_rent(&key, &Signature::Invoker);
+}
+
+#[test]
+#[should_panic]
+fn test_rental_not_agreed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let owner = e.accounts().generate();
+    let renter = e.accounts().generate();
+    let (client, _) = setup_offers(&e, &admin, &renter, 100);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &Identifier::Account(owner.clone()));
+
+    // The renter agreed to a free rental, which can't be turned into a paid one.
+    let sig = rent_sig(
+        &e,
+        &client.contract_id,
+        &renter,
+        &key,
+        30,
+        &BigInt::zero(&e),
+    );
+    client
+        .with_source_account(&owner)
+        .rent_out(&key, &sig, &30, &BigInt::from_u32(&e, 100));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(23))")]
+fn test_rental_twice() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let renter = e.accounts().generate();
+    let (client, key) = setup_rental(&e, &owner, &renter);
+
+    let sig = rent_sig(
+        &e,
+        &client.contract_id,
+        &renter,
+        &key,
+        30,
+        &BigInt::zero(&e),
+    );
+    client
+        .with_source_account(&owner)
+        .rent_out(&key, &sig, &30, &BigInt::zero(&e));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(20))")]
+fn test_rental_remove() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let renter = e.accounts().generate();
+    let (client, key) = setup_rental(&e, &owner, &renter);
+
+    client.with_source_account(&owner).remove_val(&key);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(42))")]
+fn test_rental_negative_price() {
+    let e = Env::default();
+
+    let owner = e.accounts().generate();
+    let renter = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&owner)
+        .change_val(&key, &Identifier::Account(owner.clone()));
+    let price = BigInt::from_i32(&e, -100);
+    let sig = rent_sig(&e, &contract_id, &renter, &key, 30, &price);
+    client
+        .with_source_account(&owner)
+        .rent_out(&key, &sig, &30, &price);
+}
+
+#[test]
+fn test_delegate_cap() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_maxdlg(&2);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    assert_eq!(client.dlg_count(&key), 0);
+
+    let first = Identifier::Account(e.accounts().generate());
+    let second = Identifier::Account(e.accounts().generate());
+    let mut perms = Vec::new(&e);
+    perms.push_back(Permission::Update);
+    client
+        .with_source_account(&user)
+        .grant(&key, &first, &perms);
+    client
+        .with_source_account(&user)
+        .grant(&key, &second, &perms);
+    assert_eq!(client.dlg_count(&key), 2);
+
+    // Delegates already holding permissions can be granted more at the cap.
+    let mut more = Vec::new(&e);
+    more.push_back(Permission::Metadata);
+    client.with_source_account(&user).grant(&key, &first, &more);
+    assert_eq!(client.dlg_count(&key), 2);
+
+    client
+        .with_source_account(&user)
+        .ungrant(&key, &second, &Permission::Update);
+    assert_eq!(client.dlg_count(&key), 1);
+    let third = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&user)
+        .grant(&key, &third, &perms);
+    assert_eq!(client.dlg_count(&key), 2);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_delegate_cap_exceeded() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_maxdlg(&1);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    let mut perms = Vec::new(&e);
+    perms.push_back(Permission::Update);
+    client.with_source_account(&user).grant(
+        &key,
+        &Identifier::Account(e.accounts().generate()),
+        &perms,
+    );
+    client.with_source_account(&user).grant(
+        &key,
+        &Identifier::Account(e.accounts().generate()),
+        &perms,
+    );
+}
+
+#[test]
+fn test_get_strict() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let missing = bytes!(&e, 0x6d697373696e67);
+    assert_eq!(client.try_get_strict(&missing), Err(Ok(Error::KeyNotFound)));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(client.get_strict(&key), value);
+
+    client.with_source_account(&admin).dispute(&key);
+    assert_eq!(client.try_get_strict(&key), Err(Ok(Error::Disputed)));
+    client
+        .with_source_account(&admin)
+        .resolve(&key, &symbol!("dismiss"), &None);
+    assert_eq!(client.get_strict(&key), value);
+
+    client.with_source_account(&admin).block_val(&value);
+    assert_eq!(client.try_get_strict(&key), Err(Ok(Error::NotAllowed)));
+    client.with_source_account(&admin).unblock(&value);
+    assert_eq!(client.get_strict(&key), value);
+
+    client.with_source_account(&user).burn(&key);
+    assert_eq!(client.try_get_strict(&key), Err(Ok(Error::Burned)));
+}
+
+#[test]
+fn test_noop_write_quiet() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &value);
+    let previous = client.with_source_account(&user).change_val(&key, &value);
+
+    assert_eq!(previous, Some(value));
+    assert_eq!(count_events(&e, TOPIC_SET), 1);
+    assert_eq!(client.history(&key).len(), 0);
+    assert_eq!(client.writes(), 1);
+}
+
+/// Registers the contract with `admin` and a key of `user` under dispute.
+fn setup_journal(e: &Env, admin: &AccountId, user: &AccountId) -> (ExampleContractClient, Bytes) {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(admin).dispute(&key);
+    (client, key)
+}
+
+#[test]
+fn test_journal() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_journal(&e, &admin, &user);
+
+    let new_owner = Identifier::Account(e.accounts().generate());
+    let allowed = Identifier::Account(e.accounts().generate());
+    let id = client.with_source_account(&admin).jrnl_begin();
+    client.with_source_account(&admin).jrnl_add(
+        &id,
+        &AdminOp::Reassign(KeyOwner {
+            key: key.clone(),
+            owner: new_owner.clone(),
+        }),
+    );
+    client
+        .with_source_account(&admin)
+        .jrnl_add(&id, &AdminOp::Phase(symbol!("allowlist")));
+    client
+        .with_source_account(&admin)
+        .jrnl_add(&id, &AdminOp::Allow(allowed));
+
+    // Recording leaves the registry untouched.
+    assert_eq!(client.get_owner(&key), Identifier::Account(user));
+    assert_eq!(client.get_config().phase, symbol!("open"));
+
+    client.with_source_account(&admin).jrnl_apply(&id);
+    assert_eq!(client.get_owner(&key), new_owner);
+    assert_eq!(client.get_config().phase, symbol!("allowlist"));
+    assert_eq!(
+        client.with_source_account(&admin).try_jrnl_apply(&id),
+        Err(Ok(Error::NoRequest))
+    );
+}
+
+#[test]
+fn test_journal_invalid() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_journal(&e, &admin, &user);
+
+    let id = client.with_source_account(&admin).jrnl_begin();
+    client
+        .with_source_account(&admin)
+        .jrnl_add(&id, &AdminOp::Phase(symbol!("closed")));
+    client.with_source_account(&admin).jrnl_add(
+        &id,
+        &AdminOp::Reassign(KeyOwner {
+            key: key.clone(),
+            owner: Identifier::Account(admin.clone()),
+        }),
+    );
+    client
+        .with_source_account(&admin)
+        .jrnl_add(&id, &AdminOp::Dismiss(bytes!(&e, 0x6f74686572)));
+
+    assert_eq!(
+        client.with_source_account(&admin).try_jrnl_apply(&id),
+        Err(Ok(Error::NoDispute))
+    );
+    assert_eq!(client.get_config().phase, symbol!("open"));
+    assert_eq!(client.get_owner(&key), Identifier::Account(user));
+
+    client.with_source_account(&admin).jrnl_abort(&id);
+    assert_eq!(
+        client.with_source_account(&admin).try_jrnl_apply(&id),
+        Err(Ok(Error::NoRequest))
+    );
+}
+
+#[test]
+fn test_priv_keys() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let user_id = Identifier::Account(user.clone());
+    let mut private = Vec::new(&e);
+    for i in 0..10u8 {
+        let key = Bytes::from_array(&e, &[0x6b, i]);
+        client.with_source_account(&user).change_val(&key, &user_id);
+        if i % 3 == 0 {
+            client.with_source_account(&user).set_priv(&key, &true);
+            private.push_back(key);
+        }
+    }
+    client
+        .with_source_account(&user)
+        .set_priv(&Bytes::from_array(&e, &[0x6b, 3]), &false);
+    private.remove(1);
+
+    assert_eq!(
+        client.with_source_account(&user).priv_keys(&user_id),
+        private
+    );
+    assert_eq!(
+        client.with_source_account(&admin).priv_keys(&user_id),
+        private
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_priv_keys_stranger() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let stranger = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let user_id = Identifier::Account(user.clone());
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&user).set_priv(&key, &true);
+
+    client.with_source_account(&stranger).priv_keys(&user_id);
+}
+
+#[test]
+fn test_wildcard() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let user_id = Identifier::Account(user.clone());
+    let wild = Identifier::Account(e.accounts().generate());
+    let fallback = Identifier::Account(e.accounts().generate());
+    let held = bytes!(&e, 0x68656c64);
+    let set = bytes!(&e, 0x736574);
+    let theirs = bytes!(&e, 0x746865697273);
+    let unset = bytes!(&e, 0x756e736574);
+    client.with_source_account(&user).hold_key(&held);
+    client.with_source_account(&user).change_val(&set, &user_id);
+    client.with_source_account(&other).hold_key(&theirs);
+
+    assert_eq!(client.wildcard(&user_id), None);
+    client
+        .with_source_account(&user)
+        .set_wild(&Signature::Invoker, &Some(wild.clone()));
+    client.with_source_account(&admin).set_dflt(&fallback);
+    assert_eq!(client.wildcard(&user_id), Some(wild.clone()));
+
+    assert_eq!(client.get_or(&set), user_id);
+    assert_eq!(client.get_or(&held), wild);
+    assert_eq!(client.get_or(&theirs), fallback);
+    assert_eq!(client.get_or(&unset), fallback);
+
+    client
+        .with_source_account(&user)
+        .set_wild(&Signature::Invoker, &None);
+    assert_eq!(client.wildcard(&user_id), None);
+    assert_eq!(client.get_or(&held), fallback);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(1))")]
+fn test_wildcard_none() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c64);
+    client.with_source_account(&user).hold_key(&key);
+    client.get_or(&key);
+}
+
+#[test]
+fn test_memo_val() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let memo = bytes!(&e, 0x696e766f6963652034);
+    client
+        .with_source_account(&user)
+        .memo_val(&key, &user_id, &memo);
+    assert_eq!(client.get(&key), user_id);
+    assert_eq!(client.get_memo(&key), memo);
+
+    // A later write without a memo drops the one of the previous value.
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(e.accounts().generate()));
+    assert_eq!(client.get_memo(&key), Bytes::new(&e));
+
+    let memo = Bytes::from_array(&e, &[0x61; MAX_MEMO as usize]);
+    client
+        .with_source_account(&user)
+        .memo_val(&key, &user_id, &memo);
+    assert_eq!(client.get_memo(&key), memo);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(46))")]
+fn test_memo_too_long() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client.with_source_account(&user).memo_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+        &Bytes::from_array(&e, &[0x61; MAX_MEMO as usize + 1]),
+    );
+}
+
+#[test]
+fn test_tombstone() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    assert_eq!(client.removed_at(&key), None);
+    assert_eq!(client.get_opt(&key), None);
+
+    client.with_source_account(&user).change_val(&key, &user_id);
+    e.ledger().with_mut(|l| l.sequence_number = 7);
+    client.with_source_account(&user).remove_val(&key);
+    assert_eq!(client.removed_at(&key), Some(7));
+    assert_eq!(client.get_opt(&key), None);
+
+    // Claiming the key again purges its tombstone.
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.removed_at(&key), None);
+}
+
+#[test]
+fn test_sweep_tomb() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_tmbttl(&10);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).remove_val(&key);
+
+    e.ledger().with_mut(|l| l.sequence_number = 11);
+    client.sweep_tomb(&key);
+    assert_eq!(client.removed_at(&key), None);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(31))")]
+fn test_sweep_tomb_early() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_tmbttl(&10);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).remove_val(&key);
+
+    e.ledger().with_mut(|l| l.sequence_number = 10);
+    client.sweep_tomb(&key);
+}
+
+#[test]
+fn test_social_rcv() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (first_id, first) = soroban_auth::testutils::ed25519::generate(&e);
+    let (second_id, _) = soroban_auth::testutils::ed25519::generate(&e);
+    let (third_id, third) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    let mut guardians = Vec::new(&e);
+    guardians.push_back(first_id);
+    guardians.push_back(second_id);
+    guardians.push_back(third_id);
+    client
+        .with_source_account(&user)
+        .set_guards(&key, &guardians, &2);
+
+    let new_owner = Identifier::Account(e.accounts().generate());
+    let mut sigs = Vec::new(&e);
+    sigs.push_back(soroban_auth::testutils::ed25519::sign(
+        &e,
+        &first,
+        &contract_id,
+        symbol!("social_rcv"),
+        (BigInt::zero(&e), key.clone(), new_owner.clone()),
+    ));
+    sigs.push_back(soroban_auth::testutils::ed25519::sign(
+        &e,
+        &third,
+        &contract_id,
+        symbol!("social_rcv"),
+        (BigInt::zero(&e), key.clone(), new_owner.clone()),
+    ));
+    client.social_rcv(&key, &new_owner, &sigs);
+
+    assert_eq!(client.get_owner(&key), new_owner);
+    assert_eq!(client.get(&key), Identifier::Account(user));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_social_rcv_short() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (first_id, first) = soroban_auth::testutils::ed25519::generate(&e);
+    let (second_id, _) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    let mut guardians = Vec::new(&e);
+    guardians.push_back(first_id);
+    guardians.push_back(second_id);
+    client
+        .with_source_account(&user)
+        .set_guards(&key, &guardians, &2);
+
+    let new_owner = Identifier::Account(e.accounts().generate());
+    let mut sigs = Vec::new(&e);
+    sigs.push_back(soroban_auth::testutils::ed25519::sign(
+        &e,
+        &first,
+        &contract_id,
+        symbol!("social_rcv"),
+        (BigInt::zero(&e), key.clone(), new_owner.clone()),
+    ));
+    client.social_rcv(&key, &new_owner, &sigs);
+}
+
+#[test]
+fn test_change_args_boundaries() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_maxlen(&64);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    let (ed25519_id, _) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let mut keys = Vec::new(&e);
+    keys.push_back(Bytes::from_array(&e, &[0x6b]));
+    keys.push_back(Bytes::from_array(&e, &[0x6b; 32]));
+    keys.push_back(Bytes::from_array(&e, &[0x6b; 64]));
+    let mut values = Vec::new(&e);
+    values.push_back(Identifier::Account(e.accounts().generate()));
+    values.push_back(ed25519_id);
+
+    for key in keys.iter() {
+        let key = key.unwrap();
+        for value in values.iter() {
+            let value = value.unwrap();
+            let nonce = client.nonce(&signer_id);
+            let args = build_change_args(&e, &nonce, &key, &value);
+            assert_eq!(args.len(), 3);
+
+            // Signing the tuple or the built arguments gives the same payload.
+            let sig = soroban_auth::testutils::ed25519::sign(
+                &e,
+                &signer,
+                &contract_id,
+                symbol!("change"),
+                (nonce.clone(), key.clone(), value.clone()),
+            );
+            assert_eq!(
+                sig,
+                soroban_auth::testutils::ed25519::sign(
+                    &e,
+                    &signer,
+                    &contract_id,
+                    symbol!("change"),
+                    args,
+                )
+            );
+            assert!(client.sim_sig(&sig, &key, &value, &nonce));
+            client.use_sig(&sig, &nonce, &key, &value);
+            assert_eq!(client.get(&key), value);
+        }
+    }
+}
+
+/// Registers the contract with `admin`, limiting owners to two keys, both
+/// claimed by `user`.
+fn setup_maxown(e: &Env, admin: &AccountId, user: &AccountId) -> ExampleContractClient {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(admin).set_maxown(&2);
+
+    let user_id = Identifier::Account(user.clone());
+    client
+        .with_source_account(user)
+        .change_val(&bytes!(e, 0x6f6e65), &user_id);
+    client
+        .with_source_account(user)
+        .change_val(&bytes!(e, 0x74776f), &user_id);
+    client
+}
+
+#[test]
+fn test_maxown() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_maxown(&e, &admin, &user);
+    let user_id = Identifier::Account(user.clone());
+    assert_eq!(client.key_count(&user_id), 2);
+
+    // Writes to keys already owned and giving keys away stay possible.
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x6f6e65), &Identifier::Account(admin.clone()));
+    let other = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&user)
+        .transfer(&bytes!(&e, 0x6f6e65), &other);
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x7468726565), &user_id);
+    assert_eq!(client.key_count(&user_id), 2);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_maxown_claim() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let client = setup_maxown(&e, &admin, &user);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x7468726565),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_maxown_transfer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let client = setup_maxown(&e, &admin, &user);
+
+    let key = bytes!(&e, 0x7468726565);
+    client
+        .with_source_account(&other)
+        .change_val(&key, &Identifier::Account(other.clone()));
+    client
+        .with_source_account(&other)
+        .transfer(&key, &Identifier::Account(user.clone()));
+}
+
+/// Registers the contract with `admin` and a key of `user` pointing at
+/// `user`, returning its key.
+fn setup_restrict(e: &Env, admin: &AccountId, user: &AccountId) -> (ExampleContractClient, Bytes) {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    (client, key)
+}
+
+#[test]
+fn test_restrict() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_restrict(&e, &admin, &user);
+    let user_id = Identifier::Account(user.clone());
+    assert_eq!(client.restr_of(&user_id), symbol!("none"));
+
+    client.with_source_account(&admin).restrict(&user_id);
+    assert_eq!(client.restr_of(&user_id), symbol!("restricted"));
+
+    // Updates to owned keys keep working, and so do entries already
+    // pointing at it.
+    client.with_source_account(&user).change_val(&key, &user_id);
+    let other = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &other);
+    assert_eq!(client.get(&key), other);
+
+    client.with_source_account(&admin).unrestrict(&user_id);
+    assert_eq!(client.restr_of(&user_id), symbol!("none"));
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x6f74686572), &user_id);
+
+    client.with_source_account(&admin).block_val(&user_id);
+    assert_eq!(client.restr_of(&user_id), symbol!("blocked"));
+
+    client.with_source_account(&admin).block(&user_id);
+    assert_eq!(client.restr_of(&user_id), symbol!("banned"));
+
+    // Unblocking lifts both blocks, so the identifier can be a value again.
+    client.with_source_account(&admin).unblock(&user_id);
+    assert_eq!(client.restr_of(&user_id), symbol!("none"));
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.get(&key), user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_restrict_claim() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, _) = setup_restrict(&e, &admin, &user);
+    client
+        .with_source_account(&admin)
+        .restrict(&Identifier::Account(user.clone()));
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x6f74686572),
+        &Identifier::Account(admin.clone()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_restrict_transfer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, _) = setup_restrict(&e, &admin, &user);
+    client
+        .with_source_account(&admin)
+        .restrict(&Identifier::Account(user.clone()));
+
+    let key = bytes!(&e, 0x6f74686572);
+    client
+        .with_source_account(&other)
+        .change_val(&key, &Identifier::Account(other.clone()));
+    client
+        .with_source_account(&other)
+        .transfer(&key, &Identifier::Account(user.clone()));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_restrict_value() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, _) = setup_restrict(&e, &admin, &user);
+    client
+        .with_source_account(&admin)
+        .restrict(&Identifier::Account(user.clone()));
+
+    client.with_source_account(&other).change_val(
+        &bytes!(&e, 0x6f74686572),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_banned_update() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_restrict(&e, &admin, &user);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&admin).block_val(&user_id);
+
+    // Unlike a restriction, a ban refuses even the value an entry held.
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(admin.clone()));
+    client.with_source_account(&user).change_val(&key, &user_id);
+}
+
+#[test]
+fn test_default_owner_agrees() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    assert_eq!(client.get_owner(&key), client.dflt_owner());
+    assert_eq!(client.get_owner(&key), Identifier::Contract(contract_id));
+
+    let admin_id = Identifier::Account(admin.clone());
+    client.with_source_account(&admin).set_downer(&admin_id);
+    assert_eq!(client.dflt_owner(), admin_id);
+    assert_eq!(client.get_owner(&key), admin_id);
+
+    // The owner reported for the unset key is the one its claim is
+    // authorized against, and the claim keeps it.
+    let user_id = Identifier::Account(user.clone());
+    client
+        .with_source_account(&admin)
+        .change_val(&key, &user_id);
+    assert_eq!(client.get_owner(&key), admin_id);
+    assert_eq!(client.get(&key), user_id);
+}
+
+#[test]
+fn test_redeem() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    client.with_source_account(&admin).set_csig(&signer_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("redeem"),
+        (key.clone(), user_id.clone()),
+    );
+    client.with_source_account(&user).redeem(&sig, &key);
+    assert_eq!(client.get_owner(&key), user_id);
+    assert_eq!(client.nonce(&signer_id), BigInt::zero(&e));
+
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.get(&key), user_id);
+}
+
+#[test]
+#[should_panic]
+fn test_redeem_other_invoker() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    client.with_source_account(&admin).set_csig(&signer_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("redeem"),
+        (key.clone(), Identifier::Account(user)),
+    );
+    client
+        .with_source_account(&e.accounts().generate())
+        .redeem(&sig, &key);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_redeem_other_signer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    let (signer_id, _) = soroban_auth::testutils::ed25519::generate(&e);
+    let (_, other) = soroban_auth::testutils::ed25519::generate(&e);
+    client.with_source_account(&admin).set_csig(&signer_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &other,
+        &contract_id,
+        symbol!("redeem"),
+        (key.clone(), Identifier::Account(user.clone())),
+    );
+    client.with_source_account(&user).redeem(&sig, &key);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(23))")]
+fn test_redeem_twice() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+    client.with_source_account(&admin).set_csig(&signer_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("redeem"),
+        (key.clone(), user_id),
+    );
+    client.with_source_account(&user).redeem(&sig, &key);
+
+    // Even once the key is deleted, the claim stays redeemed.
+    client.with_source_account(&user).remove_val(&key);
+    client.with_source_account(&user).redeem(&sig, &key);
+}
+
+/// Registers the contract with a key of `user` proposed for transfer to
+/// `other` until ledger 10, returning its key.
+fn setup_handoff(e: &Env, user: &AccountId, other: &AccountId) -> (ExampleContractClient, Bytes) {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    client
+        .with_source_account(user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(user)
+        .prop_xfer(&key, &Identifier::Account(other.clone()), &10);
+    (client, key)
+}
+
+#[test]
+fn test_handoff() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, key) = setup_handoff(&e, &user, &other);
+
+    e.ledger().with_mut(|l| l.sequence_number = 10);
+    client.with_source_account(&other).acpt_xfer(&key);
+    assert_eq!(client.get_owner(&key), Identifier::Account(other.clone()));
+    assert_eq!(client.get(&key), Identifier::Account(user));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(10))")]
+fn test_handoff_expired() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, key) = setup_handoff(&e, &user, &other);
+
+    e.ledger().with_mut(|l| l.sequence_number = 11);
+    client.with_source_account(&other).acpt_xfer(&key);
+}
+
+#[test]
+fn test_handoff_repropose() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, key) = setup_handoff(&e, &user, &other);
+
+    e.ledger().with_mut(|l| l.sequence_number = 11);
+    client
+        .with_source_account(&user)
+        .prop_xfer(&key, &Identifier::Account(other.clone()), &20);
+    client.with_source_account(&other).acpt_xfer(&key);
+    assert_eq!(client.get_owner(&key), Identifier::Account(other));
+}
+
+/// Runs the same claim, update, handover and removal at `verbosity`, also
+/// consuming a nonce and proposing an admin, and returns the first topic of
+/// every event published.
+fn events_at(e: &Env, verbosity: Symbol) -> Vec<Symbol> {
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    client.with_source_account(&admin).set_verbos(&verbosity);
+
+    let key = bytes!(e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(other.clone()));
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(e);
+    client.with_source_account(&user).transfer(&key, &signer_id);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::zero(e), key.clone(), user_id.clone()),
+    );
+    client.use_sig(&sig, &BigInt::zero(e), &key, &user_id);
+    client
+        .with_source_account(&admin)
+        .set_admin(&Identifier::Account(other.clone()));
+    let other_key = bytes!(e, 0x6f74686572);
+    client
+        .with_source_account(&other)
+        .change_val(&other_key, &user_id);
+    client
+        .with_source_account(&other)
+        .release_to(&other_key, &user_id);
+    client.with_source_account(&user).remove_val(&other_key);
+
+    let mut names = Vec::new(e);
+    for event in e.events().all().iter() {
+        let (_, topics, _) = event.unwrap();
+        names.push_back(topics.get_unchecked(0).unwrap().try_into_val(e).unwrap());
+    }
+    names
+}
+
+#[test]
+fn test_verbosity_full() {
+    let e = Env::default();
+    assert_eq!(
+        events_at(&e, symbol!("full")),
+        Vec::from_array(
+            &e,
+            [
+                symbol!("verbosity"),
+                symbol!("set"),
+                symbol!("set"),
+                symbol!("transfer"),
+                symbol!("nonce"),
+                symbol!("set"),
+                symbol!("proposed"),
+                symbol!("set"),
+                symbol!("transfer"),
+                symbol!("released"),
+                symbol!("delete"),
+            ]
+        )
+    );
+}
+
+#[test]
+fn test_verbosity_minimal() {
+    let e = Env::default();
+    assert_eq!(
+        events_at(&e, symbol!("minimal")),
+        Vec::from_array(
+            &e,
+            [
+                symbol!("verbosity"),
+                symbol!("set"),
+                symbol!("transfer"),
+                symbol!("set"),
+                symbol!("transfer"),
+                symbol!("released"),
+                symbol!("delete"),
+            ]
+        )
+    );
+}
+
+#[test]
+fn test_verbosity_off() {
+    let e = Env::default();
+    assert_eq!(
+        events_at(&e, symbol!("off")),
+        Vec::from_array(&e, [symbol!("verbosity")])
+    );
+}
+
+#[test]
+fn test_topics() {
+    let e = Env::default();
+    assert_eq!(
+        events_at(&e, symbol!("full")),
+        Vec::from_array(
+            &e,
+            [
+                TOPIC_VERBOSITY,
+                TOPIC_SET,
+                TOPIC_SET,
+                TOPIC_TRANSFER,
+                TOPIC_NONCE,
+                TOPIC_SET,
+                TOPIC_PROPOSED,
+                TOPIC_SET,
+                TOPIC_TRANSFER,
+                TOPIC_RELEASED,
+                TOPIC_DELETE,
+            ]
+        )
+    );
+}
+
+#[test]
+fn test_transfer_event() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let other_id = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&user).transfer(&key, &other_id);
+
+    assert_eq!(
+        parse_events(&e, &contract_id).last(),
+        Some(&RegistryEvent::Transfer(TransferEvent {
+            key: key.clone(),
+            from: user_id,
+            to: other_id,
+            hash: client.entry_hash(&key),
+        }))
+    );
+}
+
+#[test]
+fn test_topics_held() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    client
+        .with_source_account(&user)
+        .hold_key(&bytes!(&e, 0x68656c6c6f));
+    assert_eq!(count_events(&e, TOPIC_HELD), 1);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(49))")]
+fn test_verbosity_invalid() {
+    let e = Env::default();
+    events_at(&e, symbol!("loud"));
+}
+
+/// Overwrites the index of an owner, to simulate drift from the owners of the
+/// keys.
+pub struct Drift;
+
+#[contractimpl]
+impl Drift {
+    pub fn drift(e: Env, owner: Identifier, keys: Vec<Bytes>) {
+        e.data().set(DataKey::OwnerCount(owner.clone()), keys.len());
+        e.data()
+            .set(DataKey::OwnerPage(PageKey { owner, page: 0 }), keys);
+    }
+}
+
+/// The registry with `Drift` mixed in.
+struct Drifted;
+
+impl ContractFunctionSet for Drifted {
+    fn call(&self, func: &Symbol, env: Env, args: &[RawVal]) -> Option<RawVal> {
+        Drift
+            .call(func, env.clone(), args)
+            .or_else(|| ExampleContract.call(func, env, args))
+    }
+}
+
+#[test]
+fn test_reindex() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let contract_id = e.register_contract(None, Drifted);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let user_id = Identifier::Account(user.clone());
+    let other_id = Identifier::Account(other.clone());
+    let (first, second, third) = (
+        bytes!(&e, 0x6f6e65),
+        bytes!(&e, 0x74776f),
+        bytes!(&e, 0x7468726565),
+    );
+    client
+        .with_source_account(&user)
+        .change_val(&first, &user_id);
+    client
+        .with_source_account(&other)
+        .change_val(&second, &other_id);
+    client
+        .with_source_account(&user)
+        .change_val(&third, &user_id);
+
+    let drift = DriftClient::new(&e, &contract_id);
+    drift.drift(&user_id, &Vec::from_array(&e, [second.clone()]));
+    drift.drift(&other_id, &Vec::new(&e));
+    assert_eq!(client.key_count(&user_id), 1);
+
+    client.with_source_account(&admin).reindex();
+    check_invariants(&e, &contract_id);
+    assert_eq!(client.key_count(&user_id), 2);
+    assert_eq!(
+        client.owned_page(&user_id, &0),
+        Vec::from_array(&e, [first.clone(), third.clone()])
+    );
+    assert_eq!(client.key_count(&other_id), 1);
+    assert_eq!(
+        client.owned_page(&other_id, &0),
+        Vec::from_array(&e, [second])
+    );
+
+    // The regenerated slots let keys leave the index again.
+    client.with_source_account(&user).remove_val(&first);
+    assert_eq!(
+        client.owned_page(&user_id, &0),
+        Vec::from_array(&e, [third])
+    );
+    check_invariants(&e, &contract_id);
+}
+
+#[test]
+#[should_panic(expected = "past the count of its owner")]
+fn test_invariants_drift() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, Drifted);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let user_id = Identifier::Account(user.clone());
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x6f6e65), &user_id);
+    check_invariants(&e, &contract_id);
+
+    DriftClient::new(&e, &contract_id).drift(&user_id, &Vec::new(&e));
+    check_invariants(&e, &contract_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_reindex_admin_only() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin));
+
+    client
+        .with_source_account(&e.accounts().generate())
+        .reindex();
+}
+
+#[test]
+fn test_sig_batch() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let user = e.accounts().generate();
+    let keys = Vec::from_array(&e, [bytes!(&e, 0x6f6e65), bytes!(&e, 0x74776f)]);
+    for key in keys.iter() {
+        let key = key.unwrap();
+        client
+            .with_source_account(&user)
+            .change_val(&key, &Identifier::Account(user.clone()));
+        client.with_source_account(&user).transfer(&key, &signer_id);
+    }
+
+    let values = Vec::from_array(
+        &e,
+        [
+            Identifier::Account(e.accounts().generate()),
+            Identifier::Account(e.accounts().generate()),
+        ],
+    );
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("sig_batch"),
+        (BigInt::zero(&e), keys.clone(), values.clone()),
+    );
+    client.sig_batch(&sig, &keys, &values, &BigInt::zero(&e));
+
+    assert_eq!(
+        client.get(&keys.get_unchecked(0).unwrap()),
+        values.get_unchecked(0).unwrap()
+    );
+    assert_eq!(
+        client.get(&keys.get_unchecked(1).unwrap()),
+        values.get_unchecked(1).unwrap()
+    );
+    assert_eq!(client.nonce(&signer_id), BigInt::from_u32(&e, 1));
+    check_invariants(&e, &contract_id);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_sig_batch_not_owned() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let user = e.accounts().generate();
+    let user_id = Identifier::Account(user.clone());
+    let keys = Vec::from_array(&e, [bytes!(&e, 0x6f6e65), bytes!(&e, 0x74776f)]);
+    client
+        .with_source_account(&user)
+        .change_val(&keys.get_unchecked(0).unwrap(), &user_id);
+    client
+        .with_source_account(&user)
+        .transfer(&keys.get_unchecked(0).unwrap(), &signer_id);
+    client
+        .with_source_account(&user)
+        .change_val(&keys.get_unchecked(1).unwrap(), &user_id);
+
+    let values = Vec::from_array(&e, [signer_id.clone(), signer_id]);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("sig_batch"),
+        (BigInt::zero(&e), keys.clone(), values.clone()),
+    );
+    client.sig_batch(&sig, &keys, &values, &BigInt::zero(&e));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_sig_batch_replayed() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let keys = Vec::from_array(&e, [bytes!(&e, 0x6f6e65), bytes!(&e, 0x74776f)]);
+    let values = Vec::from_array(&e, [signer_id.clone(), signer_id]);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("sig_batch"),
+        (BigInt::zero(&e), keys.clone(), values.clone()),
+    );
+    client.sig_batch(&sig, &keys, &values, &BigInt::zero(&e));
+    client.sig_batch(&sig, &keys, &values, &BigInt::zero(&e));
+}
+
+#[test]
+fn test_footprint() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    let count = |category: Symbol| client.footprint(&key).get_unchecked(category).unwrap();
+    assert_eq!(count(symbol!("owner")), 0);
+
+    let value = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(count(symbol!("owner")), 6);
+    assert_eq!(count(symbol!("metadata")), 0);
+    assert_eq!(count(symbol!("history")), 2);
+
+    client
+        .with_source_account(&user)
+        .set_text(&key, &symbol!("url"), &bytes!(&e, 0x75726c));
+    client
+        .with_source_account(&user)
+        .set_sealed(&key, &bytes!(&e, 0x626c6f62));
+    assert_eq!(count(symbol!("metadata")), 1);
+    assert_eq!(count(symbol!("blob")), 1);
+
+    let other = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).grant(
+        &key,
+        &other,
+        &Vec::from_array(&e, [Permission::Metadata]),
+    );
+    client.with_source_account(&user).approve_to(&key, &other);
+    assert_eq!(count(symbol!("approvals")), 2);
+
+    client.with_source_account(&user).purge_aux(&key);
+    assert_eq!(count(symbol!("metadata")), 0);
+    // Only the owner history stays.
+    assert_eq!(count(symbol!("history")), 1);
+    assert_eq!(count(symbol!("blob")), 0);
+    assert_eq!(count(symbol!("approvals")), 2);
+    assert_eq!(count(symbol!("owner")), 6);
+    assert_eq!(client.get(&key), value);
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_purge_aux_owner_only() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(&e.accounts().generate())
+        .purge_aux(&key);
+}
+
+#[test]
+fn test_get_all() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let user_id = Identifier::Account(user.clone());
+    let other_id = Identifier::Account(other.clone());
+    let (first, second, third) = (
+        bytes!(&e, 0x6f6e65),
+        bytes!(&e, 0x74776f),
+        bytes!(&e, 0x7468726565),
+    );
+    client
+        .with_source_account(&other)
+        .change_val(&second, &user_id);
+    client
+        .with_source_account(&user)
+        .change_val(&first, &other_id);
+    client
+        .with_source_account(&user)
+        .change_val(&third, &user_id);
+    client
+        .with_source_account(&user)
+        .hold_key(&bytes!(&e, 0x68656c64));
+    client.with_source_account(&user).remove_val(&third);
+
+    let pairs = client.get_all();
+    assert_eq!(pairs.len(), 2);
+    assert!(pairs.contains(&(first.clone(), other_id.clone())));
+    assert!(pairs.contains(&(second.clone(), user_id)));
+
+    client.with_source_account(&other).set_priv(&second, &true);
+    assert_eq!(
+        client.with_source_account(&user).get_all(),
+        Vec::from_array(&e, [(first.clone(), other_id.clone())])
+    );
+    assert_eq!(client.with_source_account(&other).get_all().len(), 2);
+    check_invariants(&e, &contract_id);
+}
+
+#[test]
+fn test_owner_at() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let mut owners = Vec::new(&e);
+    for i in 0..5 {
+        let owner = e.accounts().generate();
+        let owner_id = Identifier::Account(owner.clone());
+        if i == 0 {
+            client
+                .with_source_account(&owner)
+                .change_val(&key, &owner_id);
+        } else {
+            let previous = owners.get_unchecked(i - 1).unwrap();
+            client
+                .with_source_account(&previous)
+                .transfer(&key, &owner_id);
+        }
+        owners.push_back(owner);
+    }
+    assert_eq!(client.get_fresh(&key).unwrap().1, 5);
+
+    let owner_id = |i: u32| Identifier::Account(owners.get_unchecked(i).unwrap());
+    assert_eq!(client.try_owner_at(&key, &5), Ok(Ok(owner_id(4))));
+    assert_eq!(client.try_owner_at(&key, &4), Ok(Ok(owner_id(3))));
+    assert_eq!(client.try_owner_at(&key, &2), Ok(Ok(owner_id(1))));
+    assert_eq!(
+        client.try_owner_at(&key, &(5 - OWNER_HISTORY)),
+        Err(Ok(Error::NoHistory))
+    );
+    assert_eq!(client.try_owner_at(&key, &6), Err(Ok(Error::KeyNotFound)));
+
+    // The history outlives the removal of the key.
+    client
+        .with_source_account(&owners.get_unchecked(4).unwrap())
+        .remove_val(&key);
+    assert_eq!(client.try_owner_at(&key, &5), Ok(Ok(owner_id(4))));
+    check_invariants(&e, &contract_id);
+}
+
+#[test]
+fn test_owner_at_rebind() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::zero(&e), key.clone(), signer_id.clone()),
+    );
+    client.use_sig(&sig, &BigInt::zero(&e), &key, &signer_id);
+    let version = client.get_fresh(&key).unwrap().1;
+
+    let account_id = Identifier::Account(e.accounts().generate());
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("rebind"),
+        (client.nonce(&signer_id), key.clone(), account_id.clone()),
+    );
+    client.rebind(&sig, &key, &account_id);
+
+    assert_eq!(client.try_owner_at(&key, &version), Ok(Ok(account_id)));
+    check_invariants(&e, &contract_id);
+}
+
+#[test]
+fn test_same_id() {
+    let e = Env::default();
+
+    let account = e.accounts().generate();
+    let bytes = BytesN::from_array(&e, &[7; 32]);
+    let ids = [
+        Identifier::Account(account.clone()),
+        Identifier::Ed25519(bytes.clone()),
+        Identifier::Contract(bytes.clone()),
+    ];
+    let others = [
+        Identifier::Account(e.accounts().generate()),
+        Identifier::Ed25519(BytesN::from_array(&e, &[8; 32])),
+        Identifier::Contract(BytesN::from_array(&e, &[8; 32])),
+    ];
+
+    for (i, a) in ids.iter().enumerate() {
+        assert!(same_id(a, &a.clone()));
+        for (j, b) in ids.iter().enumerate() {
+            assert_eq!(same_id(a, b), i == j);
+        }
+        for b in others.iter() {
+            assert!(!same_id(a, b));
+        }
+    }
+}
+
+/// Registers the contract with a key of `user` and one of `other`, the first
+/// proposed for a swap with the second, returning both keys.
+fn setup_swap(
+    e: &Env,
+    user: &AccountId,
+    other: &AccountId,
+) -> (ExampleContractClient, Bytes, Bytes) {
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+
+    let (mine, theirs) = (bytes!(e, 0x666f6f), bytes!(e, 0x626172));
+    client
+        .with_source_account(user)
+        .change_val(&mine, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(other)
+        .change_val(&theirs, &Identifier::Account(other.clone()));
+    client
+        .with_source_account(user)
+        .prop_swap(&mine, &theirs, &Identifier::Account(other.clone()));
+    (client, mine, theirs)
+}
+
+#[test]
+fn test_swap() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, mine, theirs) = setup_swap(&e, &user, &other);
+    let spender = Identifier::Account(e.accounts().generate());
+    client
+        .with_source_account(&other)
+        .approve_to(&theirs, &spender);
+
+    client.with_source_account(&other).acpt_swap(&mine, &theirs);
+    assert_eq!(client.get_owner(&mine), Identifier::Account(other.clone()));
+    assert_eq!(client.get_owner(&theirs), Identifier::Account(user.clone()));
+    assert_eq!(client.get(&mine), Identifier::Account(user.clone()));
+    assert_eq!(client.spender(&theirs), None);
+    assert_eq!(client.key_count(&Identifier::Account(user)), 1);
+    assert_eq!(client.key_count(&Identifier::Account(other)), 1);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(32))")]
+fn test_swap_transferred() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, mine, theirs) = setup_swap(&e, &user, &other);
+    client
+        .with_source_account(&user)
+        .transfer(&mine, &Identifier::Account(e.accounts().generate()));
+
+    client.with_source_account(&other).acpt_swap(&mine, &theirs);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(10))")]
+fn test_swap_expired() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, mine, theirs) = setup_swap(&e, &user, &other);
+
+    e.ledger().with_mut(|l| l.sequence_number = SWAP_TTL + 1);
+    client.with_source_account(&other).acpt_swap(&mine, &theirs);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(32))")]
+fn test_swap_dropped() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, mine, theirs) = setup_swap(&e, &user, &other);
+    client.with_source_account(&user).drop_swap(&mine);
+
+    client.with_source_account(&other).acpt_swap(&mine, &theirs);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_swap_other_key() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let (client, mine, _) = setup_swap(&e, &user, &other);
+    let key = bytes!(&e, 0x62617a);
+    client
+        .with_source_account(&other)
+        .change_val(&key, &Identifier::Account(other.clone()));
+
+    client.with_source_account(&other).acpt_swap(&mine, &key);
+}
+
+#[test]
+fn test_pause_key() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    client.with_source_account(&admin).pause_key(&key);
+    assert_eq!(client.get(&key), user_id);
+
+    // Other keys stay writable.
+    client
+        .with_source_account(&user)
+        .change_val(&bytes!(&e, 0x6f74686572), &user_id);
+
+    client.with_source_account(&admin).resume_key(&key);
+    let value = Identifier::Account(e.accounts().generate());
+    client.with_source_account(&user).change_val(&key, &value);
+    assert_eq!(client.get(&key), value);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(7))")]
+fn test_pause_key_write() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&admin).pause_key(&key);
+
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(admin));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(6))")]
+fn test_pause_key_admin_only() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin));
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client.with_source_account(&user).pause_key(&key);
+}
+
+/// Claims a key for an account with two signers of weight 1 and a medium
+/// threshold of 2, and returns a signature of the account setting the key to
+/// a fresh value with the first `signers` of them.
+fn account_sig(
+    e: &Env,
+    client: &ExampleContractClient,
+    contract_id: &BytesN<32>,
+    signers: usize,
+) -> (Bytes, Identifier, Signature) {
+    let account = e.accounts().generate();
+    let key = bytes!(e, 0x68656c6c6f);
+    let account_id = Identifier::Account(account.clone());
+    client
+        .with_source_account(&account)
+        .change_val(&key, &account_id);
+
+    // Invoking as the account resets it, so its signers are set afterwards.
+    let (first_id, first) = soroban_auth::testutils::ed25519::generate(e);
+    let (second_id, second) = soroban_auth::testutils::ed25519::generate(e);
+    for id in [first_id, second_id] {
+        let public_key = match id {
+            Identifier::Ed25519(public_key) => public_key,
+            _ => unreachable!(),
+        };
+        e.accounts().set_signer_weight(&account, &public_key, 1);
+    }
+    e.accounts().set_thresholds(&account, 1, 2, 2);
+
+    let value = Identifier::Account(e.accounts().generate());
+    let args = build_change_args(e, &BigInt::zero(e), &key, &value);
+    let sig = sign_account(
+        e,
+        &account,
+        &[first, second][..signers],
+        contract_id,
+        symbol!("change"),
+        args,
+    );
+    (key, value, sig)
+}
+
+#[test]
+fn test_use_sig_account() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let (key, value, sig) = account_sig(&e, &client, &contract_id, 2);
+    client.use_sig(&sig, &BigInt::zero(&e), &key, &value);
+    assert_eq!(client.get(&key), value);
+}
+
+#[test]
+#[should_panic(expected = "insufficient signing weight")]
+fn test_use_sig_account_threshold() {
+    let e = Env::default();
+
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let (key, value, sig) = account_sig(&e, &client, &contract_id, 1);
+    client.use_sig(&sig, &BigInt::zero(&e), &key, &value);
+}
+
+#[test]
+fn test_get_or_set() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let initial = Identifier::Account(e.accounts().generate());
+    assert_eq!(
+        client.with_source_account(&user).get_or_set(&key, &initial),
+        initial
+    );
+    assert_eq!(client.get_owner(&key), Identifier::Account(user));
+    let version = client.get_ver(&key);
+
+    assert_eq!(
+        client
+            .with_source_account(&other)
+            .get_or_set(&key, &Identifier::Account(other.clone())),
+        initial
+    );
+    assert_eq!(client.get(&key), initial);
+    assert_eq!(client.get_ver(&key), version);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(48))")]
+fn test_get_or_set_held() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let other = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.with_source_account(&user).hold_key(&key);
+    client
+        .with_source_account(&other)
+        .get_or_set(&key, &Identifier::Account(other.clone()));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(48))")]
+fn test_get_or_set_held_by_invoker() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client.with_source_account(&user).hold_key(&key);
+    client
+        .with_source_account(&user)
+        .get_or_set(&key, &Identifier::Account(user.clone()));
+}
+
+#[test]
+fn test_block() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_restrict(&e, &admin, &user);
+    let user_id = Identifier::Account(user.clone());
+
+    client.with_source_account(&admin).block(&user_id);
+    assert_eq!(client.restr_of(&user_id), symbol!("banned"));
+    // Keys of a blocked owner can still be read.
+    assert_eq!(client.get(&key), user_id);
+    assert_eq!(client.get_owner(&key), user_id);
+
+    client.with_source_account(&admin).unblock(&user_id);
+    assert_eq!(client.restr_of(&user_id), symbol!("none"));
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(e.accounts().generate()));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_block_write() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_restrict(&e, &admin, &user);
+
+    client
+        .with_source_account(&admin)
+        .block(&Identifier::Account(user.clone()));
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(e.accounts().generate()));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_block_claim() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, _) = setup_restrict(&e, &admin, &user);
+
+    client
+        .with_source_account(&admin)
+        .block(&Identifier::Account(user.clone()));
+    client
+        .with_source_account(&user)
+        .hold_key(&bytes!(&e, 0x6869));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_block_claim_ns() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, _) = setup_restrict(&e, &admin, &user);
+
+    let ns = bytes!(&e, 0x6161);
+    client.create_ns(&ns, &Identifier::Account(admin.clone()));
+    client
+        .with_source_account(&admin)
+        .block(&Identifier::Account(user.clone()));
+    client.with_source_account(&user).set_ns(
+        &ns,
+        &bytes!(&e, 0x6869),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_block_grantee() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let grantee = e.accounts().generate();
+    let grantee_id = Identifier::Account(grantee.clone());
+    let (client, key) = setup_restrict(&e, &admin, &user);
+
+    let mut perms = Vec::new(&e);
+    perms.push_back(Permission::Update);
+    client
+        .with_source_account(&user)
+        .grant(&key, &grantee_id, &perms);
+    client.with_source_account(&admin).block(&grantee_id);
+    client
+        .with_source_account(&grantee)
+        .push_val(&key, &grantee_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_block_transfer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let other = Identifier::Account(e.accounts().generate());
+    let (client, key) = setup_restrict(&e, &admin, &user);
+
+    client.with_source_account(&admin).block(&other);
+    client.with_source_account(&user).transfer(&key, &other);
+}
+
+#[test]
+fn test_block_admin_del() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_restrict(&e, &admin, &user);
+
+    client
+        .with_source_account(&admin)
+        .block(&Identifier::Account(user));
+    client.with_source_account(&admin).admin_del(&key);
+    assert_eq!(client.get_opt(&key), None);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_block_owner_transfer() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_restrict(&e, &admin, &user);
+
+    client
+        .with_source_account(&admin)
+        .block(&Identifier::Account(user.clone()));
+    client
+        .with_source_account(&user)
+        .transfer(&key, &Identifier::Account(e.accounts().generate()));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_block_signed() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    client.with_source_account(&admin).block(&signer_id);
+    let key = bytes!(&e, 0x68656c6c6f);
+    let value = Identifier::Account(e.accounts().generate());
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::zero(&e), key.clone(), value.clone()),
+    );
+    client.use_sig(&sig, &BigInt::zero(&e), &key, &value);
+}
+
+pub struct Holdings;
+
+#[contractimpl]
+impl Holdings {
+    pub fn set_bal(e: Env, id: Identifier, amount: BigInt) {
+        e.data().set(id, amount);
+    }
+
+    pub fn balance(e: Env, id: Identifier) -> BigInt {
+        e.data()
+            .get(id)
+            .unwrap_or_else(|| Ok(BigInt::zero(&e)))
+            .unwrap()
+    }
+}
+
+fn setup_minbal(e: &Env, user: &AccountId, balance: u32) -> ExampleContractClient {
+    let admin = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+
+    let token_id = e.register_contract(None, Holdings);
+    HoldingsClient::new(e, &token_id).set_bal(
+        &Identifier::Account(user.clone()),
+        &BigInt::from_u32(e, balance),
+    );
+    client
+        .with_source_account(&admin)
+        .set_minbal(&token_id, &BigInt::from_u32(e, 100));
+    client
+}
+
+#[test]
+fn test_minbal() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_minbal(&e, &user, 100);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    client.with_source_account(&user).change_val(&key, &user_id);
+    assert_eq!(client.get_owner(&key), user_id);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_minbal_insufficient() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let client = setup_minbal(&e, &user, 99);
+
+    client.with_source_account(&user).change_val(
+        &bytes!(&e, 0x68656c6c6f),
+        &Identifier::Account(user.clone()),
+    );
+}
+
+#[test]
+fn test_clear_hist() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let user_id = Identifier::Account(user.clone());
+    for _ in 0..3 {
+        client
+            .with_source_account(&user)
+            .change_val(&key, &Identifier::Account(e.accounts().generate()));
+    }
+    let value = client.get(&key);
+    let version = client.get_ver(&key);
+    assert_eq!(client.history(&key).len(), 2);
+
+    client.with_source_account(&user).clear_hist(&key);
+    assert!(client.history(&key).is_empty());
+    assert_eq!(client.get(&key), value);
+    assert_eq!(client.get_owner(&key), user_id);
+    assert_eq!(client.get_ver(&key), version);
+    assert_eq!(
+        parse_events(&e, &contract_id).last(),
+        Some(&RegistryEvent::HistClear(key))
+    );
+}
+
+#[test]
+#[should_panic(expected = "you are not allowed to change this value")]
+fn test_clear_hist_owner_only() {
+    let e = Env::default();
+
+    let user = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    client
+        .with_source_account(&user)
+        .change_val(&key, &Identifier::Account(user.clone()));
+    client
+        .with_source_account(&e.accounts().generate())
+        .clear_hist(&key);
+}
+
+pub struct Receiver;
+
+#[contractimpl]
+impl Receiver {
+    pub fn set_accept(e: Env, accept: bool) {
+        e.data().set(symbol!("accept"), accept);
+    }
+
+    pub fn can_recv(e: Env, _key: Bytes, _from: Identifier) -> bool {
+        e.data().get(symbol!("accept")).unwrap().unwrap()
+    }
+}
+
+fn setup_receiver(e: &Env, admin: &AccountId, user: &AccountId) -> (ExampleContractClient, Bytes) {
+    let (client, key) = setup_restrict(e, admin, user);
+    client.with_source_account(admin).recv_guard(&true);
+    (client, key)
+}
+
+#[test]
+fn test_recv_guard() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_receiver(&e, &admin, &user);
+
+    let receiver_id = e.register_contract(None, Receiver);
+    ReceiverClient::new(&e, &receiver_id).set_accept(&true);
+    let receiver = Identifier::Contract(receiver_id);
+    client.with_source_account(&user).transfer(&key, &receiver);
+    assert_eq!(client.get_owner(&key), receiver);
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_recv_guard_refused() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_receiver(&e, &admin, &user);
+
+    let receiver_id = e.register_contract(None, Receiver);
+    ReceiverClient::new(&e, &receiver_id).set_accept(&false);
+    client
+        .with_source_account(&user)
+        .transfer(&key, &Identifier::Contract(receiver_id));
+}
+
+#[test]
+#[should_panic(expected = "Status(ContractError(18))")]
+fn test_recv_guard_unaware() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_receiver(&e, &admin, &user);
+
+    let watcher_id = e.register_contract(None, Watcher);
+    client
+        .with_source_account(&user)
+        .transfer(&key, &Identifier::Contract(watcher_id));
+}
+
+#[test]
+fn test_recv_guard_off() {
+    let e = Env::default();
+
+    let admin = e.accounts().generate();
+    let user = e.accounts().generate();
+    let (client, key) = setup_restrict(&e, &admin, &user);
+
+    let watcher = Identifier::Contract(e.register_contract(None, Watcher));
+    client.with_source_account(&user).transfer(&key, &watcher);
+    assert_eq!(client.get_owner(&key), watcher);
+}
+
+#[test]
+fn test_sig_state() {
+    let e = Env::default();
+    e.ledger().with_mut(|l| l.sequence_number = 42);
+
+    let admin = e.accounts().generate();
+    let contract_id = e.register_contract(None, ExampleContract);
+    let client = ExampleContractClient::new(&e, &contract_id);
+    client.initialize(&Identifier::Account(admin.clone()));
+    let (signer_id, signer) = soroban_auth::testutils::ed25519::generate(&e);
+
+    let key = bytes!(&e, 0x68656c6c6f);
+    let sig = soroban_auth::testutils::ed25519::sign(
+        &e,
+        &signer,
+        &contract_id,
+        symbol!("change"),
+        (BigInt::zero(&e), key.clone(), signer_id.clone()),
+    );
+    client.use_sig(&sig, &BigInt::zero(&e), &key, &signer_id);
+    assert_eq!(
+        client.sig_state(&signer_id),
+        SignerState {
+            nonce: BigInt::from_u32(&e, 1),
+            blocked: false,
+            ledger: 42,
+        }
+    );
+
+    client.with_source_account(&admin).block(&signer_id);
+    assert!(client.sig_state(&signer_id).blocked);
+}
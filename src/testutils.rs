@@ -0,0 +1,270 @@
+//! Decoders for the events published by the registry, for tests asserting on
+//! them without spelling out topic and data tuples, and builders of the
+//! payloads its signatures cover.
+
+extern crate std;
+
+use crate::topics::{
+    TOPIC_ACCEPTED, TOPIC_ATTEST, TOPIC_DELETE, TOPIC_DISPUTE, TOPIC_ENDED, TOPIC_GUARDIAN,
+    TOPIC_HELD, TOPIC_HIST_CLEAR, TOPIC_HOOK_FAIL, TOPIC_NONCE, TOPIC_PROPOSED, TOPIC_REBOUND,
+    TOPIC_RECOVER, TOPIC_RECOVERED, TOPIC_RELEASED, TOPIC_RENOUNCED, TOPIC_RESOLVED, TOPIC_SET,
+    TOPIC_TRANSFER, TOPIC_VERBOSITY, TOPIC_VETOED,
+};
+use soroban_auth::{
+    testutils::ed25519, AccountSignatures, Identifier, Signature, SignaturePayload,
+};
+use soroban_sdk::{
+    testutils::{ed25519::Sign, Events},
+    AccountId, BigInt, Bytes, BytesN, Env, RawVal, Symbol, TryIntoVal,
+};
+use std::vec::Vec;
+
+/// A value written with the key, its owner, the new value, the new
+/// `entry_hash` of the key and whether its text records were replaced too.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetEvent {
+    pub key: Bytes,
+    pub owner: Identifier,
+    pub value: Identifier,
+    pub hash: BytesN<32>,
+    pub texts: bool,
+}
+
+/// A key removed with the value it held.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeleteEvent {
+    pub key: Bytes,
+    pub value: Identifier,
+}
+
+/// A key handed from its owner `from` to `to`, with the new `entry_hash` of
+/// the key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransferEvent {
+    pub key: Bytes,
+    pub from: Identifier,
+    pub to: Identifier,
+    pub hash: BytesN<32>,
+}
+
+/// A nonce consumed, with the new nonce of `id`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NonceEvent {
+    pub id: Identifier,
+    pub nonce: BigInt,
+}
+
+/// A key claimed with `hold_key` without a value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HeldEvent {
+    pub key: Bytes,
+    pub owner: Identifier,
+}
+
+/// A key handed to `successor` with `release_to`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReleasedEvent {
+    pub key: Bytes,
+    pub successor: Identifier,
+}
+
+/// A dispute over a key resolved with `outcome`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResolvedEvent {
+    pub key: Bytes,
+    pub outcome: Symbol,
+}
+
+/// A recovery of a key started, completing at `deadline`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecoverEvent {
+    pub key: Bytes,
+    pub new_owner: Identifier,
+    pub deadline: u32,
+}
+
+/// A guardian set for a key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GuardianEvent {
+    pub key: Bytes,
+    pub guardian: Identifier,
+}
+
+/// A recovery of a key completed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecoveredEvent {
+    pub key: Bytes,
+    pub new_owner: Identifier,
+}
+
+/// A key moved to another identity of its owner with `rebind`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReboundEvent {
+    pub key: Bytes,
+    pub old: Identifier,
+    pub new: Identifier,
+}
+
+/// A hook or subscriber of a key that trapped.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HookFailEvent {
+    pub key: Bytes,
+    pub hook: BytesN<32>,
+}
+
+/// An event published by the registry, named after its topic.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RegistryEvent {
+    Set(SetEvent),
+    Delete(DeleteEvent),
+    Transfer(TransferEvent),
+    Nonce(NonceEvent),
+    Held(HeldEvent),
+    Released(ReleasedEvent),
+    Dispute(Bytes),
+    Resolved(ResolvedEvent),
+    Recover(RecoverEvent),
+    Guardian(GuardianEvent),
+    Vetoed(Bytes),
+    Recovered(RecoveredEvent),
+    Rebound(ReboundEvent),
+    HookFail(HookFailEvent),
+    Attest(BytesN<32>),
+    Proposed(Identifier),
+    Accepted(Identifier),
+    Renounced,
+    Ended,
+    HistClear(Bytes),
+    Verbosity(Symbol),
+}
+
+/// Decodes the events published so far by the registry at `contract_id`, in
+/// order. Events of other contracts are skipped. Panics on an event the
+/// registry doesn't publish, so new events can't go without a decoder.
+pub fn parse_events(e: &Env, contract_id: &BytesN<32>) -> Vec<RegistryEvent> {
+    let mut events = Vec::new();
+    for event in e.events().all().iter() {
+        let (id, topics, data) = event.unwrap();
+        if id != *contract_id {
+            continue;
+        }
+
+        let name: Symbol = topics.get_unchecked(0).unwrap().try_into_val(e).unwrap();
+        let event = if name == TOPIC_SET {
+            let (key, owner, value, hash, texts) = data.try_into_val(e).unwrap();
+            RegistryEvent::Set(SetEvent {
+                key,
+                owner,
+                value,
+                hash,
+                texts,
+            })
+        } else if name == TOPIC_DELETE {
+            let (key, value) = data.try_into_val(e).unwrap();
+            RegistryEvent::Delete(DeleteEvent { key, value })
+        } else if name == TOPIC_TRANSFER {
+            let (key, from, to, hash) = data.try_into_val(e).unwrap();
+            RegistryEvent::Transfer(TransferEvent {
+                key,
+                from,
+                to,
+                hash,
+            })
+        } else if name == TOPIC_NONCE {
+            let (id, nonce) = data.try_into_val(e).unwrap();
+            RegistryEvent::Nonce(NonceEvent { id, nonce })
+        } else if name == TOPIC_HELD {
+            let (key, owner) = data.try_into_val(e).unwrap();
+            RegistryEvent::Held(HeldEvent { key, owner })
+        } else if name == TOPIC_RELEASED {
+            let (key, successor) = data.try_into_val(e).unwrap();
+            RegistryEvent::Released(ReleasedEvent { key, successor })
+        } else if name == TOPIC_DISPUTE {
+            RegistryEvent::Dispute(data.try_into_val(e).unwrap())
+        } else if name == TOPIC_RESOLVED {
+            let (key, outcome) = data.try_into_val(e).unwrap();
+            RegistryEvent::Resolved(ResolvedEvent { key, outcome })
+        } else if name == TOPIC_RECOVER {
+            let (key, new_owner, deadline) = data.try_into_val(e).unwrap();
+            RegistryEvent::Recover(RecoverEvent {
+                key,
+                new_owner,
+                deadline,
+            })
+        } else if name == TOPIC_GUARDIAN {
+            let (key, guardian) = data.try_into_val(e).unwrap();
+            RegistryEvent::Guardian(GuardianEvent { key, guardian })
+        } else if name == TOPIC_VETOED {
+            RegistryEvent::Vetoed(data.try_into_val(e).unwrap())
+        } else if name == TOPIC_RECOVERED {
+            let (key, new_owner) = data.try_into_val(e).unwrap();
+            RegistryEvent::Recovered(RecoveredEvent { key, new_owner })
+        } else if name == TOPIC_REBOUND {
+            let (key, old, new) = data.try_into_val(e).unwrap();
+            RegistryEvent::Rebound(ReboundEvent { key, old, new })
+        } else if name == TOPIC_HOOK_FAIL {
+            let (key, hook) = data.try_into_val(e).unwrap();
+            RegistryEvent::HookFail(HookFailEvent { key, hook })
+        } else if name == TOPIC_ATTEST {
+            RegistryEvent::Attest(data.try_into_val(e).unwrap())
+        } else if name == TOPIC_PROPOSED {
+            RegistryEvent::Proposed(data.try_into_val(e).unwrap())
+        } else if name == TOPIC_ACCEPTED {
+            RegistryEvent::Accepted(data.try_into_val(e).unwrap())
+        } else if name == TOPIC_RENOUNCED {
+            RegistryEvent::Renounced
+        } else if name == TOPIC_ENDED {
+            RegistryEvent::Ended
+        } else if name == TOPIC_HIST_CLEAR {
+            RegistryEvent::HistClear(data.try_into_val(e).unwrap())
+        } else if name == TOPIC_VERBOSITY {
+            RegistryEvent::Verbosity(data.try_into_val(e).unwrap())
+        } else {
+            panic!("unknown registry event {:?}", name)
+        };
+        events.push(event);
+    }
+    events
+}
+
+/// Returns the arguments a signature for `use_sig` setting `key` to `value`
+/// with `nonce` must cover, built by the same code the contract verifies the
+/// signature with, so off-chain signers can check their payloads against it.
+pub fn build_change_args(
+    e: &Env,
+    nonce: &BigInt,
+    key: &Bytes,
+    value: &Identifier,
+) -> soroban_sdk::Vec<RawVal> {
+    crate::change_args(e, nonce.clone(), key.clone(), value.clone())
+}
+
+/// Signs `name` over `args` within `contract_id` for `account` with each of
+/// `signers`, ordered by public key as account signatures must be. Whether
+/// their weights reach the threshold of the account is up to the test.
+pub fn sign_account(
+    e: &Env,
+    account: &AccountId,
+    signers: &[impl ed25519::Identifier + Sign<SignaturePayload, Signature = [u8; 64]>],
+    contract_id: &BytesN<32>,
+    name: Symbol,
+    args: soroban_sdk::Vec<RawVal>,
+) -> Signature {
+    let mut signed = Vec::new();
+    for signer in signers {
+        match ed25519::sign(e, signer, contract_id, name, args.clone()) {
+            Signature::Ed25519(sig) => signed.push(sig),
+            _ => unreachable!(),
+        }
+    }
+    signed.sort_by(|a, b| a.public_key.cmp(&b.public_key));
+
+    let mut signatures = soroban_sdk::Vec::new(e);
+    for sig in signed {
+        signatures.push_back(sig);
+    }
+    Signature::Account(AccountSignatures {
+        account_id: account.clone(),
+        signatures,
+    })
+}
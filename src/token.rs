@@ -0,0 +1,34 @@
+//! Client for the token contract used to escrow payments.
+
+use soroban_auth::{Identifier, Signature};
+use soroban_sdk::{contractclient, contracttype, BigInt, Bytes, Env};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype(export = false)]
+pub struct TokenMetadata {
+    pub name: Bytes,
+    pub symbol: Bytes,
+    pub decimals: u32,
+}
+
+#[contractclient(name = "Client")]
+pub trait Token {
+    fn init(env: Env, admin: Identifier, metadata: TokenMetadata);
+
+    fn approve(env: Env, from: Signature, nonce: BigInt, spender: Identifier, amount: BigInt);
+
+    fn balance(env: Env, id: Identifier) -> BigInt;
+
+    fn xfer(env: Env, from: Signature, nonce: BigInt, to: Identifier, amount: BigInt);
+
+    fn xfer_from(
+        env: Env,
+        spender: Signature,
+        nonce: BigInt,
+        from: Identifier,
+        to: Identifier,
+        amount: BigInt,
+    );
+
+    fn mint(env: Env, admin: Signature, nonce: BigInt, to: Identifier, amount: BigInt);
+}
@@ -0,0 +1,47 @@
+//! The first topic of every event published by the registry. Indexers can
+//! match events on these, the contract publishes them from nowhere else.
+
+use soroban_sdk::{symbol, Symbol};
+
+/// A value written, with the key as a second topic when it fits a symbol.
+pub const TOPIC_SET: Symbol = symbol!("set");
+/// A key removed with the value it held.
+pub const TOPIC_DELETE: Symbol = symbol!("delete");
+/// A key handed to a new owner.
+pub const TOPIC_TRANSFER: Symbol = symbol!("transfer");
+/// A nonce consumed.
+pub const TOPIC_NONCE: Symbol = symbol!("nonce");
+/// A key claimed without a value.
+pub const TOPIC_HELD: Symbol = symbol!("held");
+/// A key handed to its successor.
+pub const TOPIC_RELEASED: Symbol = symbol!("released");
+/// A key disputed.
+pub const TOPIC_DISPUTE: Symbol = symbol!("dispute");
+/// A dispute over a key resolved.
+pub const TOPIC_RESOLVED: Symbol = symbol!("resolved");
+/// A recovery of a key started.
+pub const TOPIC_RECOVER: Symbol = symbol!("recover");
+/// A guardian set for a key.
+pub const TOPIC_GUARDIAN: Symbol = symbol!("guardian");
+/// A recovery of a key vetoed.
+pub const TOPIC_VETOED: Symbol = symbol!("vetoed");
+/// A recovery of a key completed.
+pub const TOPIC_RECOVERED: Symbol = symbol!("recovered");
+/// A key moved to another identity of its owner.
+pub const TOPIC_REBOUND: Symbol = symbol!("rebound");
+/// A hook or subscriber of a key that trapped.
+pub const TOPIC_HOOK_FAIL: Symbol = symbol!("hook_fail");
+/// An attestation recorded.
+pub const TOPIC_ATTEST: Symbol = symbol!("attest");
+/// A new admin proposed.
+pub const TOPIC_PROPOSED: Symbol = symbol!("proposed");
+/// A proposed admin accepted.
+pub const TOPIC_ACCEPTED: Symbol = symbol!("accepted");
+/// The admin renounced.
+pub const TOPIC_RENOUNCED: Symbol = symbol!("renounced");
+/// The registry terminated.
+pub const TOPIC_ENDED: Symbol = symbol!("ended");
+/// The value history of a key cleared.
+pub const TOPIC_HIST_CLEAR: Symbol = symbol!("hist_clear");
+/// The event verbosity changed.
+pub const TOPIC_VERBOSITY: Symbol = symbol!("verbosity");